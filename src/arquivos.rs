@@ -0,0 +1,147 @@
+use crate::errors::descrever_erro_io;
+use crate::interpreter::Value;
+use std::cell::RefCell;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::rc::Rc;
+
+/// Arquivo aberto com `abrir()`, lido linha a linha até `fechar()`.
+#[derive(Debug)]
+pub struct Arquivo {
+    pub caminho: String,
+    leitor: Option<BufReader<File>>,
+}
+
+impl Arquivo {
+    pub fn esta_aberto(&self) -> bool {
+        self.leitor.is_some()
+    }
+
+    /// Lê a próxima linha sem a quebra final; `None` no fim do arquivo.
+    pub fn ler_linha(&mut self) -> Result<Option<String>, String> {
+        let caminho = &self.caminho;
+        let leitor = self
+            .leitor
+            .as_mut()
+            .ok_or_else(|| format!("Arquivo '{}' já foi fechado", caminho))?;
+
+        let mut linha = String::new();
+        let lidos = leitor
+            .read_line(&mut linha)
+            .map_err(|e| format!("Erro ao ler arquivo '{}': {}", caminho, descrever_erro_io(&e)))?;
+
+        if lidos == 0 {
+            return Ok(None);
+        }
+
+        let sem_quebra = linha.trim_end_matches(['\n', '\r']).len();
+        linha.truncate(sem_quebra);
+        Ok(Some(linha))
+    }
+
+    pub fn fechar(&mut self) {
+        self.leitor = None;
+    }
+}
+
+pub const NATIVAS: &[(&str, &[&str], &str)] = &[
+    ("ler_arquivo", &["caminho"], "Conteúdo inteiro de um arquivo de texto"),
+    ("escrever_arquivo", &["caminho", "conteudo"], "Grava o conteúdo no arquivo, substituindo o anterior"),
+    ("anexar_arquivo", &["caminho", "conteudo"], "Acrescenta o conteúdo ao fim do arquivo"),
+    ("existe_arquivo", &["caminho"], "Se o caminho existe"),
+    ("listar_diretorio", &["caminho"], "Array com os nomes das entradas do diretório"),
+    ("remover_arquivo", &["caminho"], "Apaga o arquivo"),
+    ("abrir", &["caminho"], "Abre um arquivo para leitura linha a linha"),
+    ("ler_linha", &["arquivo"], "Próxima linha de um arquivo aberto; nulo no fim"),
+    ("fechar", &["arquivo"], "Fecha um arquivo aberto"),
+];
+
+pub fn chamar(nome: &str, args: &[Value]) -> Option<Result<Value, String>> {
+    let resultado = match nome {
+        "ler_arquivo" => caminho_arg(nome, args, 1).and_then(|caminho| {
+            fs::read_to_string(&caminho)
+                .map(Value::Text)
+                .map_err(|e| erro_io("ler arquivo", &caminho, &e))
+        }),
+        "escrever_arquivo" => caminho_arg(nome, args, 2).and_then(|caminho| {
+            fs::write(&caminho, args[1].to_string_repr())
+                .map(|_| Value::Nil)
+                .map_err(|e| erro_io("escrever arquivo", &caminho, &e))
+        }),
+        "anexar_arquivo" => caminho_arg(nome, args, 2).and_then(|caminho| {
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&caminho)
+                .and_then(|mut f| f.write_all(args[1].to_string_repr().as_bytes()))
+                .map(|_| Value::Nil)
+                .map_err(|e| erro_io("anexar ao arquivo", &caminho, &e))
+        }),
+        "existe_arquivo" => {
+            caminho_arg(nome, args, 1).map(|caminho| Value::Bool(Path::new(&caminho).exists()))
+        }
+        "listar_diretorio" => caminho_arg(nome, args, 1).and_then(|caminho| listar_diretorio(&caminho)),
+        "remover_arquivo" => caminho_arg(nome, args, 1).and_then(|caminho| {
+            fs::remove_file(&caminho)
+                .map(|_| Value::Nil)
+                .map_err(|e| erro_io("remover arquivo", &caminho, &e))
+        }),
+        "abrir" => caminho_arg(nome, args, 1).and_then(|caminho| {
+            File::open(&caminho)
+                .map(|f| {
+                    Value::Arquivo(Rc::new(RefCell::new(Arquivo {
+                        caminho: caminho.clone(),
+                        leitor: Some(BufReader::new(f)),
+                    })))
+                })
+                .map_err(|e| erro_io("abrir arquivo", &caminho, &e))
+        }),
+        "ler_linha" => arquivo_arg(nome, args).and_then(|arq| {
+            let linha = arq.borrow_mut().ler_linha()?;
+            Ok(linha.map_or(Value::Nil, Value::Text))
+        }),
+        "fechar" => arquivo_arg(nome, args).map(|arq| {
+            arq.borrow_mut().fechar();
+            Value::Nil
+        }),
+        _ => return None,
+    };
+    Some(resultado)
+}
+
+fn erro_io(acao: &str, caminho: &str, e: &std::io::Error) -> String {
+    format!("Erro ao {} '{}': {}", acao, caminho, descrever_erro_io(e))
+}
+
+fn caminho_arg(nome: &str, args: &[Value], esperados: usize) -> Result<String, String> {
+    if args.len() != esperados {
+        return Err(format!("{}() espera {} argumento(s)", nome, esperados));
+    }
+    match &args[0] {
+        Value::Text(s) => Ok(s.clone()),
+        _ => Err(format!("{}() espera o caminho como texto", nome)),
+    }
+}
+
+fn arquivo_arg(nome: &str, args: &[Value]) -> Result<Rc<RefCell<Arquivo>>, String> {
+    match args {
+        [Value::Arquivo(arq)] => Ok(arq.clone()),
+        [_] => Err(format!("{}() espera um arquivo retornado por abrir()", nome)),
+        _ => Err(format!("{}() espera 1 argumento", nome)),
+    }
+}
+
+fn listar_diretorio(caminho: &str) -> Result<Value, String> {
+    let entradas = fs::read_dir(caminho).map_err(|e| erro_io("listar diretório", caminho, &e))?;
+
+    let mut nomes = Vec::new();
+    for entrada in entradas {
+        let entrada = entrada.map_err(|e| erro_io("listar diretório", caminho, &e))?;
+        nomes.push(entrada.file_name().to_string_lossy().into_owned());
+    }
+    nomes.sort();
+
+    let itens = nomes.into_iter().map(Value::Text).collect();
+    Ok(Value::Array(Rc::new(RefCell::new(itens))))
+}
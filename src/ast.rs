@@ -1,41 +1,69 @@
+/// Posição `(profundidade, posição)` de uma variável local, preenchida pelo
+/// resolver. `None` indica busca pelo nome (globais e nativas).
+pub type Resolucao = Option<(usize, usize)>;
+
 #[derive(Debug, Clone)]
 pub enum Expr {
     Numero(f64),
     Texto(String),
     Bool(bool),
     Nulo,
-    Var(String),
+    Var(String, Resolucao),
     Array(Vec<Expr>),
+    Dicionario(Vec<(Expr, Expr)>),
     Index(Box<Expr>, Box<Expr>),
-    Atribuir(String, Box<Expr>),
+    Atribuir(String, Box<Expr>, Resolucao),
     AtribuirIndex(Box<Expr>, Box<Expr>, Box<Expr>),
     Unario(UnarioOp, Box<Expr>),
     Binario(Box<Expr>, BinOp, Box<Expr>),
     Chamada(Box<Expr>, Vec<Expr>),
+    /// `inicio..fim` ou `inicio..=fim`; os limites podem ser omitidos em fatias
+    Intervalo(Option<Box<Expr>>, Option<Box<Expr>>, bool),
 }
 
+/// Os `usize` finais guardam a linha do código-fonte, usada nas mensagens de erro.
 #[derive(Debug, Clone)]
+#[allow(clippy::enum_variant_names)]
 pub enum Stmt {
-    ExprStmt(Expr),
-    Imprimir(Expr),
-    VarDecl(String, Expr),
+    ExprStmt(Expr, usize),
+    Imprimir(Expr, usize),
+    VarDecl(String, Expr, usize),
+    /// `constante NOME = valor;`
+    Constante(String, Expr, usize),
     Bloco(Vec<Stmt>),
-    If(Expr, Box<Stmt>, Option<Box<Stmt>>),
-    While(Expr, Box<Stmt>),
+    If(Expr, Box<Stmt>, Option<Box<Stmt>>, usize),
+    While(Expr, Box<Stmt>, usize),
     For(Option<Box<Stmt>>, Option<Expr>, Option<Expr>, Box<Stmt>),
+    /// `para cada (indice, item em iteravel)`; o índice é opcional
+    ParaCada(Option<String>, String, Expr, Box<Stmt>, usize),
     FuncDecl(String, Vec<String>, Vec<Stmt>),
-    Return(Option<Expr>),
+    Return(Option<Expr>, usize),
+    Tentar(Vec<Stmt>, Option<Captura>, Option<Vec<Stmt>>),
+    Lancar(Expr, usize),
+    /// `importar "caminho" como nome;`
+    Importar(String, String, usize),
+    /// `de "caminho" importar a, b;`
+    DeImportar(String, Vec<String>, usize),
+    /// `exportar a, b;` ou `exportar` antes de uma declaração
+    Exportar(Vec<String>, Option<Box<Stmt>>),
     Break,
     Continue,
 }
 
+/// Bloco `capturar (nome) { ... }` de um `tentar`.
 #[derive(Debug, Clone)]
+pub struct Captura {
+    pub nome: Option<String>,
+    pub corpo: Vec<Stmt>,
+}
+
+#[derive(Debug, Clone, Copy)]
 pub enum UnarioOp {
     Neg,
     Nao,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy)]
 pub enum BinOp {
     Add,
     Sub,
@@ -49,4 +77,61 @@ pub enum BinOp {
     Ge,
     And,
     Or,
-}
\ No newline at end of file
+}
+impl Stmt {
+    pub fn linha(&self) -> Option<usize> {
+        match self {
+            Stmt::ExprStmt(_, linha)
+            | Stmt::Imprimir(_, linha)
+            | Stmt::VarDecl(_, _, linha)
+            | Stmt::Constante(_, _, linha)
+            | Stmt::If(_, _, _, linha)
+            | Stmt::While(_, _, linha)
+            | Stmt::ParaCada(_, _, _, _, linha)
+            | Stmt::Return(_, linha)
+            | Stmt::Lancar(_, linha)
+            | Stmt::Importar(_, _, linha)
+            | Stmt::DeImportar(_, _, linha) => Some(*linha),
+            Stmt::Exportar(_, Some(decl)) => decl.linha(),
+            _ => None,
+        }
+    }
+
+    /// Se o comando declara algum nome no escopo em que está; um bloco sem
+    /// declarações não precisa de escopo próprio.
+    pub fn declara(&self) -> bool {
+        match self {
+            Stmt::VarDecl(..)
+            | Stmt::Constante(..)
+            | Stmt::FuncDecl(..)
+            | Stmt::Importar(..)
+            | Stmt::DeImportar(..)
+            | Stmt::Exportar(_, Some(_)) => true,
+            Stmt::If(_, entao, senao, _) => entao.declara() || senao.as_ref().is_some_and(|s| s.declara()),
+            Stmt::While(_, corpo, _) => corpo.declara(),
+            _ => false,
+        }
+    }
+
+    /// Nomes declarados diretamente no escopo do comando, com `verdadeiro`
+    /// para constantes. Desce em `se`/`enquanto` sem chaves, cujas declarações
+    /// pertencem ao escopo que os contém.
+    pub fn declaracoes<'a>(&'a self, saida: &mut Vec<(&'a str, bool)>) {
+        match self {
+            Stmt::VarDecl(nome, _, _) | Stmt::FuncDecl(nome, _, _) | Stmt::Importar(_, nome, _) => {
+                saida.push((nome, false))
+            }
+            Stmt::Constante(nome, _, _) => saida.push((nome, true)),
+            Stmt::DeImportar(_, nomes, _) => saida.extend(nomes.iter().map(|n| (n.as_str(), false))),
+            Stmt::Exportar(_, Some(decl)) => decl.declaracoes(saida),
+            Stmt::If(_, entao, senao, _) => {
+                entao.declaracoes(saida);
+                if let Some(senao) = senao {
+                    senao.declaracoes(saida);
+                }
+            }
+            Stmt::While(_, corpo, _) => corpo.declaracoes(saida),
+            _ => {}
+        }
+    }
+}
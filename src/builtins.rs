@@ -0,0 +1,200 @@
+use crate::arquivos;
+use crate::environment::EnvRef;
+use crate::interpreter::{Corpo, ErroExecucao, Function, Interpreter, Value};
+use crate::iterador::Intervalo;
+use crate::json;
+use std::cell::RefCell;
+use std::io::{self, BufRead};
+use std::rc::Rc;
+
+const NATIVAS: &[(&str, &[&str], &str)] = &[
+    ("imprimir", &[], "Escreve os valores na saída, separados por espaço"),
+    ("comprimento", &["x"], "Número de caracteres de um texto ou de elementos de um array, dicionário ou intervalo"),
+    ("maiuscula", &["x"], "Texto em letras maiúsculas"),
+    ("minuscula", &["x"], "Texto em letras minúsculas"),
+    ("ler", &["prompt"], "Lê uma linha da entrada, mostrando o prompt; nulo no fim da entrada"),
+    ("ler_numero", &["prompt"], "Lê um número da entrada (aceita vírgula decimal)"),
+    ("ler_linhas", &[], "Lê todas as linhas restantes da entrada como um array de textos"),
+    ("chaves", &["dicionario"], "Array com as chaves de um dicionário"),
+    ("intervalo", &["inicio", "fim", "passo"], "Intervalo de inicio até fim (exclusivo), com passo opcional"),
+    ("json_ler", &["texto"], "Converte um texto JSON em valor"),
+    ("json_escrever", &["valor", "identado"], "Converte um valor em texto JSON, identado se pedido"),
+    ("sair", &["codigo"], "Encerra o script com o código de saída dado (0 se omitido)"),
+];
+
+pub fn registrar(globals: &EnvRef) {
+    for (nome, params, _) in NATIVAS.iter().chain(arquivos::NATIVAS) {
+        let func = Function {
+            name: nome.to_string(),
+            params: params.iter().map(|p| p.to_string()).collect(),
+            corpo: Corpo::Nativa,
+        };
+        globals.borrow_mut().define(nome.to_string(), Value::Function(Rc::new(func)));
+    }
+}
+
+/// Nomes de todas as funções nativas.
+#[cfg(feature = "repl")]
+pub fn nomes() -> impl Iterator<Item = &'static str> {
+    NATIVAS.iter().chain(arquivos::NATIVAS).map(|(nome, _, _)| *nome)
+}
+
+/// Parâmetros e descrição da nativa `nome`, para a ajuda do REPL.
+#[cfg(feature = "repl")]
+pub fn ajuda(nome: &str) -> Option<(&'static [&'static str], &'static str)> {
+    NATIVAS
+        .iter()
+        .chain(arquivos::NATIVAS)
+        .find(|(n, _, _)| *n == nome)
+        .map(|(_, params, descricao)| (*params, *descricao))
+}
+
+pub fn chamar(interpreter: &mut Interpreter, nome: &str, args: &[Value]) -> Option<Result<Value, ErroExecucao>> {
+    let resultado = match nome {
+        "imprimir" => {
+            let textos: Result<Vec<String>, _> = args.iter().map(|v| interpreter.texto(v)).collect();
+            return Some(textos.and_then(|t| interpreter.escrever_linha(&t.join(" "))).map(|_| Value::Nil));
+        }
+        "comprimento" => {
+            if args.len() != 1 {
+                return Some(Err("comprimento() espera 1 argumento".into()));
+            }
+            match &args[0] {
+                Value::Text(s) => Ok(Value::Number(s.chars().count() as f64)),
+                Value::Array(arr) => Ok(Value::Number(arr.borrow().len() as f64)),
+                Value::Dicionario(dic) => Ok(Value::Number(dic.borrow().len() as f64)),
+                Value::Intervalo(intervalo) => match intervalo.tamanho() {
+                    Some(n) => Ok(Value::Number(n as f64)),
+                    None => Err("comprimento() de um intervalo sem fim".into()),
+                },
+                _ => Err("comprimento() espera texto, array, dicionário ou intervalo".into()),
+            }
+        }
+        "maiuscula" => {
+            if args.len() != 1 {
+                return Some(Err("maiuscula() espera 1 argumento".into()));
+            }
+            match &args[0] {
+                Value::Text(s) => Ok(Value::Text(s.to_uppercase())),
+                _ => Err("maiuscula() espera texto".into()),
+            }
+        }
+        "minuscula" => {
+            if args.len() != 1 {
+                return Some(Err("minuscula() espera 1 argumento".into()));
+            }
+            match &args[0] {
+                Value::Text(s) => Ok(Value::Text(s.to_lowercase())),
+                _ => Err("minuscula() espera texto".into()),
+            }
+        }
+        "ler" => return Some(ler(interpreter, "ler", args).map(|linha| linha.map_or(Value::Nil, Value::Text))),
+        "ler_numero" => return Some(ler_numero(interpreter, args)),
+        "ler_linhas" => ler_linhas(args),
+        "chaves" => match args {
+            [Value::Dicionario(dic)] => {
+                let chaves = dic.borrow().keys().cloned().map(Value::Text).collect();
+                Ok(Value::Array(Rc::new(RefCell::new(chaves))))
+            }
+            [_] => Err("chaves() espera um dicionário".into()),
+            _ => Err("chaves() espera 1 argumento".into()),
+        },
+        "intervalo" => match args {
+            [Value::Number(inicio), Value::Number(fim)] => {
+                Intervalo::novo(Some(*inicio), Some(*fim), false, 1.0).map(|i| Value::Intervalo(Rc::new(i))).map_err(|e| e.mensagem)
+            }
+            [Value::Number(inicio), Value::Number(fim), Value::Number(passo)] => {
+                Intervalo::novo(Some(*inicio), Some(*fim), false, *passo).map(|i| Value::Intervalo(Rc::new(i))).map_err(|e| e.mensagem)
+            }
+            _ => Err("intervalo() espera inicio, fim e passo opcional numéricos".into()),
+        },
+        "json_ler" => match args {
+            [Value::Text(texto)] => json::ler(texto),
+            [_] => Err("json_ler() espera texto".into()),
+            _ => Err("json_ler() espera 1 argumento".into()),
+        },
+        "json_escrever" => {
+            let identado = match args {
+                [_] => false,
+                [_, identado] => identado.is_truthy(),
+                _ => return Some(Err("json_escrever() espera 1 ou 2 argumentos".into())),
+            };
+            let mut orcamento = interpreter.consumo.orcamento(&interpreter.limites);
+            return Some(json::escrever(&args[0], identado, &mut orcamento).map(Value::Text));
+        }
+        _ if !interpreter.limites.acesso_sistema && (nome == "sair" || arquivos::NATIVAS.iter().any(|(n, _, _)| *n == nome)) => {
+            return Some(Err(ErroExecucao::novo(
+                "PermissaoNegada",
+                format!("A função '{}' está desativada neste ambiente", nome),
+            )));
+        }
+        "sair" => {
+            return Some(Err(match args {
+                [] => ErroExecucao::saida(0),
+                // O sistema só guarda 8 bits: `sair(256)` viraria sucesso
+                [Value::Number(codigo)] if codigo.fract() == 0.0 && (0.0..=255.0).contains(codigo) => {
+                    ErroExecucao::saida(*codigo as i32)
+                }
+                [_] => "sair() espera um código inteiro entre 0 e 255".into(),
+                _ => "sair() espera no máximo 1 argumento".into(),
+            }));
+        }
+        _ => arquivos::chamar(nome, args)?,
+    };
+    Some(resultado.map_err(ErroExecucao::from))
+}
+
+/// Mostra o prompt (se houver) e lê uma linha da entrada padrão.
+/// Retorna `None` quando a entrada chegou ao fim.
+fn ler(interpreter: &mut Interpreter, nome: &str, args: &[Value]) -> Result<Option<String>, ErroExecucao> {
+    let prompt = match args {
+        [] => interpreter.prompt_leitura.clone(),
+        [p] => Some(p.to_string_repr()),
+        _ => return Err(format!("{}() espera no máximo 1 argumento", nome).into()),
+    };
+
+    if let Some(p) = prompt {
+        interpreter.escrever(&p)?;
+    }
+
+    let mut linha = String::new();
+    let lidos = io::stdin()
+        .read_line(&mut linha)
+        .map_err(|e| format!("Erro ao ler entrada: {}", e))?;
+
+    if lidos == 0 {
+        return Ok(None);
+    }
+
+    let sem_quebra = linha.trim_end_matches(['\n', '\r']).len();
+    linha.truncate(sem_quebra);
+    Ok(Some(linha))
+}
+
+fn ler_numero(interpreter: &mut Interpreter, args: &[Value]) -> Result<Value, ErroExecucao> {
+    let linha = match ler(interpreter, "ler_numero", args)? {
+        Some(l) => l,
+        None => return Ok(Value::Nil),
+    };
+
+    let texto = linha.trim();
+    // Aceita vírgula decimal ("3,5"), comum no teclado brasileiro
+    texto
+        .replace(',', ".")
+        .parse::<f64>()
+        .map(Value::Number)
+        .map_err(|_| format!("ler_numero() esperava um número, mas recebeu '{}'", texto).into())
+}
+
+fn ler_linhas(args: &[Value]) -> Result<Value, String> {
+    if !args.is_empty() {
+        return Err("ler_linhas() não recebe argumentos".into());
+    }
+
+    let mut linhas = Vec::new();
+    for linha in io::stdin().lock().lines() {
+        let linha = linha.map_err(|e| format!("Erro ao ler entrada: {}", e))?;
+        linhas.push(Value::Text(linha));
+    }
+    Ok(Value::Array(Rc::new(RefCell::new(linhas))))
+}
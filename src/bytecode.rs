@@ -0,0 +1,141 @@
+use crate::ast::{BinOp, UnarioOp};
+use crate::interpreter::Value;
+use std::cell::Cell;
+use std::fmt;
+use std::rc::Rc;
+
+/// Instruções da `Vm`. Operandos `u32` são índices no `Prototipo`
+/// (constantes, nomes, posições locais, funções) ou endereços de salto.
+#[derive(Debug, Clone, Copy)]
+pub enum Op {
+    Constante(u32),
+    Nulo,
+    Pop,
+    Dup,
+    /// Conta um comando para os limites de passos e de tempo
+    Passo,
+
+    /// Lê a posição local; se ainda não foi declarada, segue para a próxima
+    /// instrução, que busca o mesmo nome num escopo mais externo.
+    /// Caso contrário empilha o valor e salta para o endereço dado.
+    LerLocal(u32, u32),
+    /// Escritas deixam o valor na pilha, a menos que o último operando
+    /// (`descartar`) seja verdadeiro, caso da atribuição usada como comando
+    EscreverLocal(u32, u32, bool),
+    LerCaptura(u32, u32),
+    EscreverCaptura(u32, u32, bool),
+    LerGlobal(u32),
+    EscreverGlobal(u32, bool),
+    /// Desempilha o valor inicial de uma declaração local
+    DefinirLocal(u32),
+    /// `DeclararGlobal(nome, constante)`
+    DeclararGlobal(u32, bool),
+    /// Zera as posições `[inicio, fim)` ao entrar num escopo: cada entrada
+    /// cria variáveis novas, como o ambiente novo do interpretador de árvore
+    LimparLocais(u32, u32),
+
+    Unario(UnarioOp),
+    Binario(BinOp),
+    /// Operador com uma constante à direita: `BinarioConstante(op, constante)`
+    BinarioConstante(BinOp, u32),
+    /// Comparação seguida de `SaltarSeFalso`: `CompararSaltar(op, alvo)`
+    CompararSaltar(BinOp, u32),
+    CompararConstanteSaltar(BinOp, u32, u32),
+    /// Formas das anteriores para uma variável local já inicializada:
+    /// `LocalBinarioConstante(op, posicao, constante)`
+    LocalBinarioConstante(BinOp, u32, u32),
+    CompararLocalConstanteSaltar(BinOp, u32, u32, u32),
+    /// `x = x op constante` como comando
+    AtualizarLocal(BinOp, u32, u32),
+    Array(u32),
+    Dicionario(u32),
+    /// Valida que o topo é texto, antes de avaliar o valor do par
+    ChaveDicionario,
+    Indexar,
+    AtribuirIndice,
+    /// `Intervalo(tem_inicio, tem_fim, inclusivo)`
+    Intervalo(bool, bool, bool),
+
+    Saltar(u32),
+    SaltarSeFalso(u32),
+    Chamar(u32),
+    /// `retornar f(...)` fora de blocos `tentar`: o quadro atual dá lugar ao
+    /// da função chamada. Com funções não compiladas age como `Chamar` e o
+    /// `Retornar` seguinte completa o retorno.
+    ChamarFinal(u32),
+    Funcao(u32),
+    Retornar,
+    Imprimir,
+    Lancar,
+
+    IterarInicio,
+    /// `IterarProximo(fim, posicao_indice, posicao_item)`; `SEM_POSICAO` quando
+    /// o laço não tem índice
+    IterarProximo(u32, u32, u32),
+    IterarFim,
+
+    TentarInicio(u32),
+    /// Fim normal do corpo ou do `capturar`: segue para o `finalmente`, se houver
+    TentarConcluir,
+    FimFinalmente,
+    /// `parar`/`continuar` que atravessa blocos `tentar`: `SaltarSaindo(blocos, alvo)`
+    SaltarSaindo(u32, u32),
+    /// `parar` (verdadeiro) ou `continuar` fora de um laço
+    ForaDeLaco(bool),
+
+    CarregarModulo(u32),
+    /// Lê o nome exportado do módulo no topo da pilha: `ExtrairExportado(caminho, nome)`
+    ExtrairExportado(u32, u32),
+    MarcarExportados(u32, u32),
+}
+
+pub const SEM_POSICAO: u32 = u32::MAX;
+
+#[derive(Debug, Clone)]
+pub struct InfoLocal {
+    pub nome: String,
+    pub constante: bool,
+}
+
+/// Variável capturada por uma função: uma posição local da função que a
+/// declara (`local`) ou uma captura dessa função.
+#[derive(Debug, Clone)]
+pub struct InfoCaptura {
+    pub local: bool,
+    pub indice: u32,
+    pub nome: String,
+    pub constante: bool,
+}
+
+/// Endereços de um bloco `tentar`.
+#[derive(Debug, Clone, Copy)]
+pub struct InfoTentativa {
+    pub captura: Option<u32>,
+    pub finalmente: Option<u32>,
+    pub fim: u32,
+}
+
+/// Código compilado de uma função (ou do programa principal).
+#[derive(Default)]
+pub struct Prototipo {
+    pub nome: String,
+    pub params: Vec<String>,
+    pub codigo: Vec<Op>,
+    /// Linha do código-fonte de cada instrução (0 quando não há)
+    pub linhas: Vec<usize>,
+    pub constantes: Vec<Value>,
+    pub nomes: Vec<String>,
+    /// Última posição de cada nome no ambiente global, para `LerGlobal`
+    /// evitar a busca por nome quando a variável não mudou de lugar
+    pub cache_globais: Vec<Cell<usize>>,
+    pub locais: Vec<InfoLocal>,
+    pub capturas: Vec<InfoCaptura>,
+    pub funcoes: Vec<Rc<Prototipo>>,
+    pub tentativas: Vec<InfoTentativa>,
+}
+
+impl fmt::Debug for Prototipo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<prototipo {} ({} instruções)>", self.nome, self.codigo.len())
+    }
+}
@@ -0,0 +1,711 @@
+use crate::ast::{BinOp, Expr, Stmt};
+use crate::bytecode::{InfoCaptura, InfoLocal, InfoTentativa, Op, Prototipo, SEM_POSICAO};
+use crate::interpreter::Value;
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// Onde uma variável pode estar, da declaração mais interna para a mais externa.
+#[derive(Clone, Copy)]
+enum Acesso {
+    Local(u32),
+    Captura(u32),
+}
+
+#[derive(Default)]
+struct Escopo {
+    locais: Vec<(String, u32)>,
+    /// Profundidade de `se`/laços sem chaves dentro deste escopo: uma
+    /// declaração ali pode não executar
+    condicional: usize,
+}
+
+struct Laco {
+    /// Blocos `tentar` abertos quando o laço começou
+    tentativas: usize,
+    parar: Vec<usize>,
+    continuar: Vec<usize>,
+}
+
+struct FuncaoEmCompilacao {
+    proto: Prototipo,
+    escopos: Vec<Escopo>,
+    lacos: Vec<Laco>,
+    tentativas: usize,
+    /// Posições locais que certamente já têm valor no ponto atual do código
+    inicializadas: Vec<bool>,
+}
+
+/// Traduz a árvore sintática para o bytecode da `Vm`.
+///
+/// Cada função tem uma posição fixa para cada variável local. As declarações
+/// de um escopo são reservadas na entrada dele, de modo que uma função pode
+/// usar uma variável declarada mais adiante no mesmo bloco, como acontece com
+/// os ambientes do interpretador de árvore. Uma leitura tenta as declarações
+/// visíveis com aquele nome da mais interna para a mais externa, pulando as
+/// que ainda não foram executadas, e termina nas globais.
+pub struct Compilador {
+    funcoes: Vec<FuncaoEmCompilacao>,
+    linha: usize,
+    /// Emite `Op::Passo` antes de cada comando, para os `Limites` de execução
+    contar_passos: bool,
+}
+
+impl Compilador {
+    pub fn compilar(stmts: &[Stmt], contar_passos: bool) -> Prototipo {
+        let mut compilador = Compilador { funcoes: Vec::new(), linha: 0, contar_passos };
+        compilador.iniciar_funcao("<principal>", &[]);
+        for stmt in stmts {
+            compilador.stmt(stmt);
+        }
+        compilador.emitir(Op::Nulo);
+        compilador.emitir(Op::Retornar);
+        compilador.concluir_funcao()
+    }
+
+    fn concluir_funcao(&mut self) -> Prototipo {
+        let mut proto = self.funcoes.pop().expect("função em compilação").proto;
+        proto.cache_globais = proto.nomes.iter().map(|_| Cell::new(usize::MAX)).collect();
+        proto
+    }
+
+    fn atual(&mut self) -> &mut FuncaoEmCompilacao {
+        self.funcoes.last_mut().expect("sempre há uma função em compilação")
+    }
+
+    fn iniciar_funcao(&mut self, nome: &str, params: &[String]) {
+        let proto = Prototipo {
+            nome: nome.to_string(),
+            params: params.to_vec(),
+            ..Default::default()
+        };
+        self.funcoes.push(FuncaoEmCompilacao {
+            proto,
+            escopos: Vec::new(),
+            lacos: Vec::new(),
+            tentativas: 0,
+            inicializadas: Vec::new(),
+        });
+    }
+
+    fn emitir(&mut self, op: Op) -> usize {
+        let linha = self.linha;
+        let proto = &mut self.atual().proto;
+        proto.codigo.push(op);
+        proto.linhas.push(linha);
+        proto.codigo.len() - 1
+    }
+
+    fn posicao(&mut self) -> u32 {
+        self.atual().proto.codigo.len() as u32
+    }
+
+    /// Completa o endereço de salto da instrução em `pos`.
+    fn corrigir(&mut self, pos: usize, alvo: u32) {
+        let op = &mut self.atual().proto.codigo[pos];
+        match op {
+            Op::LerLocal(_, a)
+            | Op::EscreverLocal(_, a, _)
+            | Op::LerCaptura(_, a)
+            | Op::EscreverCaptura(_, a, _)
+            | Op::Saltar(a)
+            | Op::SaltarSeFalso(a)
+            | Op::CompararSaltar(_, a)
+            | Op::CompararConstanteSaltar(_, _, a)
+            | Op::CompararLocalConstanteSaltar(_, _, _, a)
+            | Op::IterarProximo(a, _, _)
+            | Op::SaltarSaindo(_, a) => *a = alvo,
+            _ => unreachable!("instrução sem endereço de salto: {:?}", op),
+        }
+    }
+
+    fn constante(&mut self, valor: Value) -> u32 {
+        let proto = &mut self.atual().proto;
+        proto.constantes.push(valor);
+        proto.constantes.len() as u32 - 1
+    }
+
+    fn nome(&mut self, nome: &str) -> u32 {
+        let proto = &mut self.atual().proto;
+        if let Some(i) = proto.nomes.iter().position(|n| n == nome) {
+            return i as u32;
+        }
+        proto.nomes.push(nome.to_string());
+        proto.nomes.len() as u32 - 1
+    }
+
+    // ---- escopos e variáveis ----
+
+    /// Reserva uma posição local nova no escopo mais interno.
+    fn reservar(&mut self, nome: &str, constante: bool) -> u32 {
+        let f = self.atual();
+        if let Some(&(_, pos)) = f.escopos.last().and_then(|e| e.locais.iter().find(|(n, _)| n == nome)) {
+            return pos;
+        }
+        let pos = f.proto.locais.len() as u32;
+        f.proto.locais.push(InfoLocal { nome: nome.to_string(), constante });
+        f.inicializadas.push(false);
+        if let Some(escopo) = f.escopos.last_mut() {
+            escopo.locais.push((nome.to_string(), pos));
+        }
+        pos
+    }
+
+    /// Abre um escopo com os nomes dados e as declarações de `stmts`, e emite
+    /// a limpeza das posições reservadas. Devolve o intervalo reservado.
+    fn abrir_escopo(&mut self, nomes: &[&str], stmts: &[Stmt]) -> (u32, u32) {
+        self.atual().escopos.push(Escopo::default());
+        let inicio = self.atual().proto.locais.len() as u32;
+        for nome in nomes {
+            self.reservar(nome, false);
+        }
+        let mut decls = Vec::new();
+        for stmt in stmts {
+            stmt.declaracoes(&mut decls);
+        }
+        for (nome, constante) in decls {
+            self.reservar(nome, constante);
+        }
+        let fim = self.atual().proto.locais.len() as u32;
+        (inicio, fim)
+    }
+
+    fn abrir_bloco(&mut self, stmts: &[Stmt]) {
+        let (inicio, fim) = self.abrir_escopo(&[], stmts);
+        if fim > inicio {
+            self.emitir(Op::LimparLocais(inicio, fim));
+        }
+    }
+
+    fn fechar_escopo(&mut self) {
+        self.atual().escopos.pop();
+    }
+
+    fn inicializar(&mut self, pos: u32) {
+        self.atual().inicializadas[pos as usize] = true;
+    }
+
+    /// Comando que pode não executar, como o corpo de um `se` ou de um laço.
+    fn ramo(&mut self, stmt: &Stmt) {
+        if let Some(escopo) = self.atual().escopos.last_mut() {
+            escopo.condicional += 1;
+        }
+        self.stmt(stmt);
+        if let Some(escopo) = self.atual().escopos.last_mut() {
+            escopo.condicional -= 1;
+        }
+    }
+
+    /// Posição de `nome` quando a declaração visível é local à função atual
+    /// e já foi executada em todo caminho até aqui: a leitura dispensa a
+    /// busca nos escopos externos.
+    fn local_inicializado(&mut self, nome: &str) -> Option<u32> {
+        let f = self.atual();
+        let pos = f.escopos.iter().rev().find_map(|e| e.locais.iter().find(|(n, _)| n == nome).map(|&(_, p)| p))?;
+        f.inicializadas[pos as usize].then_some(pos)
+    }
+
+    fn captura(&mut self, nivel: usize, info: InfoCaptura) -> u32 {
+        let capturas = &mut self.funcoes[nivel].proto.capturas;
+        if let Some(i) = capturas.iter().position(|c| c.local == info.local && c.indice == info.indice) {
+            return i as u32;
+        }
+        capturas.push(info);
+        capturas.len() as u32 - 1
+    }
+
+    /// Todas as declarações visíveis de `nome` a partir da função `nivel`,
+    /// da mais interna para a mais externa (sem contar as globais).
+    fn acessos(&mut self, nivel: usize, nome: &str) -> Vec<Acesso> {
+        let mut acessos: Vec<Acesso> = self.funcoes[nivel]
+            .escopos
+            .iter()
+            .rev()
+            .filter_map(|e| e.locais.iter().find(|(n, _)| n == nome).map(|&(_, pos)| Acesso::Local(pos)))
+            .collect();
+        if nivel > 0 {
+            for externo in self.acessos(nivel - 1, nome) {
+                let (local, indice) = match externo {
+                    Acesso::Local(pos) => (true, pos),
+                    Acesso::Captura(i) => (false, i),
+                };
+                let constante = match externo {
+                    Acesso::Local(pos) => self.funcoes[nivel - 1].proto.locais[pos as usize].constante,
+                    Acesso::Captura(i) => self.funcoes[nivel - 1].proto.capturas[i as usize].constante,
+                };
+                let info = InfoCaptura { local, indice, nome: nome.to_string(), constante };
+                acessos.push(Acesso::Captura(self.captura(nivel, info)));
+            }
+        }
+        acessos
+    }
+
+    /// Lê `nome` ou, com `escrita`, atribui a ele o valor no topo da pilha
+    /// (que sai da pilha se `descartar`).
+    fn acessar(&mut self, nome: &str, escrita: bool, descartar: bool) {
+        if let Some(pos) = self.local_inicializado(nome) {
+            let op = if escrita { Op::EscreverLocal(pos, 0, descartar) } else { Op::LerLocal(pos, 0) };
+            let salto = self.emitir(op);
+            let fim = self.posicao();
+            self.corrigir(salto, fim);
+            return;
+        }
+        let nivel = self.funcoes.len() - 1;
+        let mut saltos = Vec::new();
+        for acesso in self.acessos(nivel, nome) {
+            let op = match (acesso, escrita) {
+                (Acesso::Local(pos), false) => Op::LerLocal(pos, 0),
+                (Acesso::Local(pos), true) => Op::EscreverLocal(pos, 0, descartar),
+                (Acesso::Captura(i), false) => Op::LerCaptura(i, 0),
+                (Acesso::Captura(i), true) => Op::EscreverCaptura(i, 0, descartar),
+            };
+            saltos.push(self.emitir(op));
+        }
+        let indice = self.nome(nome);
+        self.emitir(if escrita { Op::EscreverGlobal(indice, descartar) } else { Op::LerGlobal(indice) });
+        let fim = self.posicao();
+        for pos in saltos {
+            self.corrigir(pos, fim);
+        }
+    }
+
+    /// Guarda o valor no topo da pilha na variável declarada.
+    fn definir(&mut self, nome: &str, constante: bool) {
+        if self.funcoes.len() == 1 && self.atual().escopos.is_empty() {
+            let indice = self.nome(nome);
+            self.emitir(Op::DeclararGlobal(indice, constante));
+        } else {
+            let pos = self.reservar(nome, constante);
+            self.emitir(Op::DefinirLocal(pos));
+            if self.atual().escopos.last().is_some_and(|e| e.condicional == 0) {
+                self.inicializar(pos);
+            }
+        }
+    }
+
+    // ---- comandos ----
+
+    fn bloco(&mut self, stmts: &[Stmt]) {
+        self.abrir_bloco(stmts);
+        for stmt in stmts {
+            self.stmt(stmt);
+        }
+        self.fechar_escopo();
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        let linha_anterior = self.linha;
+        if let Some(linha) = stmt.linha() {
+            self.linha = linha;
+        }
+        if self.contar_passos {
+            self.emitir(Op::Passo);
+        }
+        self.compilar_stmt(stmt);
+        self.linha = linha_anterior;
+    }
+
+    fn compilar_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::ExprStmt(e, _) => self.expr_descartada(e),
+            Stmt::Imprimir(e, _) => {
+                self.expr(e);
+                self.emitir(Op::Imprimir);
+            }
+            Stmt::VarDecl(nome, init, _) => {
+                self.expr(init);
+                self.definir(nome, false);
+            }
+            Stmt::Constante(nome, init, _) => {
+                self.expr(init);
+                self.definir(nome, true);
+            }
+            Stmt::Bloco(stmts) => self.bloco(stmts),
+            Stmt::If(cond, entao, senao, _) => {
+                let salto_senao = self.condicao(cond);
+                self.ramo(entao);
+                match senao {
+                    Some(senao) => {
+                        let salto_fim = self.emitir(Op::Saltar(0));
+                        let pos = self.posicao();
+                        self.corrigir(salto_senao, pos);
+                        self.ramo(senao);
+                        let fim = self.posicao();
+                        self.corrigir(salto_fim, fim);
+                    }
+                    None => {
+                        let fim = self.posicao();
+                        self.corrigir(salto_senao, fim);
+                    }
+                }
+            }
+            Stmt::While(cond, corpo, _) => {
+                let inicio = self.posicao();
+                let salto_fim = self.condicao(cond);
+                self.abrir_laco();
+                self.ramo(corpo);
+                self.emitir(Op::Saltar(inicio));
+                let fim = self.posicao();
+                self.corrigir(salto_fim, fim);
+                self.fechar_laco(inicio, fim);
+            }
+            Stmt::For(init, cond, incr, corpo) => {
+                let init_stmts: Vec<Stmt> = init.iter().map(|s| (**s).clone()).collect();
+                self.abrir_bloco(&init_stmts);
+                if let Some(init) = init {
+                    self.stmt(init);
+                }
+                let inicio = self.posicao();
+                let salto_fim = cond.as_ref().map(|cond| self.condicao(cond));
+                self.abrir_laco();
+                self.ramo(corpo);
+                let continuar = self.posicao();
+                if let Some(incr) = incr {
+                    self.expr_descartada(incr);
+                }
+                self.emitir(Op::Saltar(inicio));
+                let fim = self.posicao();
+                if let Some(salto) = salto_fim {
+                    self.corrigir(salto, fim);
+                }
+                self.fechar_laco(continuar, fim);
+                self.fechar_escopo();
+            }
+            Stmt::ParaCada(indice, item, iteravel, corpo, _) => {
+                self.expr(iteravel);
+                self.emitir(Op::IterarInicio);
+
+                let mut nomes = Vec::new();
+                if let Some(indice) = indice {
+                    nomes.push(indice.as_str());
+                }
+                nomes.push(item.as_str());
+                let (inicio_locais, fim_locais) = self.abrir_escopo(&nomes, std::slice::from_ref(corpo));
+                let pos_item = inicio_locais + nomes.len() as u32 - 1;
+                let pos_indice = if indice.is_some() { inicio_locais } else { SEM_POSICAO };
+                for pos in inicio_locais..=pos_item {
+                    self.inicializar(pos);
+                }
+
+                // Cada volta tem seu próprio escopo
+                let inicio = self.posicao();
+                self.emitir(Op::LimparLocais(inicio_locais, fim_locais));
+                let proximo = self.emitir(Op::IterarProximo(0, pos_indice, pos_item));
+                self.abrir_laco();
+                self.ramo(corpo);
+                self.emitir(Op::Saltar(inicio));
+                let fim = self.posicao();
+                self.corrigir(proximo, fim);
+                self.emitir(Op::IterarFim);
+                self.fechar_laco(inicio, fim);
+                self.fechar_escopo();
+            }
+            Stmt::FuncDecl(nome, params, corpo) => {
+                let indice = self.funcao(nome, params, corpo);
+                self.emitir(Op::Funcao(indice));
+                self.definir(nome, false);
+            }
+            Stmt::Return(valor, _) => {
+                match valor {
+                    Some(Expr::Chamada(funcao, args)) if self.atual().tentativas == 0 => {
+                        self.expr(funcao);
+                        for arg in args {
+                            self.expr(arg);
+                        }
+                        self.emitir(Op::ChamarFinal(args.len() as u32));
+                    }
+                    Some(valor) => self.expr(valor),
+                    None => {
+                        self.emitir(Op::Nulo);
+                    }
+                }
+                self.emitir(Op::Retornar);
+            }
+            Stmt::Tentar(corpo, captura, finalmente) => {
+                let indice = {
+                    let tentativas = &mut self.atual().proto.tentativas;
+                    tentativas.push(InfoTentativa { captura: None, finalmente: None, fim: 0 });
+                    tentativas.len() - 1
+                };
+                self.emitir(Op::TentarInicio(indice as u32));
+                self.atual().tentativas += 1;
+
+                self.bloco(corpo);
+                self.emitir(Op::TentarConcluir);
+
+                let mut info = InfoTentativa { captura: None, finalmente: None, fim: 0 };
+                if let Some(captura) = captura {
+                    info.captura = Some(self.posicao());
+                    let nomes: Vec<&str> = captura.nome.iter().map(String::as_str).collect();
+                    let (inicio, fim) = self.abrir_escopo(&nomes, &captura.corpo);
+                    if fim > inicio {
+                        self.emitir(Op::LimparLocais(inicio, fim));
+                    }
+                    // O erro capturado chega no topo da pilha
+                    if nomes.is_empty() {
+                        self.emitir(Op::Pop);
+                    } else {
+                        self.emitir(Op::DefinirLocal(inicio));
+                        self.inicializar(inicio);
+                    }
+                    for stmt in &captura.corpo {
+                        self.stmt(stmt);
+                    }
+                    self.fechar_escopo();
+                    self.emitir(Op::TentarConcluir);
+                }
+                if let Some(finalmente) = finalmente {
+                    info.finalmente = Some(self.posicao());
+                    self.bloco(finalmente);
+                    self.emitir(Op::FimFinalmente);
+                }
+                info.fim = self.posicao();
+                self.atual().proto.tentativas[indice] = info;
+                self.atual().tentativas -= 1;
+            }
+            Stmt::Lancar(valor, _) => {
+                self.expr(valor);
+                self.emitir(Op::Lancar);
+            }
+            Stmt::Importar(caminho, apelido, _) => {
+                let caminho = self.nome(caminho);
+                self.emitir(Op::CarregarModulo(caminho));
+                self.definir(apelido, false);
+            }
+            Stmt::DeImportar(caminho, nomes, _) => {
+                let caminho = self.nome(caminho);
+                self.emitir(Op::CarregarModulo(caminho));
+                for nome in nomes {
+                    self.emitir(Op::Dup);
+                    let indice = self.nome(nome);
+                    self.emitir(Op::ExtrairExportado(caminho, indice));
+                    self.definir(nome, false);
+                }
+                self.emitir(Op::Pop);
+            }
+            Stmt::Exportar(nomes, decl) => {
+                if let Some(decl) = decl {
+                    self.stmt(decl);
+                }
+                // Nomes contíguos na tabela, para a instrução receber só o intervalo
+                let inicio = self.atual().proto.nomes.len() as u32;
+                self.atual().proto.nomes.extend(nomes.iter().cloned());
+                self.emitir(Op::MarcarExportados(inicio, nomes.len() as u32));
+            }
+            Stmt::Break => self.sair_do_laco(true),
+            Stmt::Continue => self.sair_do_laco(false),
+        }
+    }
+
+    fn abrir_laco(&mut self) {
+        let tentativas = self.atual().tentativas;
+        self.atual().lacos.push(Laco { tentativas, parar: Vec::new(), continuar: Vec::new() });
+    }
+
+    fn fechar_laco(&mut self, continuar: u32, parar: u32) {
+        let laco = self.atual().lacos.pop().expect("laço aberto");
+        for pos in laco.continuar {
+            self.corrigir(pos, continuar);
+        }
+        for pos in laco.parar {
+            self.corrigir(pos, parar);
+        }
+    }
+
+    fn sair_do_laco(&mut self, parar: bool) {
+        let tentativas = self.atual().tentativas;
+        let Some(laco_tentativas) = self.atual().lacos.last().map(|l| l.tentativas) else {
+            self.emitir(Op::ForaDeLaco(parar));
+            return;
+        };
+        let atravessadas = (tentativas - laco_tentativas) as u32;
+        let pos = if atravessadas > 0 {
+            self.emitir(Op::SaltarSaindo(atravessadas, 0))
+        } else {
+            self.emitir(Op::Saltar(0))
+        };
+        let laco = self.atual().lacos.last_mut().expect("laço aberto");
+        if parar {
+            laco.parar.push(pos);
+        } else {
+            laco.continuar.push(pos);
+        }
+    }
+
+    fn funcao(&mut self, nome: &str, params: &[String], corpo: &[Stmt]) -> u32 {
+        let linha_anterior = self.linha;
+        self.iniciar_funcao(nome, params);
+        let nomes: Vec<&str> = params.iter().map(String::as_str).collect();
+        // Parâmetros e corpo dividem o escopo; um quadro novo já começa limpo
+        let (inicio, _) = self.abrir_escopo(&nomes, corpo);
+        for pos in inicio..inicio + nomes.len() as u32 {
+            self.inicializar(pos);
+        }
+        for stmt in corpo {
+            self.stmt(stmt);
+        }
+        self.emitir(Op::Nulo);
+        self.emitir(Op::Retornar);
+        let proto = self.concluir_funcao();
+        self.linha = linha_anterior;
+
+        let funcoes = &mut self.atual().proto.funcoes;
+        funcoes.push(Rc::new(proto));
+        funcoes.len() as u32 - 1
+    }
+
+    // ---- expressões ----
+
+    /// Valor de uma expressão literal, para as instruções com constante.
+    fn literal(expr: &Expr) -> Option<Value> {
+        match expr {
+            Expr::Numero(n) => Some(Value::Number(*n)),
+            Expr::Texto(s) => Some(Value::Text(s.clone())),
+            Expr::Bool(b) => Some(Value::Bool(*b)),
+            _ => None,
+        }
+    }
+
+    /// Operandos de `local op literal`, com a local já inicializada.
+    fn local_com_constante(&mut self, esq: &Expr, dir: &Expr) -> Option<(u32, u32)> {
+        let Expr::Var(nome, _) = esq else { return None };
+        let valor = Self::literal(dir)?;
+        let pos = self.local_inicializado(nome)?;
+        Some((pos, self.constante(valor)))
+    }
+
+    /// Expressão usada como comando: o resultado não fica na pilha.
+    fn expr_descartada(&mut self, expr: &Expr) {
+        if let Expr::Atribuir(nome, valor, _) = expr {
+            if let Expr::Binario(esq, op, dir) = &**valor {
+                if let (Expr::Var(lido, _), Some(c)) = (&**esq, Self::literal(dir)) {
+                    if let Some(pos) = self.local_inicializado(nome).filter(|_| lido == nome) {
+                        if !self.atual().proto.locais[pos as usize].constante {
+                            let c = self.constante(c);
+                            self.emitir(Op::AtualizarLocal(*op, pos, c));
+                            return;
+                        }
+                    }
+                }
+            }
+            self.expr(valor);
+            self.acessar(nome, true, true);
+        } else {
+            self.expr(expr);
+            self.emitir(Op::Pop);
+        }
+    }
+
+    /// Avalia a condição e emite o salto para quando ela for falsa; devolve
+    /// a posição do salto, a ser corrigida.
+    fn condicao(&mut self, cond: &Expr) -> usize {
+        match cond {
+            Expr::Binario(esq, op @ (BinOp::Lt | BinOp::Gt | BinOp::Le | BinOp::Ge | BinOp::Eq | BinOp::Neq), dir) => {
+                if let Some((pos, c)) = self.local_com_constante(esq, dir) {
+                    return self.emitir(Op::CompararLocalConstanteSaltar(*op, pos, c, 0));
+                }
+                self.expr(esq);
+                match Self::literal(dir) {
+                    Some(valor) => {
+                        let c = self.constante(valor);
+                        self.emitir(Op::CompararConstanteSaltar(*op, c, 0))
+                    }
+                    None => {
+                        self.expr(dir);
+                        self.emitir(Op::CompararSaltar(*op, 0))
+                    }
+                }
+            }
+            _ => {
+                self.expr(cond);
+                self.emitir(Op::SaltarSeFalso(0))
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Numero(n) => {
+                let c = self.constante(Value::Number(*n));
+                self.emitir(Op::Constante(c));
+            }
+            Expr::Texto(s) => {
+                let c = self.constante(Value::Text(s.clone()));
+                self.emitir(Op::Constante(c));
+            }
+            Expr::Bool(b) => {
+                let c = self.constante(Value::Bool(*b));
+                self.emitir(Op::Constante(c));
+            }
+            Expr::Nulo => {
+                self.emitir(Op::Nulo);
+            }
+            Expr::Var(nome, _) => self.acessar(nome, false, false),
+            Expr::Atribuir(nome, valor, _) => {
+                self.expr(valor);
+                self.acessar(nome, true, false);
+            }
+            Expr::Array(itens) => {
+                for item in itens {
+                    self.expr(item);
+                }
+                self.emitir(Op::Array(itens.len() as u32));
+            }
+            Expr::Dicionario(pares) => {
+                for (chave, valor) in pares {
+                    self.expr(chave);
+                    self.emitir(Op::ChaveDicionario);
+                    self.expr(valor);
+                }
+                self.emitir(Op::Dicionario(pares.len() as u32));
+            }
+            Expr::Index(alvo, indice) => {
+                self.expr(alvo);
+                self.expr(indice);
+                self.emitir(Op::Indexar);
+            }
+            Expr::AtribuirIndex(alvo, indice, valor) => {
+                self.expr(alvo);
+                self.expr(indice);
+                self.expr(valor);
+                self.emitir(Op::AtribuirIndice);
+            }
+            Expr::Unario(op, e) => {
+                self.expr(e);
+                self.emitir(Op::Unario(*op));
+            }
+            Expr::Binario(esq, op, dir) => {
+                if let Some((pos, c)) = self.local_com_constante(esq, dir) {
+                    self.emitir(Op::LocalBinarioConstante(*op, pos, c));
+                    return;
+                }
+                self.expr(esq);
+                match Self::literal(dir) {
+                    Some(valor) => {
+                        let c = self.constante(valor);
+                        self.emitir(Op::BinarioConstante(*op, c));
+                    }
+                    None => {
+                        self.expr(dir);
+                        self.emitir(Op::Binario(*op));
+                    }
+                }
+            }
+            Expr::Chamada(funcao, args) => {
+                self.expr(funcao);
+                for arg in args {
+                    self.expr(arg);
+                }
+                self.emitir(Op::Chamar(args.len() as u32));
+            }
+            Expr::Intervalo(inicio, fim, inclusivo) => {
+                if let Some(inicio) = inicio {
+                    self.expr(inicio);
+                }
+                if let Some(fim) = fim {
+                    self.expr(fim);
+                }
+                self.emitir(Op::Intervalo(inicio.is_some(), fim.is_some(), *inclusivo));
+            }
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use std::io::{self, Write};
+
+/// Lista os tokens de `codigo`, com linha e coluna.
+pub fn print_tokens(codigo: &str, saida: &mut dyn Write) -> io::Result<()> {
+    for token in Lexer::new(codigo).tokenize() {
+        writeln!(saida, "  {:>3}:{:<3} {:?}", token.linha, token.coluna, token.tipo)?;
+    }
+    Ok(())
+}
+
+/// Mostra a árvore sintática de cada comando de `codigo`.
+pub fn print_ast(codigo: &str, saida: &mut dyn Write) -> io::Result<()> {
+    let tokens = Lexer::new(codigo).tokenize();
+    for stmt in Parser::new(tokens).parse() {
+        writeln!(saida, "{:#?}", stmt)?;
+    }
+    Ok(())
+}
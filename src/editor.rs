@@ -0,0 +1,238 @@
+use crate::lexer::Lexer;
+use crate::tokens::{e_operador, Token, TokenType};
+use colored::*;
+use rustyline::completion::Completer;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Helper};
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+/// Integração do REPL com o editor de linha: completa palavras-chave,
+/// nativas e as variáveis da sessão com Tab e colore o código digitado.
+#[derive(Default)]
+pub struct Auxiliar {
+    /// Nomes oferecidos ao completar, atualizados após cada comando
+    pub nomes: Vec<String>,
+    /// Nomes que guardam funções, destacados ao colorir
+    pub funcoes: HashSet<String>,
+}
+
+impl Completer for Auxiliar {
+    type Candidate = String;
+
+    fn complete(&self, linha: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<String>)> {
+        let inicio = linha[..pos]
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| c.is_alphanumeric() || *c == '_')
+            .last()
+            .map_or(pos, |(i, _)| i);
+        let prefixo = &linha[inicio..pos];
+        if prefixo.is_empty() {
+            return Ok((pos, Vec::new()));
+        }
+        let candidatos = self.nomes.iter().filter(|n| n.starts_with(prefixo)).cloned().collect();
+        Ok((inicio, candidatos))
+    }
+}
+
+impl Hinter for Auxiliar {
+    type Hint = String;
+}
+
+impl Highlighter for Auxiliar {
+    fn highlight<'l>(&self, linha: &'l str, pos: usize) -> Cow<'l, str> {
+        Cow::Owned(self.colorir(linha, pos))
+    }
+
+    fn highlight_char(&self, _linha: &str, _pos: usize, _forced: bool) -> bool {
+        // O par de um parêntese muda conforme o cursor anda
+        true
+    }
+}
+
+impl Validator for Auxiliar {}
+
+impl Helper for Auxiliar {}
+
+impl Auxiliar {
+    /// Colore `linha` com base nos tokens do `Lexer` e destaca o parêntese,
+    /// chave ou colchete sob o cursor (`pos`, em bytes) junto com o seu par.
+    fn colorir(&self, linha: &str, pos: usize) -> String {
+        let chars: Vec<char> = linha.chars().collect();
+        let cursor = linha[..pos].chars().count();
+        let tokens: Vec<Token> = Lexer::new(linha).tokenize().into_iter().filter(|t| t.tipo != TokenType::Fim).collect();
+        let trechos: Vec<(usize, usize)> = tokens.iter().map(|t| trecho(&chars, t)).collect();
+        let par = par_de_delimitadores(&tokens, &trechos, cursor);
+
+        let mut saida = String::new();
+        let mut anterior = 0;
+        for (i, (token, &(inicio, fim))) in tokens.iter().zip(&trechos).enumerate() {
+            let inicio = inicio.max(anterior);
+            let fim = fim.max(inicio);
+            saida.push_str(&colorir_intervalo(&chars[anterior..inicio]));
+            let texto: String = chars[inicio..fim].iter().collect();
+            let colorido = if token.tipo == TokenType::E && !e_operador(&tokens, i) {
+                texto.normal()
+            } else {
+                self.colorir_token(&token.tipo, &texto)
+            };
+            if par.is_some_and(|(a, b)| i == a || i == b) {
+                saida.push_str(&colorido.bold().underline().to_string());
+            } else {
+                saida.push_str(&colorido.to_string());
+            }
+            anterior = fim;
+        }
+        saida.push_str(&colorir_intervalo(&chars[anterior..]));
+        saida
+    }
+
+    fn colorir_token(&self, tipo: &TokenType, texto: &str) -> ColoredString {
+        use TokenType::*;
+        match tipo {
+            Texto(_) => texto.green(),
+            Numero(_) | Verdadeiro | Falso => texto.cyan(),
+            Nulo => texto.dimmed(),
+            Ident(nome) if self.funcoes.contains(nome) => texto.yellow(),
+            Ident(_) => texto.normal(),
+            Variavel | Constante | Funcao | Se | Senao | Enquanto | Para | Retornar | Imprimir | E | Ou
+            | Break | Continue | Tentar | Capturar | Finalmente | Lancar | Importar | Exportar => {
+                texto.magenta().bold()
+            }
+            _ => texto.normal(),
+        }
+    }
+}
+
+/// Posições `[inicio, fim)` do token na linha, em caracteres. O lexema de um
+/// texto perde os escapes, então o fim dele é procurado na própria linha.
+fn trecho(chars: &[char], token: &Token) -> (usize, usize) {
+    let inicio = token.coluna.saturating_sub(1).min(chars.len());
+    if !matches!(token.tipo, TokenType::Texto(_)) {
+        return (inicio, (inicio + token.lexema.chars().count()).min(chars.len()));
+    }
+    let mut fim = inicio + 1;
+    while fim < chars.len() {
+        match chars[fim] {
+            '\\' => fim += 2,
+            '"' => return (inicio, fim + 1),
+            _ => fim += 1,
+        }
+    }
+    (inicio, chars.len())
+}
+
+/// Índices do delimitador junto ao cursor e do seu par, se houver.
+fn par_de_delimitadores(tokens: &[Token], trechos: &[(usize, usize)], cursor: usize) -> Option<(usize, usize)> {
+    use TokenType::*;
+    let abre = |t: &TokenType| matches!(t, AbrePar | AbreChave | AbreColchete);
+    let fecha = |t: &TokenType| matches!(t, FechaPar | FechaChave | FechaColchete);
+    let atual = trechos
+        .iter()
+        .position(|&(inicio, _)| inicio == cursor)
+        .filter(|&i| abre(&tokens[i].tipo) || fecha(&tokens[i].tipo))
+        .or_else(|| trechos.iter().position(|&(_, fim)| fim == cursor))
+        .filter(|&i| abre(&tokens[i].tipo) || fecha(&tokens[i].tipo))?;
+
+    let mut profundidade = 0i32;
+    if abre(&tokens[atual].tipo) {
+        for (i, token) in tokens.iter().enumerate().skip(atual) {
+            profundidade += if abre(&token.tipo) { 1 } else if fecha(&token.tipo) { -1 } else { 0 };
+            if profundidade == 0 {
+                return Some((atual, i));
+            }
+        }
+    } else {
+        for (i, token) in tokens.iter().enumerate().take(atual + 1).rev() {
+            profundidade += if fecha(&token.tipo) { 1 } else if abre(&token.tipo) { -1 } else { 0 };
+            if profundidade == 0 {
+                return Some((i, atual));
+            }
+        }
+    }
+    None
+}
+
+/// Texto entre tokens: espaços e, talvez, um comentário `//` até o fim da linha.
+fn colorir_intervalo(chars: &[char]) -> String {
+    let texto: String = chars.iter().collect();
+    match texto.find("//") {
+        Some(i) => format!("{}{}", &texto[..i], texto[i..].dimmed()),
+        None => texto,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{par_de_delimitadores, trecho, Auxiliar};
+    use crate::lexer::Lexer;
+    use crate::tokens::TokenType;
+    use rustyline::completion::Completer;
+    use rustyline::history::DefaultHistory;
+    use rustyline::Context;
+
+    fn completar(auxiliar: &Auxiliar, linha: &str) -> (usize, Vec<String>) {
+        let historico = DefaultHistory::new();
+        auxiliar.complete(linha, linha.len(), &Context::new(&historico)).unwrap()
+    }
+
+    #[test]
+    fn completa_a_palavra_antes_do_cursor() {
+        let auxiliar = Auxiliar { nomes: vec!["imprimir".into(), "soma".into(), "sobra".into()], ..Auxiliar::default() };
+        assert_eq!(completar(&auxiliar, "x = so"), (4, vec!["soma".to_string(), "sobra".to_string()]));
+        assert_eq!(completar(&auxiliar, "imp"), (0, vec!["imprimir".to_string()]));
+        assert_eq!(completar(&auxiliar, "f(ção_s"), (2, Vec::new()));
+        assert_eq!(completar(&auxiliar, "x = "), (4, Vec::new()));
+    }
+
+    /// Par destacado em `linha` com o cursor em `cursor`, como texto.
+    fn par(linha: &str, cursor: usize) -> Option<(String, String)> {
+        let chars: Vec<char> = linha.chars().collect();
+        let tokens: Vec<_> = Lexer::new(linha).tokenize().into_iter().filter(|t| t.tipo != TokenType::Fim).collect();
+        let trechos: Vec<_> = tokens.iter().map(|t| trecho(&chars, t)).collect();
+        let (a, b) = par_de_delimitadores(&tokens, &trechos, cursor)?;
+        Some((format!("{}@{}", tokens[a].lexema, trechos[a].0), format!("{}@{}", tokens[b].lexema, trechos[b].0)))
+    }
+
+    #[test]
+    fn destaca_o_par_do_delimitador_no_cursor() {
+        let par_de = |a: &str, b: &str| Some((a.to_string(), b.to_string()));
+        assert_eq!(par("f(a[1])", 1), par_de("(@1", ")@6"));
+        assert_eq!(par("f(a[1])", 7), par_de("(@1", ")@6"));
+        assert_eq!(par("f(a[1])", 3), par_de("[@3", "]@5"));
+        // Logo depois de um delimitador também vale
+        assert_eq!(par("f(a[1])", 2), par_de("(@1", ")@6"));
+        assert_eq!(par("f(a + b)", 3), None);
+        assert_eq!(par("{ (", 0), None);
+    }
+
+    #[test]
+    fn colore_sem_mudar_o_texto() {
+        colored::control::set_override(true);
+        let auxiliar = Auxiliar { funcoes: ["soma".to_string()].into(), ..Auxiliar::default() };
+        let linha = "se (x e nulo) { soma(\"a\\\"b\", 1); } // fim";
+        let colorido = auxiliar.colorir(linha, 0);
+        let mut sem_cores = String::new();
+        let mut escape = false;
+        for c in colorido.chars() {
+            match c {
+                '\u{1b}' => escape = true,
+                'm' if escape => escape = false,
+                _ if !escape => sem_cores.push(c),
+                _ => {}
+            }
+        }
+        assert_eq!(sem_cores, linha);
+        assert!(colorido.contains("\u{1b}[1;35mse\u{1b}[0m"), "{:?}", colorido);
+        assert!(colorido.contains("\u{1b}[1;35me\u{1b}[0m"), "{:?}", colorido);
+        assert!(colorido.contains("\u{1b}[2mnulo\u{1b}[0m"), "{:?}", colorido);
+        assert!(colorido.contains("\u{1b}[33msoma\u{1b}[0m"), "{:?}", colorido);
+        assert!(colorido.contains("\u{1b}[32m\"a\\\"b\"\u{1b}[0m"), "{:?}", colorido);
+        assert!(colorido.contains("\u{1b}[2m// fim\u{1b}[0m"), "{:?}", colorido);
+        // `e` fora da posição de operador é uma variável comum
+        assert_eq!(auxiliar.colorir("e", 1), "e");
+    }
+}
@@ -1,49 +1,102 @@
-use crate::interpreter::Value;
-use std::collections::HashMap;
+use crate::interpreter::{ErroExecucao, Value};
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 use std::cell::RefCell;
 
 pub type EnvRef = Rc<RefCell<Environment>>;
 
+/// Escopo de variáveis. Os valores ficam na ordem de declaração, de modo que
+/// o resolver pode apontar a posição de cada variável local.
+/// A partir de quantas variáveis um escopo mantém um índice por nome; os
+/// menores (como os de uma chamada de função) são percorridos em sequência.
+const TAMANHO_INDEXADO: usize = 8;
+
 #[derive(Debug)]
 pub struct Environment {
-    values: HashMap<String, Value>,
+    values: Vec<(String, Value)>,
+    /// Índice por nome, só nos escopos com `TAMANHO_INDEXADO` variáveis ou mais
+    posicoes: HashMap<String, usize>,
+    /// Nomes declarados com `constante` neste escopo
+    constantes: HashSet<String>,
     enclosing: Option<EnvRef>,
 }
 
 impl Environment {
     pub fn new() -> Self {
         Self {
-            values: HashMap::new(),
+            values: Vec::new(),
+            posicoes: HashMap::new(),
+            constantes: HashSet::new(),
             enclosing: None,
         }
     }
 
     pub fn with_enclosing(enclosing: EnvRef) -> Self {
         Self {
-            values: HashMap::new(),
+            values: Vec::new(),
+            posicoes: HashMap::new(),
+            constantes: HashSet::new(),
             enclosing: Some(enclosing),
         }
     }
 
     pub fn define(&mut self, name: String, value: Value) {
-        self.values.insert(name, value);
+        match self.posicao(&name) {
+            Some(posicao) => self.values[posicao].1 = value,
+            None => {
+                self.values.push((name, value));
+                let ultima = self.values.len() - 1;
+                if self.values.len() == TAMANHO_INDEXADO {
+                    self.posicoes = self.values.iter().enumerate().map(|(i, (n, _))| (n.clone(), i)).collect();
+                } else if self.values.len() > TAMANHO_INDEXADO {
+                    self.posicoes.insert(self.values[ultima].0.clone(), ultima);
+                }
+            }
+        }
+    }
+
+    /// Declaração feita pelo script (`variavel`, `constante`, `funcao`, `importar`).
+    /// Diferente de `define`, recusa um nome que já existe neste escopo, a menos
+    /// que `redeclarar` seja verdadeiro e o nome anterior não seja constante.
+    pub fn declarar(&mut self, name: &str, value: Value, constante: bool, redeclarar: bool) -> Result<(), ErroExecucao> {
+        if self.constantes.contains(name) {
+            return Err(ErroExecucao::novo(
+                "Redeclaracao",
+                format!("Constante '{}' já foi declarada neste escopo", name),
+            ));
+        }
+        if self.posicao(name).is_some() && !redeclarar {
+            return Err(ErroExecucao::novo(
+                "Redeclaracao",
+                format!("Variável '{}' já foi declarada neste escopo", name),
+            ));
+        }
+        if constante {
+            self.constantes.insert(name.to_string());
+        }
+        self.define(name.to_string(), value);
+        Ok(())
     }
 
-    pub fn assign(&mut self, name: &str, val: Value) -> Result<(), String> {
-        if self.values.contains_key(name) {
-            self.values.insert(name.to_string(), val);
+    pub fn assign(&mut self, name: &str, val: Value) -> Result<(), ErroExecucao> {
+        if self.constantes.contains(name) {
+            Err(ErroExecucao::novo(
+                "AtribuicaoConstante",
+                format!("Não é possível alterar a constante '{}'", name),
+            ))
+        } else if let Some(posicao) = self.posicao(name) {
+            self.values[posicao].1 = val;
             Ok(())
         } else if let Some(ref enc) = self.enclosing {
             enc.borrow_mut().assign(name, val)
         } else {
-            Err(format!("Variável '{}' não definida", name))
+            Err(ErroExecucao::novo("VariavelIndefinida", format!("Variável '{}' não definida", name)))
         }
     }
 
     pub fn get(&self, name: &str) -> Result<Value, String> {
-        if let Some(v) = self.values.get(name) {
-            Ok(v.clone())
+        if let Some(posicao) = self.posicao(name) {
+            Ok(self.values[posicao].1.clone())
         } else if let Some(ref enc) = self.enclosing {
             enc.borrow().get(name)
         } else {
@@ -51,10 +104,74 @@ impl Environment {
         }
     }
 
+    /// Lê a variável na posição calculada pelo resolver. O resolver só
+    /// devolve posições que não mudam durante a execução, então uma posição
+    /// que não guarda `name` é um erro interno.
+    pub fn get_at(&self, profundidade: usize, posicao: usize, name: &str) -> Result<Value, String> {
+        if profundidade > 0 {
+            return match self.enclosing {
+                Some(ref enc) => enc.borrow().get_at(profundidade - 1, posicao, name),
+                None => Err(Self::posicao_invalida(profundidade, posicao, name)),
+            };
+        }
+        match self.values.get(posicao) {
+            Some((nome, valor)) if nome == name => Ok(valor.clone()),
+            _ => Err(Self::posicao_invalida(profundidade, posicao, name)),
+        }
+    }
+
+    pub fn assign_at(&mut self, profundidade: usize, posicao: usize, name: &str, val: Value) -> Result<(), ErroExecucao> {
+        if profundidade > 0 {
+            return match self.enclosing {
+                Some(ref enc) => enc.borrow_mut().assign_at(profundidade - 1, posicao, name, val),
+                None => Err(ErroExecucao::novo("ErroInterno", Self::posicao_invalida(profundidade, posicao, name))),
+            };
+        }
+        match self.values.get_mut(posicao) {
+            Some((nome, _)) if nome == name && self.constantes.contains(name) => self.assign(name, val),
+            Some((nome, valor)) if nome == name => {
+                *valor = val;
+                Ok(())
+            }
+            _ => Err(ErroExecucao::novo("ErroInterno", Self::posicao_invalida(profundidade, posicao, name))),
+        }
+    }
+
+    fn posicao_invalida(profundidade: usize, posicao: usize, name: &str) -> String {
+        debug_assert!(false, "'{}' não está em ({}, {})", name, profundidade, posicao);
+        format!("Erro interno: '{}' não está na posição ({}, {}) calculada pelo resolver", name, profundidade, posicao)
+    }
+
+    /// Posição de `name` neste escopo, sem olhar os escopos externos.
+    pub fn posicao(&self, name: &str) -> Option<usize> {
+        if self.values.len() < TAMANHO_INDEXADO {
+            self.values.iter().position(|(n, _)| n == name)
+        } else {
+            self.posicoes.get(name).copied()
+        }
+    }
+
+    /// Valor na posição dada, se ela ainda guardar `name`.
+    pub fn valor_em(&self, posicao: usize, name: &str) -> Option<Value> {
+        match self.values.get(posicao) {
+            Some((nome, valor)) if nome == name => Some(valor.clone()),
+            _ => None,
+        }
+    }
+
+    /// Nomes visíveis a partir deste escopo, incluindo os escopos externos.
+    pub fn nomes_visiveis(&self) -> Vec<String> {
+        let mut nomes: Vec<String> = self.values.iter().map(|(n, _)| n.clone()).collect();
+        if let Some(ref enc) = self.enclosing {
+            nomes.extend(enc.borrow().nomes_visiveis());
+        }
+        nomes
+    }
+
     pub fn get_all_variables(&self) -> Vec<(String, Value)> {
-        let mut vars: Vec<(String, Value)> = self.values.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+        let mut vars: Vec<(String, Value)> = self.values.clone();
 
         vars.sort_by(|a, b| a.0.cmp(&b.0));
         vars
     }
-}
\ No newline at end of file
+}
@@ -1,4 +1,7 @@
+
 use colored::*;
+use std::fmt;
+use std::io::{self, Write};
 
 #[derive(Debug, Clone)]
 pub struct SourceLocation {
@@ -7,10 +10,21 @@ pub struct SourceLocation {
     pub fonte: String,
 }
 
+impl SourceLocation {
+    /// Início do código da linha `linha` de `fonte`, se ela existir.
+    pub fn na_linha(fonte: &str, linha: usize) -> Option<Self> {
+        let texto = fonte.lines().nth(linha.saturating_sub(1))?;
+        let coluna = texto.len() - texto.trim_start().len() + 1;
+        Some(Self { linha, coluna, fonte: fonte.to_string() })
+    }
+}
+
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum ErrorType {
     LexicoError,
     SintaticoError,
+    SemanticoError,
     RuntimeError,
 }
 
@@ -19,6 +33,8 @@ pub struct LucasError {
     pub mensagem: String,
     pub localizacao: Option<SourceLocation>,
     pub sugestao: Option<String>,
+    /// Chamadas em andamento quando o erro ocorreu, da mais externa para a mais interna
+    pub rastro: Vec<String>,
 }
 
 impl LucasError {
@@ -28,6 +44,7 @@ impl LucasError {
             mensagem,
             localizacao: None,
             sugestao: None,
+            rastro: Vec::new(),
         }
     }
 
@@ -41,52 +58,102 @@ impl LucasError {
         self
     }
 
+    pub fn com_rastro(mut self, rastro: Vec<String>) -> Self {
+        self.rastro = rastro;
+        self
+    }
+
+    pub fn titulo(&self) -> &'static str {
+        match self.tipo {
+            ErrorType::LexicoError => "Erro Léxico",
+            ErrorType::SintaticoError => "Erro Sintático",
+            ErrorType::SemanticoError => "Erro Semântico",
+            ErrorType::RuntimeError => "Erro de Execução",
+        }
+    }
+
+    /// Mostra o erro na saída de erros.
     pub fn exibir(&self) {
+        let _ = self.exibir_em(&mut io::stderr().lock());
+    }
+
+    pub fn exibir_em(&self, saida: &mut dyn Write) -> io::Result<()> {
+        // Monta a mensagem inteira antes, para ela chegar ao destino de uma vez
+        let mut texto = Vec::new();
+        self.escrever(&mut texto)?;
+        saida.write_all(&texto)
+    }
+
+    fn escrever(&self, saida: &mut dyn Write) -> io::Result<()> {
         let tipo_str = match self.tipo {
-            ErrorType::LexicoError => "Erro Léxico".red().bold(),
-            ErrorType::SintaticoError => "Erro Sintático".yellow().bold(),
-            ErrorType::RuntimeError => "Erro de Execução".red().bold(),
+            ErrorType::LexicoError | ErrorType::RuntimeError => self.titulo().red().bold(),
+            ErrorType::SintaticoError | ErrorType::SemanticoError => self.titulo().yellow().bold(),
         };
 
-        println!("\n{} {}", "❌".red(), tipo_str);
+        writeln!(saida, "\n{} {}", "❌".red(), tipo_str)?;
 
         if let Some(ref loc) = self.localizacao {
-            self.exibir_com_contexto(loc);
+            self.exibir_com_contexto(saida, loc)?;
         } else {
-            println!("  {}", self.mensagem.white());
+            writeln!(saida, "  {}", self.mensagem.white())?;
         }
 
         if let Some(ref sug) = self.sugestao {
-            println!("\n{} {}", "💡".yellow(), sug.cyan());
+            writeln!(saida, "\n{} {}", "💡".yellow(), sug.cyan())?;
         }
 
-        println!();
+        if !self.rastro.is_empty() {
+            writeln!(saida, "\n{}", "Rastreamento (chamada mais recente por último):".dimmed())?;
+            for linha in &self.rastro {
+                writeln!(saida, "  {}", linha.dimmed())?;
+            }
+        }
+
+        writeln!(saida)
     }
 
-    fn exibir_com_contexto(&self, loc: &SourceLocation) {
-        println!(" na linha {}, coluna {}:", loc.linha.to_string().cyan(), loc.coluna.to_string().cyan());
-        println!();
+    fn exibir_com_contexto(&self, saida: &mut dyn Write, loc: &SourceLocation) -> io::Result<()> {
+        writeln!(saida, " na linha {}, coluna {}:", loc.linha.to_string().cyan(), loc.coluna.to_string().cyan())?;
+        writeln!(saida)?;
 
         let linhas: Vec<&str> = loc.fonte.lines().collect();
         let linha_idx = loc.linha.saturating_sub(1);
 
         if linha_idx > 0 {
-            println!(" {} | {}", format!("{:3}", linha_idx).dimmed(), linhas[linha_idx - 1].dimmed());
+            writeln!(saida, " {} | {}", format!("{:3}", linha_idx).dimmed(), linhas[linha_idx - 1].dimmed())?;
         }
 
         if linha_idx < linhas.len() {
-            println!(" {} | {}", format!("{:3}", loc.linha).cyan().bold(), linhas[linha_idx]);
+            writeln!(saida, " {} | {}", format!("{:3}", loc.linha).cyan().bold(), linhas[linha_idx])?;
 
-            let espacos = " ".repeat(loc.coluna + 6);
-            println!("  {} {} {}", espacos, "^".red().bold(), self.mensagem.red());
+            let espacos = " ".repeat(loc.coluna + 3);
+            writeln!(saida, "  {} {} {}", espacos, "^".red().bold(), self.mensagem.red())?;
         }
 
         if linha_idx + 1 < linhas.len() {
-            println!(" {} | {}", format!("{:3}", linha_idx + 2).dimmed(), linhas[linha_idx + 1].dimmed());
+            writeln!(saida, " {} | {}", format!("{:3}", linha_idx + 2).dimmed(), linhas[linha_idx + 1].dimmed())?;
         }
+        Ok(())
+    }
+}
+
+impl fmt::Display for LucasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.localizacao {
+            Some(loc) => write!(f, "{} na linha {}: {}", self.titulo(), loc.linha, self.mensagem),
+            None => write!(f, "{}: {}", self.titulo(), self.mensagem),
+        }
+    }
+}
+
+impl fmt::Debug for LucasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
     }
 }
 
+impl std::error::Error for LucasError {}
+
 pub fn sugerir_similar(nome: &str, disponiveis: &[String]) -> Option<String> {
     let mut melhor_match: Option<(String, usize)> = None;
 
@@ -107,8 +174,8 @@ pub fn sugerir_similar(nome: &str, disponiveis: &[String]) -> Option<String> {
 }
 
 fn levenshtein_distance(s1: &str, s2: &str) -> usize {
-    let len1 = s1.len();
-    let len2 = s2.len();
+    let len1 = s1.chars().count();
+    let len2 = s2.chars().count();
 
     if len1 == 0 {
         return len2;
@@ -119,11 +186,11 @@ fn levenshtein_distance(s1: &str, s2: &str) -> usize {
 
     let mut matrix = vec![vec![0; len2 + 1]; len1 + 1];
 
-    for i in 0..=len1 {
-        matrix[i][0] = i;
+    for (i, linha) in matrix.iter_mut().enumerate() {
+        linha[0] = i;
     }
-    for j in 0..=len2 {
-        matrix[0][j] = j;
+    for (j, celula) in matrix[0].iter_mut().enumerate() {
+        *celula = j;
     }
 
     for (i, c1) in s1.chars().enumerate() {
@@ -139,6 +206,26 @@ fn levenshtein_distance(s1: &str, s2: &str) -> usize {
     matrix[len1][len2]
 }
 
-pub fn erro(msg: &str) {
-    LucasError::novo(ErrorType::RuntimeError, msg.to_string()).exibir();
-}
\ No newline at end of file
+/// Traduz um `std::io::Error` para uma mensagem em português.
+pub fn descrever_erro_io(err: &std::io::Error) -> String {
+    use std::io::ErrorKind;
+
+    match err.kind() {
+        ErrorKind::NotFound => "arquivo ou diretório não encontrado".into(),
+        ErrorKind::PermissionDenied => "permissão negada".into(),
+        ErrorKind::AlreadyExists => "o arquivo já existe".into(),
+        ErrorKind::InvalidData => "o conteúdo não é texto UTF-8 válido".into(),
+        ErrorKind::IsADirectory => "o caminho é um diretório".into(),
+        ErrorKind::NotADirectory => "o caminho não é um diretório".into(),
+        ErrorKind::DirectoryNotEmpty => "o diretório não está vazio".into(),
+        ErrorKind::ReadOnlyFilesystem => "sistema de arquivos somente leitura".into(),
+        ErrorKind::StorageFull => "sem espaço em disco".into(),
+        ErrorKind::Interrupted => "operação interrompida".into(),
+        _ => format!("erro de entrada/saída ({})", err),
+    }
+}
+
+/// Avisos não interrompem a execução e ocupam uma linha só.
+pub fn aviso(saida: &mut dyn Write, onde: &str, msg: &str) {
+    let _ = writeln!(saida, "{} {} {}: {}", "⚠".yellow(), "Aviso".yellow().bold(), onde, msg);
+}
@@ -0,0 +1,241 @@
+use crate::errors::LucasError;
+use crate::lexer::{Comentario, Lexer};
+use crate::parser::Parser;
+use crate::tokens::{e_operador, Token, TokenType};
+use std::collections::{HashMap, HashSet};
+
+const INDENTACAO: &str = "    ";
+
+/// Reescreve `codigo` no estilo padrão de Lucas: 4 espaços por nível de
+/// chaves, espaços em volta dos operadores e um comando por linha, mesmo
+/// quando o original os junta sem `;`. As quebras de linha do original são mantidas (no máximo
+/// uma linha em branco seguida), assim como os comentários. Formatar o
+/// resultado de novo não o altera. Código com erro léxico ou sintático é
+/// recusado.
+pub fn formatar(codigo: &str) -> Result<String, LucasError> {
+    let mut lexer = Lexer::new(codigo);
+    let mut tokens = lexer.tokenize();
+    if let Some(erro) = lexer.erros.first() {
+        return Err(erro.to_lucas(codigo));
+    }
+    let mut parser = Parser::new(tokens.clone());
+    parser.parse();
+    if let Some(erro) = &parser.erro {
+        return Err(erro.to_lucas(codigo));
+    }
+    tokens.pop();
+
+    let mut formatador = Formatador { comandos: parser.comandos.into_iter().collect(), ..Default::default() };
+    formatador.escrever(&tokens, &lexer.comentarios);
+    Ok(formatador.saida)
+}
+
+/// Um `(`, `[` ou `{` ainda aberto.
+struct Abertura {
+    /// Chave de um bloco de comandos (e não de um dicionário)
+    bloco: bool,
+    /// Nível da linha onde foi aberto; o conteúdo fica um nível adiante
+    nivel: usize,
+}
+
+#[derive(Default)]
+struct Formatador {
+    saida: String,
+    /// Linha em montagem, sem a indentação
+    linha: String,
+    nivel: usize,
+    pilha: Vec<Abertura>,
+    /// Linha da fonte do último token ou comentário escrito
+    ultima_linha: usize,
+    /// O próximo token precisa começar uma linha nova
+    quebrar: bool,
+    /// Índices dos tokens que começam um comando, segundo o parser
+    comandos: HashSet<usize>,
+}
+
+impl Formatador {
+    fn escrever(&mut self, tokens: &[Token], comentarios: &[Comentario]) {
+        // Um comentário numa linha com código vai depois do último token dela
+        let mut ultimo_da_linha = HashMap::new();
+        for (i, token) in tokens.iter().enumerate() {
+            ultimo_da_linha.insert(token.linha, i);
+        }
+        let mut finais: HashMap<usize, &str> = HashMap::new();
+        let mut soltos = Vec::new();
+        for comentario in comentarios {
+            match ultimo_da_linha.get(&comentario.linha) {
+                Some(&i) => {
+                    finais.insert(i, &comentario.texto);
+                }
+                None => soltos.push(comentario),
+            }
+        }
+        let mut soltos = soltos.into_iter().peekable();
+
+        let mut bloco_fechado = false;
+        for (i, token) in tokens.iter().enumerate() {
+            while let Some(comentario) = soltos.next_if(|c| c.linha < token.linha) {
+                self.comentario_solto(comentario);
+            }
+            let anterior = i.checked_sub(1).map(|j| &tokens[j]);
+            let comando = self.comandos.contains(&i);
+            let bloco = token.tipo == TokenType::AbreChave && (comando || abre_bloco(anterior, bloco_fechado, &self.pilha));
+            let fecha_bloco = token.tipo == TokenType::FechaChave && self.pilha.last().is_some_and(|a| a.bloco);
+
+            // `{` de um cabeçalho e `senao`/`capturar`/`finalmente` depois de `}` ficam
+            // na mesma linha, a não ser que um comentário no fim dela impeça
+            let junta = match token.tipo {
+                TokenType::AbreChave => bloco,
+                TokenType::Senao | TokenType::Capturar | TokenType::Finalmente => bloco_fechado,
+                _ => false,
+            };
+            let nova_linha = (anterior.is_some_and(|a| token.linha > a.linha) && !junta) || comando;
+            if self.quebrar || nova_linha || (fecha_bloco && !self.linha.is_empty()) {
+                self.quebrar_linha();
+            }
+            if self.linha.is_empty() {
+                self.comecar_linha(token.linha, matches!(token.tipo, TokenType::FechaPar | TokenType::FechaColchete | TokenType::FechaChave));
+            } else if let Some(anterior) = anterior {
+                if espaco(anterior, token, unario(tokens, i - 1), &self.pilha) {
+                    self.linha.push(' ');
+                }
+            }
+            self.linha.push_str(&texto(token));
+            self.ultima_linha = token.linha;
+
+            match token.tipo {
+                TokenType::AbrePar | TokenType::AbreColchete | TokenType::AbreChave => {
+                    self.pilha.push(Abertura { bloco, nivel: self.nivel });
+                }
+                TokenType::FechaPar | TokenType::FechaColchete | TokenType::FechaChave => {
+                    self.pilha.pop();
+                }
+                _ => {}
+            }
+            let proximo = tokens.get(i + 1).map(|t| &t.tipo);
+            self.quebrar = match token.tipo {
+                TokenType::PontoVirgula => self.pilha.last().is_none_or(|a| a.bloco),
+                TokenType::AbreChave => bloco,
+                TokenType::FechaChave => {
+                    fecha_bloco
+                        && !matches!(
+                            proximo,
+                            Some(TokenType::Senao | TokenType::Capturar | TokenType::Finalmente | TokenType::PontoVirgula)
+                        )
+                }
+                _ => false,
+            };
+            bloco_fechado = fecha_bloco;
+
+            if let Some(texto) = finais.get(&i) {
+                self.linha.push_str(&format!(" //{}", texto.trim_end()));
+                self.quebrar = true;
+            }
+        }
+        for comentario in soltos {
+            self.comentario_solto(comentario);
+        }
+        self.quebrar_linha();
+    }
+
+    fn comentario_solto(&mut self, comentario: &Comentario) {
+        self.quebrar_linha();
+        self.comecar_linha(comentario.linha, false);
+        self.linha.push_str(&format!("//{}", comentario.texto.trim_end()));
+        self.ultima_linha = comentario.linha;
+        self.quebrar = true;
+    }
+
+    /// Prepara uma linha nova para o que está na linha `linha` da fonte,
+    /// mantendo uma linha em branco se havia alguma antes.
+    fn comecar_linha(&mut self, linha: usize, fechamento: bool) {
+        if !self.saida.is_empty() && linha > self.ultima_linha + 1 {
+            self.saida.push('\n');
+        }
+        self.nivel = match self.pilha.last() {
+            Some(abertura) if fechamento => abertura.nivel,
+            Some(abertura) => abertura.nivel + 1,
+            None => 0,
+        };
+    }
+
+    fn quebrar_linha(&mut self) {
+        if !self.linha.is_empty() {
+            self.saida.push_str(&INDENTACAO.repeat(self.nivel));
+            self.saida.push_str(&std::mem::take(&mut self.linha));
+            self.saida.push('\n');
+        }
+        self.quebrar = false;
+    }
+}
+
+/// Se o `{` depois de `anterior` abre um bloco de comandos em vez de um
+/// dicionário: depois de um cabeçalho ou no início de um comando.
+fn abre_bloco(anterior: Option<&Token>, bloco_fechado: bool, pilha: &[Abertura]) -> bool {
+    match anterior.map(|t| &t.tipo) {
+        None | Some(TokenType::FechaPar | TokenType::Senao | TokenType::Tentar | TokenType::Finalmente | TokenType::PontoVirgula) => true,
+        Some(TokenType::FechaChave) => bloco_fechado,
+        Some(TokenType::AbreChave) => pilha.last().is_some_and(|a| a.bloco),
+        _ => false,
+    }
+}
+
+/// Se `tokens[i]` é um `-` unário, olhando o token antes dele.
+fn unario(tokens: &[Token], i: usize) -> bool {
+    use TokenType::*;
+    if tokens[i].tipo != Menos {
+        return false;
+    }
+    let Some(antes) = i.checked_sub(1) else {
+        return true;
+    };
+    match tokens[antes].tipo {
+        E => e_operador(tokens, antes),
+        Mais | Menos | Multiplica | Divide | Igual | IgualIgual | Diferente | Menor | MenorIgual | Maior | MaiorIgual
+        | Ou | Nao | AbrePar | AbreColchete | AbreChave | Virgula | DoisPontos | PontoVirgula | PontoPonto
+        | PontoPontoIgual | Retornar | Imprimir | Lancar | Senao => true,
+        _ => false,
+    }
+}
+
+/// Se vai um espaço entre `anterior` e `atual` na mesma linha. `unario` diz se
+/// `anterior` é um `-` unário; `pilha` tem o `(`, `[` ou `{` mais interno no topo.
+fn espaco(anterior: &Token, atual: &Token, unario: bool, pilha: &[Abertura]) -> bool {
+    use TokenType::*;
+    match (&anterior.tipo, &atual.tipo) {
+        (_, Virgula | PontoVirgula | DoisPontos | FechaPar | FechaColchete) => false,
+        // Depois do `{` de um bloco a linha sempre quebra; este é de um dicionário
+        (AbrePar | AbreColchete | AbreChave, _) => false,
+        (_, FechaChave) => pilha.last().is_some_and(|a| a.bloco),
+        (Ponto | PontoPonto | PontoPontoIgual, _) | (_, Ponto | PontoPonto | PontoPontoIgual) => false,
+        (Nao, _) if anterior.lexema == "!" => false,
+        (Menos, _) if unario => false,
+        // Em `para cada (x em [1, 2])`, nem `cada (` nem `em [` são chamada ou índice
+        (Ident(nome), AbrePar | AbreColchete) => nome == "cada" || nome == "em",
+        (FechaPar | FechaColchete | Imprimir, AbrePar) => false,
+        (FechaPar | FechaColchete | Texto(_), AbreColchete) => false,
+        _ => true,
+    }
+}
+
+/// Texto do token como aparece no código formatado.
+fn texto(token: &Token) -> String {
+    match &token.tipo {
+        TokenType::Texto(s) => {
+            let mut texto = String::from('"');
+            for c in s.chars() {
+                match c {
+                    '"' => texto.push_str("\\\""),
+                    '\\' => texto.push_str("\\\\"),
+                    '\n' => texto.push_str("\\n"),
+                    '\t' => texto.push_str("\\t"),
+                    '\r' => texto.push_str("\\r"),
+                    c => texto.push(c),
+                }
+            }
+            texto.push('"');
+            texto
+        }
+        _ => token.lexema.clone(),
+    }
+}
@@ -1,7 +1,24 @@
 use crate::ast::{Expr, Stmt, UnarioOp, BinOp};
+use crate::arquivos::Arquivo;
 use crate::environment::{Environment, EnvRef};
+use crate::errors::{ErrorType, LucasError, SourceLocation};
+use crate::iterador::{normalizar_indice, Intervalo, Iterador};
+use crate::limites::{Consumo, Limites, Orcamento, LIMITE_EXCEDIDO};
+use crate::modulos::Modulos;
+use crate::resolver::{Diagnostico, Resolver};
+use crate::saida::Saida;
+use crate::bytecode::Prototipo;
+use crate::compilador::Compilador;
+use crate::vm::{Celula, Vm};
 use std::rc::Rc;
 use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Até quantos arrays e dicionários aninhados `to_string_repr` escreve.
+const PROFUNDIDADE_MAXIMA_REPR: usize = 512;
 
 #[derive(Debug, Clone)]
 pub enum Value {
@@ -10,7 +27,10 @@ pub enum Value {
     Bool(bool),
     Nil,
     Array(Rc<RefCell<Vec<Value>>>),
+    Dicionario(Rc<RefCell<BTreeMap<String, Value>>>),
     Function(Rc<Function>),
+    Arquivo(Rc<RefCell<Arquivo>>),
+    Intervalo(Rc<Intervalo>),
 }
 
 impl Value {
@@ -21,31 +41,134 @@ impl Value {
             Value::Number(n) => *n != 0.0,
             Value::Text(s) => !s.is_empty(),
             Value::Array(arr) => !arr.borrow().is_empty(),
+            Value::Dicionario(dic) => !dic.borrow().is_empty(),
             Value::Function(_) => true,
+            Value::Arquivo(_) => true,
+            Value::Intervalo(_) => true,
+        }
+    }
+
+    /// Nome do tipo, como mostrado ao usuário.
+    pub fn nome_tipo(&self) -> &'static str {
+        match self {
+            Value::Number(_) => "número",
+            Value::Text(_) => "texto",
+            Value::Bool(_) => "lógico",
+            Value::Nil => "nulo",
+            Value::Array(_) => "array",
+            Value::Dicionario(_) => "dicionário",
+            Value::Function(_) => "função",
+            Value::Arquivo(_) => "arquivo",
+            Value::Intervalo(_) => "intervalo",
         }
     }
 
     pub fn to_string_repr(&self) -> String {
+        let mut texto = String::new();
+        // Sem orçamento, a escrita não tem como falhar
+        let _ = self.escrever_repr(&mut texto, &mut Vec::new(), &mut Orcamento::default());
+        texto
+    }
+
+    /// Como `to_string_repr`, mas desiste assim que o texto estoura o `orcamento`.
+    pub(crate) fn repr_limitado(&self, orcamento: &mut Orcamento) -> Result<String, ErroExecucao> {
+        let mut texto = String::new();
+        self.escrever_repr(&mut texto, &mut Vec::new(), orcamento)?;
+        Ok(texto)
+    }
+
+    /// Escreve a representação em `saida`. Um array ou dicionário que contém
+    /// a si mesmo (ou aninhado fundo demais) aparece como `[...]` ou `{...}`.
+    fn escrever_repr(&self, saida: &mut String, abertos: &mut Vec<*const ()>, orcamento: &mut Orcamento) -> Result<(), ErroExecucao> {
         match self {
             Value::Number(n) => {
                 if (n.fract() - 0.0).abs() < 1e-10 { 
-                    format!("{}", *n as i64) 
+                    saida.push_str(&format!("{}", *n as i64))
                 } else { 
-                    format!("{}", n) 
+                    saida.push_str(&format!("{}", n))
                 }
             }
-            Value::Text(s) => s.clone(),
-            Value::Bool(b) => format!("{}", b),
-            Value::Nil => "nulo".into(),
+            Value::Text(s) => saida.push_str(s),
+            Value::Bool(b) => saida.push_str(&format!("{}", b)),
+            Value::Nil => saida.push_str("nulo"),
             Value::Array(arr) => {
-                let items: Vec<String> = arr.borrow()
-                    .iter()
-                    .map(|v| v.to_string_repr())
-                    .collect();
-                format!("[{}]", items.join(", "))
+                let ptr = Rc::as_ptr(arr) as *const ();
+                if abertos.len() >= PROFUNDIDADE_MAXIMA_REPR || abertos.contains(&ptr) {
+                    saida.push_str("[...]");
+                    return orcamento.conferir(saida.len());
+                }
+                abertos.push(ptr);
+                saida.push('[');
+                for (i, item) in arr.borrow().iter().enumerate() {
+                    if i > 0 {
+                        saida.push_str(", ");
+                    }
+                    item.escrever_repr(saida, abertos, orcamento)?;
+                }
+                saida.push(']');
+                abertos.pop();
+            }
+            Value::Dicionario(dic) => {
+                let ptr = Rc::as_ptr(dic) as *const ();
+                if abertos.len() >= PROFUNDIDADE_MAXIMA_REPR || abertos.contains(&ptr) {
+                    saida.push_str("{...}");
+                    return orcamento.conferir(saida.len());
+                }
+                abertos.push(ptr);
+                saida.push('{');
+                for (i, (k, v)) in dic.borrow().iter().enumerate() {
+                    if i > 0 {
+                        saida.push_str(", ");
+                    }
+                    saida.push_str(k);
+                    saida.push_str(": ");
+                    v.escrever_repr(saida, abertos, orcamento)?;
+                }
+                saida.push('}');
+                abertos.pop();
             }
-            Value::Function(f) => format!("<fn {}>", f.name),
+            Value::Function(f) => saida.push_str(&format!("<fn {}>", f.name)),
+            Value::Arquivo(arq) => {
+                let arq = arq.borrow();
+                if arq.esta_aberto() {
+                    saida.push_str(&format!("<arquivo {}>", arq.caminho))
+                } else {
+                    saida.push_str(&format!("<arquivo {} (fechado)>", arq.caminho))
+                }
+            }
+            Value::Intervalo(intervalo) => saida.push_str(&intervalo.to_string_repr()),
         }
+        orcamento.conferir(saida.len())
+    }
+}
+
+impl From<f64> for Value {
+    fn from(n: f64) -> Self {
+        Value::Number(n)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::Text(s)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::Text(s.to_string())
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(itens: Vec<Value>) -> Self {
+        Value::Array(Rc::new(RefCell::new(itens)))
     }
 }
 
@@ -53,125 +176,722 @@ impl Value {
 pub struct Function {
     pub name: String,
     pub params: Vec<String>,
-    pub body: Vec<Stmt>,
-    pub closure: EnvRef,
+    pub(crate) corpo: Corpo,
+}
+
+/// Como uma função é executada.
+#[derive(Debug)]
+pub enum Corpo {
+    /// Função nativa, despachada pelo nome em `builtins::chamar`
+    Nativa,
+    /// Função do programa que embute o interpretador (`Interpreter::register_function`)
+    Hospedeira(Hospedeira),
+    /// Corpo interpretado sobre a árvore, com o ambiente onde foi declarada
+    Arvore(Vec<Stmt>, EnvRef),
+    /// Corpo compilado para bytecode, com as variáveis capturadas e as
+    /// globais do módulo onde foi declarada
+    Bytecode(Rc<Prototipo>, Vec<Celula>, EnvRef),
+}
+
+type FnHospedeira = dyn Fn(&[Value]) -> Result<Value, String>;
+
+/// Closure Rust chamada como função Lucas.
+pub struct Hospedeira(Box<FnHospedeira>);
+
+impl fmt::Debug for Hospedeira {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<função hospedeira>")
+    }
 }
 
 impl Function {
-    pub fn call(&self, interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, String> {
+    /// Nome e parâmetros, como em `soma(a, b)`.
+    pub fn assinatura(&self) -> String {
+        format!("{}({})", self.name, self.params.join(", "))
+    }
+
+    /// Se é uma das nativas da linguagem.
+    pub fn nativa(&self) -> bool {
+        matches!(self.corpo, Corpo::Nativa)
+    }
+
+    pub fn call(&self, interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, ErroExecucao> {
+        match self.executar(interpreter, args)? {
+            Controle::ChamadaFinal(funcao, args, _) => interpreter.chamar(funcao, args),
+            Controle::Retorno(v) => Ok(v),
+            controle => Err(controle.fora_de_laco()),
+        }
+    }
+
+    /// Executa o corpo. Uma chamada em posição final volta sem ser feita, como
+    /// `Controle::ChamadaFinal`, para `Interpreter::chamar` fazê-la sem
+    /// aumentar a pilha do Rust.
+    fn executar(&self, interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Controle, ErroExecucao> {
+        let (body, closure) = match &self.corpo {
+            Corpo::Nativa => {
+                if let Some(resultado) = crate::builtins::chamar(interpreter, &self.name, &args) {
+                    return resultado.and_then(|v| interpreter.limites.verificar(v)).map(Controle::Retorno);
+                }
+                return Err(format!("Função nativa '{}' desconhecida", self.name).into());
+            }
+            Corpo::Hospedeira(funcao) => {
+                return (funcao.0)(&args)
+                    .map_err(ErroExecucao::from)
+                    .and_then(|v| interpreter.limites.verificar(v))
+                    .map(Controle::Retorno);
+            }
+            Corpo::Bytecode(..) => return Vm::novo(interpreter).chamar(self, args).map(Controle::Retorno),
+            Corpo::Arvore(body, closure) => (body, closure),
+        };
         if args.len() != self.params.len() {
-            return Err(format!("Esperado {} argumentos mas recebeu {}", self.params.len(), args.len()));
+            return Err(format!("Esperado {} argumentos mas recebeu {}", self.params.len(), args.len()).into());
         }
-        let env = Rc::new(RefCell::new(Environment::with_enclosing(self.closure.clone())));
-        for (p, a) in self.params.iter().zip(args.into_iter()) {
+        let env = Rc::new(RefCell::new(Environment::with_enclosing(closure.clone())));
+        for (p, a) in self.params.iter().zip(args) {
             env.borrow_mut().define(p.clone(), a);
         }
-        let prev_env = interpreter.env.clone();
-        interpreter.env = env.clone();
-        
-        let mut ret_val = Value::Nil;
-        for stmt in &self.body {
-            if let Some(v) = interpreter.execute(stmt)? {
-                ret_val = v;
-                break;
+
+        match interpreter.execute_block(body, env)? {
+            Some(controle @ (Controle::Parar | Controle::Continuar)) => Err(controle.fora_de_laco()),
+            Some(controle) => Ok(controle),
+            None => Ok(Controle::Retorno(Value::Nil)),
+        }
+    }
+}
+
+/// Sinal que interrompe a execução sequencial de um bloco.
+#[derive(Debug, Clone)]
+pub enum Controle {
+    Retorno(Value),
+    /// `retornar f(...)`: a função, os argumentos já avaliados e a linha do
+    /// `retornar`. Quem chamou a função atual faz a chamada no lugar dela.
+    ChamadaFinal(Rc<Function>, Vec<Value>, usize),
+    Parar,
+    Continuar,
+}
+
+impl Controle {
+    pub fn fora_de_laco(&self) -> ErroExecucao {
+        let palavra = if matches!(self, Controle::Parar) { "parar" } else { "continuar" };
+        ErroExecucao::novo("ErroExecucao", format!("'{}' usado fora de um laço", palavra))
+    }
+}
+
+/// Tipo do erro usado por `sair(codigo)` para encerrar o script. Um script
+/// não consegue criá-lo: `lancar` troca os tipos reservados por `Erro`.
+pub const SAIR: &str = "Sair";
+
+/// Erro de execução; pode ser capturado por `tentar`/`capturar`.
+#[derive(Debug, Clone)]
+pub struct ErroExecucao {
+    pub tipo: String,
+    pub mensagem: String,
+    pub linha: Option<usize>,
+    /// Valor passado a `lancar`, quando o erro veio do próprio script
+    pub valor: Option<Value>,
+    /// Funções em execução quando o erro ocorreu, da mais externa para a
+    /// mais interna, com a linha em que cada uma estava
+    pub rastro: Option<Rc<Vec<(String, usize)>>>,
+}
+
+impl ErroExecucao {
+    pub fn novo(tipo: &str, mensagem: impl Into<String>) -> Self {
+        Self {
+            tipo: tipo.to_string(),
+            mensagem: mensagem.into(),
+            linha: None,
+            valor: None,
+            rastro: None,
+        }
+    }
+
+    /// Pedido de `sair(codigo)`; atravessa os blocos `capturar` até `Interpreter::run`.
+    pub fn saida(codigo: i32) -> Self {
+        let mut erro = Self::novo(SAIR, format!("sair({})", codigo));
+        erro.valor = Some(Value::Number(codigo as f64));
+        erro
+    }
+
+    /// Código pedido por `sair`, se o erro veio de lá.
+    pub fn codigo_saida(&self) -> Option<i32> {
+        match (&self.valor, self.tipo == SAIR) {
+            (Some(Value::Number(codigo)), true) => Some(*codigo as i32),
+            _ => None,
+        }
+    }
+
+    pub fn recursao_maxima(limite: usize) -> Self {
+        Self::novo("ErroRecursao", format!("recursão máxima excedida (limite de {} chamadas)", limite))
+    }
+
+    /// Erro criado por `lancar valor;`. Um dicionário com `mensagem`/`tipo`
+    /// (como o recebido em `capturar`) preserva esses campos ao ser relançado;
+    /// os tipos reservados ao interpretador (`Sair`, `LimiteExcedido`) viram `Erro`.
+    pub fn lancado(valor: Value) -> Self {
+        let mut erro = Self::novo("Erro", valor.to_string_repr());
+        if let Value::Dicionario(dic) = &valor {
+            let dic = dic.borrow();
+            if let Some(m) = dic.get("mensagem") {
+                erro.mensagem = m.to_string_repr();
+            }
+            if let Some(Value::Text(t)) = dic.get("tipo") {
+                if t != SAIR && t != LIMITE_EXCEDIDO {
+                    erro.tipo = t.clone();
+                }
             }
+            if let Some(Value::Number(l)) = dic.get("linha") {
+                erro.linha = Some(*l as usize);
+            }
+            if let Some(v) = dic.get("valor") {
+                erro.valor = Some(v.clone());
+            }
+        }
+        if erro.valor.is_none() {
+            erro.valor = Some(valor);
+        }
+        erro
+    }
+
+    fn na_linha(mut self, linha: usize) -> Self {
+        if self.linha.is_none() {
+            self.linha = Some(linha);
         }
-        interpreter.env = prev_env;
-        Ok(ret_val)
+        self
+    }
+
+    /// Valor recebido pela variável de `capturar (e)`.
+    pub fn to_value(&self) -> Value {
+        let mut mapa = BTreeMap::new();
+        mapa.insert("mensagem".to_string(), Value::Text(self.mensagem.clone()));
+        mapa.insert("tipo".to_string(), Value::Text(self.tipo.clone()));
+        mapa.insert("linha".to_string(), self.linha.map_or(Value::Nil, |l| Value::Number(l as f64)));
+        mapa.insert("valor".to_string(), self.valor.clone().unwrap_or(Value::Nil));
+        Value::Dicionario(Rc::new(RefCell::new(mapa)))
+    }
+}
+
+impl From<String> for ErroExecucao {
+    fn from(mensagem: String) -> Self {
+        Self::novo("ErroExecucao", mensagem)
     }
 }
 
+impl From<&str> for ErroExecucao {
+    fn from(mensagem: &str) -> Self {
+        Self::novo("ErroExecucao", mensagem)
+    }
+}
+
+/// Função em execução, para o limite de recursão e o rastreamento de erros.
+pub struct Chamada {
+    pub funcao: Rc<Function>,
+    /// Linha de onde a função foi chamada
+    pub linha: usize,
+}
+
+/// Profundidade padrão de `Interpreter::profundidade_maxima`.
+pub const PROFUNDIDADE_MAXIMA: usize = 1000;
+
+/// Pilha livre que cada chamada de função precisa encontrar; com menos,
+/// `Interpreter::chamar` continua num segmento novo de `SEGMENTO_PILHA` bytes.
+/// Cobre os quadros do Rust de uma chamada Lucas com expressões aninhadas.
+const RESERVA_PILHA: usize = 256 * 1024;
+const SEGMENTO_PILHA: usize = 4 * 1024 * 1024;
+
+/// Interpretador de Lucas. Pode rodar em qualquer thread: as chamadas de
+/// função aumentam a pilha do Rust conforme precisam, em segmentos alocados
+/// à parte, e a recursão é limitada só por `set_profundidade_maxima`. Só a
+/// análise de código aninhado demais (milhares de parênteses) usa a pilha
+/// da própria thread.
 pub struct Interpreter {
-    pub globals: EnvRef,
-    pub env: EnvRef,
-    pub fonte: String,
+    pub(crate) globals: EnvRef,
+    pub(crate) env: EnvRef,
+    pub(crate) fonte: String,
+    /// Prompt mostrado por `ler()` quando o script não informa um
+    pub(crate) prompt_leitura: Option<String>,
+    /// Arquivo em execução; importações são resolvidas a partir do seu diretório
+    pub(crate) caminho: Option<PathBuf>,
+    pub(crate) modulos: Modulos,
+    /// Permite redeclarar variáveis (não constantes) no mesmo escopo, como no REPL
+    pub(crate) permitir_redeclaracao: bool,
+    /// Compila para bytecode e executa na `Vm` em vez de percorrer a árvore
+    pub(crate) usar_vm: bool,
+    /// Funções em execução, da mais externa para a mais interna
+    pub(crate) pilha_chamadas: Vec<Chamada>,
+    /// Chamadas aninhadas permitidas antes de "recursão máxima excedida"
+    pub(crate) profundidade_maxima: usize,
+    /// Linha do comando em execução
+    linha: usize,
+    pub(crate) limites: Limites,
+    pub(crate) consumo: Consumo,
+    /// Destino de `imprimir` e dos prompts de `ler()`
+    pub(crate) saida: Saida,
+    /// Destino das mensagens de erro e dos avisos
+    pub(crate) saida_erros: Saida,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Interpreter {
     pub fn new() -> Self { 
-        let g = Rc::new(RefCell::new(Environment::new()));
-        let it = Self { 
+        // As nativas ficam num escopo acima das globais, que podem redefinir seus nomes
+        let nativas = Rc::new(RefCell::new(Environment::new()));
+        crate::builtins::registrar(&nativas);
+        // Argumentos da linha de comando; quem executa o script os define como global
+        nativas.borrow_mut().define("argumentos".into(), Value::from(Vec::new()));
+        let g = Rc::new(RefCell::new(Environment::with_enclosing(nativas)));
+        Self { 
             globals: g.clone(), 
             env: g.clone(),
             fonte: String::new(),
+            prompt_leitura: None,
+            caminho: None,
+            modulos: Modulos::default(),
+            permitir_redeclaracao: false,
+            usar_vm: false,
+            pilha_chamadas: Vec::new(),
+            profundidade_maxima: PROFUNDIDADE_MAXIMA,
+            linha: 0,
+            limites: Limites::default(),
+            consumo: Consumo::default(),
+            saida: Saida::Padrao,
+            saida_erros: Saida::ErroPadrao,
+        }
+    }
+
+    /// Executa `source`, mostrando os erros na saída de erros. Devolve o
+    /// código pedido por `sair(codigo)`, ou 0 se o script chegou ao fim.
+    pub fn run(&mut self, source: &str) -> Result<i32, LucasError> {
+        self.fonte = source.to_string();
+        self.consumo = Consumo::default();
+        let stmts = match self.analisar(source) {
+            Ok(stmts) => stmts,
+            Err(erros) => {
+                for erro in &erros {
+                    self.reportar_diagnostico(erro);
+                }
+                return Err(self.diagnostico_lucas(&erros[0]));
+            }
         };
-        
-        it.globals.borrow_mut().define("imprimir".into(), Value::Function(Rc::new(Function {
-            name: "imprimir".into(),
-            params: vec![],
-            body: vec![],
-            closure: it.globals.clone(),
-        })));
-        
-        it.globals.borrow_mut().define("comprimento".into(), Value::Function(Rc::new(Function {
-            name: "comprimento".into(),
-            params: vec!["x".into()],
-            body: vec![],
-            closure: it.globals.clone(),
-        })));
-        
-        it.globals.borrow_mut().define("maiuscula".into(), Value::Function(Rc::new(Function {
-            name: "maiuscula".into(),
-            params: vec!["x".into()],
-            body: vec![],
-            closure: it.globals.clone(),
-        })));
-        
-        it.globals.borrow_mut().define("minuscula".into(), Value::Function(Rc::new(Function {
-            name: "minuscula".into(),
-            params: vec!["x".into()],
-            body: vec![],
-            closure: it.globals.clone(),
-        })));
-        
-        it
-    }
-
-    pub fn run(&mut self, source: &str) {
+        match self.executar(&stmts) {
+            Ok(()) => Ok(0),
+            Err(e) => match e.codigo_saida() {
+                Some(codigo) => Ok(codigo),
+                None => {
+                    self.reportar_erro(&e);
+                    Err(self.erro_lucas(&e))
+                }
+            },
+        }
+    }
+
+    /// Analisa e resolve `source` sem executar, mostrando os erros encontrados.
+    /// Devolve se o código está correto.
+    pub fn verificar(&mut self, source: &str) -> bool {
         self.fonte = source.to_string();
-        let tokens = crate::lexer::Lexer::new(source).tokenize();
+        match self.analisar(source) {
+            Ok(_) => true,
+            Err(erros) => {
+                for erro in &erros {
+                    self.reportar_diagnostico(erro);
+                }
+                false
+            }
+        }
+    }
+
+    /// Como `verificar`, mas sem resolver nomes: procura só erros léxicos ou,
+    /// com `gramatica`, também de sintaxe. Devolve se o código está correto.
+    pub fn verificar_sintaxe(&mut self, source: &str, gramatica: bool) -> bool {
+        self.fonte = source.to_string();
+        let mut lexer = crate::lexer::Lexer::new(source);
+        let tokens = lexer.tokenize();
+        let mut erros = lexer.erros;
+        if erros.is_empty() && gramatica {
+            let mut parser = crate::parser::Parser::new(tokens);
+            parser.parse();
+            erros.extend(parser.erro);
+        }
+        for erro in &erros {
+            self.reportar_diagnostico(erro);
+        }
+        erros.is_empty()
+    }
+
+    /// Funções globais cujo nome começa com `teste_`, em ordem alfabética:
+    /// as que `lucas testar` chama depois de executar o arquivo.
+    pub fn funcoes_de_teste(&self) -> Vec<String> {
+        let globais = self.globals.borrow().get_all_variables();
+        globais
+            .into_iter()
+            .filter(|(nome, valor)| nome.starts_with("teste_") && matches!(valor, Value::Function(_)))
+            .map(|(nome, _)| nome)
+            .collect()
+    }
+
+    /// Executa `source` e devolve o valor da última expressão (ou `nulo`),
+    /// sem mostrar os erros.
+    pub fn eval(&mut self, source: &str) -> Result<Value, LucasError> {
+        self.fonte = source.to_string();
+        self.consumo = Consumo::default();
+        let stmts = self.analisar(source).map_err(|erros| self.diagnostico_lucas(&erros[0]))?;
+        self.executar_com_valor(&stmts).map_err(|e| self.erro_lucas(&e))
+    }
+
+    pub fn set_global(&mut self, name: &str, value: Value) {
+        self.globals.borrow_mut().define(name.to_string(), value);
+    }
+
+    pub fn get_global(&self, name: &str) -> Option<Value> {
+        self.globals.borrow().get(name).ok()
+    }
+
+    /// Chama a função global `name`, como `name(args...)` no script.
+    pub fn call_function(&mut self, name: &str, args: Vec<Value>) -> Result<Value, LucasError> {
+        let funcao = match self.get_global(name) {
+            Some(Value::Function(f)) => f,
+            Some(_) => return Err(self.erro_lucas(&format!("'{}' não é uma função", name).into())),
+            None => return Err(self.erro_lucas(&format!("Função '{}' não definida", name).into())),
+        };
+        self.chamar(funcao, args).map_err(|e| self.erro_lucas(&e))
+    }
+
+    /// Registra uma closure Rust como a função global `name`. Um `Err` vira
+    /// um erro de execução no script, que `tentar`/`capturar` pode tratar.
+    pub fn register_function<F>(&mut self, name: &str, funcao: F)
+    where
+        F: Fn(&[Value]) -> Result<Value, String> + 'static,
+    {
+        let funcao = Function {
+            name: name.to_string(),
+            params: Vec::new(),
+            corpo: Corpo::Hospedeira(Hospedeira(Box::new(funcao))),
+        };
+        self.set_global(name, Value::Function(Rc::new(funcao)));
+    }
+
+    /// Troca os limites das próximas execuções; veja `Limites`.
+    pub fn set_limites(&mut self, limites: Limites) {
+        self.limites = limites;
+    }
+
+    pub fn limites(&self) -> &Limites {
+        &self.limites
+    }
+
+    /// Troca o destino de `imprimir` e dos prompts de `ler()`.
+    pub fn set_saida(&mut self, saida: Saida) {
+        self.saida = saida;
+    }
+
+    pub fn saida_mut(&mut self) -> &mut Saida {
+        &mut self.saida
+    }
+
+    /// Troca o destino das mensagens de erro e dos avisos.
+    pub fn set_saida_erros(&mut self, saida: Saida) {
+        self.saida_erros = saida;
+    }
+
+    pub fn saida_erros_mut(&mut self) -> &mut Saida {
+        &mut self.saida_erros
+    }
+
+    /// Compila para bytecode e executa na máquina virtual em vez de
+    /// percorrer a árvore.
+    pub fn set_usar_vm(&mut self, usar_vm: bool) {
+        self.usar_vm = usar_vm;
+    }
+
+    /// Arquivo do código executado; as importações são resolvidas a partir
+    /// do seu diretório (sem ele, do diretório atual).
+    pub fn set_caminho(&mut self, caminho: Option<PathBuf>) {
+        self.caminho = caminho;
+    }
+
+    /// Chamadas aninhadas permitidas antes de "recursão máxima excedida".
+    pub fn set_profundidade_maxima(&mut self, profundidade: usize) {
+        self.profundidade_maxima = profundidade;
+    }
+
+    /// Analisa `source` e resolve suas variáveis em relação ao ambiente atual.
+    /// Os avisos são mostrados aqui; os erros impedem a execução.
+    pub(crate) fn analisar(&mut self, source: &str) -> Result<Vec<Stmt>, Vec<Diagnostico>> {
+        let mut lexer = crate::lexer::Lexer::new(source);
+        let tokens = lexer.tokenize();
+        if !lexer.erros.is_empty() {
+            return Err(lexer.erros);
+        }
         let mut parser = crate::parser::Parser::new(tokens);
-        let stmts = parser.parse();
+        let mut stmts = parser.parse();
+        if let Some(erro) = parser.erro {
+            return Err(vec![erro]);
+        }
+
+        let mut resolver = Resolver::novo(self.env.borrow().nomes_visiveis());
+        resolver.redeclarar_globais = self.permitir_redeclaracao;
+        resolver.resolver(&mut stmts);
+        for aviso in &resolver.avisos {
+            let onde = match self.caminho.as_ref().and_then(|c| c.file_name()) {
+                Some(arquivo) => format!("em {}, linha {}", arquivo.to_string_lossy(), aviso.linha),
+                None => format!("na linha {}", aviso.linha),
+            };
+            crate::errors::aviso(&mut self.saida_erros, &onde, &aviso.mensagem);
+        }
+        if resolver.erros.is_empty() {
+            Ok(stmts)
+        } else {
+            Err(resolver.erros)
+        }
+    }
+
+    /// Executa `stmts` no ambiente atual, parando no primeiro erro.
+    pub(crate) fn executar(&mut self, stmts: &[Stmt]) -> Result<(), ErroExecucao> {
+        if self.usar_vm {
+            let proto = Rc::new(Compilador::compilar(stmts, self.limites.conta_passos()));
+            return Vm::novo(self).executar_programa(proto);
+        }
+        for s in stmts {
+            if let Some(controle @ (Controle::Parar | Controle::Continuar)) = self.execute(s)? {
+                let linha = s.linha().unwrap_or(self.linha);
+                let anterior = std::mem::replace(&mut self.linha, linha);
+                let rastro = Some(Rc::new(self.rastro()));
+                self.linha = anterior;
+                return Err(ErroExecucao { rastro, ..controle.fora_de_laco() }.na_linha(linha));
+            }
+        }
+        Ok(())
+    }
+
+    /// Como `executar`, mas devolve o valor do último comando quando ele é uma expressão.
+    pub(crate) fn executar_com_valor(&mut self, stmts: &[Stmt]) -> Result<Value, ErroExecucao> {
+        let Some((ultimo @ Stmt::ExprStmt(expr, _), resto)) = stmts.split_last() else {
+            return self.executar(stmts).map(|_| Value::Nil);
+        };
+        self.executar(resto)?;
+        self.no_comando(ultimo, |interp| interp.evaluate(expr))
+    }
+
+    fn localizar(&self, linha: usize) -> Option<SourceLocation> {
+        SourceLocation::na_linha(&self.fonte, linha)
+    }
+
+    pub(crate) fn reportar_erro(&mut self, erro: &ErroExecucao) {
+        let _ = self.erro_lucas(erro).exibir_em(&mut self.saida_erros);
+    }
+
+    pub(crate) fn reportar_diagnostico(&mut self, diagnostico: &Diagnostico) {
+        let _ = self.diagnostico_lucas(diagnostico).exibir_em(&mut self.saida_erros);
+    }
+
+    pub(crate) fn erro_lucas(&self, erro: &ErroExecucao) -> LucasError {
+        let mensagem = if erro.tipo == "ErroExecucao" {
+            erro.mensagem.clone()
+        } else {
+            format!("{}: {}", erro.tipo, erro.mensagem)
+        };
+        let mut lucas_erro = LucasError::novo(ErrorType::RuntimeError, mensagem);
+
+        if let Some(loc) = erro.linha.and_then(|l| self.localizar(l)) {
+            lucas_erro = lucas_erro.com_localizacao(loc);
+        }
+        if let Some(rastro) = &erro.rastro {
+            lucas_erro = lucas_erro.com_rastro(formatar_rastro(rastro));
+        }
+        lucas_erro
+    }
+
+    pub(crate) fn diagnostico_lucas(&self, diagnostico: &Diagnostico) -> LucasError {
+        diagnostico.to_lucas(&self.fonte)
+    }
+
+    /// Executa `stmts` em `env`, restaurando o ambiente anterior mesmo em caso de erro.
+    pub(crate) fn execute_block(&mut self, stmts: &[Stmt], env: EnvRef) -> Result<Option<Controle>, ErroExecucao> {
+        let prev = std::mem::replace(&mut self.env, env);
+        let mut resultado = Ok(None);
         for s in stmts {
-            if let Err(e) = self.execute(&s) {
-                eprintln!("Erro: {}", e);
+            resultado = self.execute(s);
+            if !matches!(resultado, Ok(None)) {
                 break;
             }
         }
+        self.env = prev;
+        resultado
+    }
+
+    fn novo_escopo(&self) -> EnvRef {
+        Rc::new(RefCell::new(Environment::with_enclosing(self.env.clone())))
+    }
+
+    pub(crate) fn execute(&mut self, stmt: &Stmt) -> Result<Option<Controle>, ErroExecucao> {
+        self.no_comando(stmt, |interp| interp.execute_stmt(stmt))
+    }
+
+    /// Roda `f` como o comando `stmt`: conta o passo e situa os erros na sua linha.
+    #[inline]
+    fn no_comando<T>(
+        &mut self,
+        stmt: &Stmt,
+        f: impl FnOnce(&mut Self) -> Result<T, ErroExecucao>,
+    ) -> Result<T, ErroExecucao> {
+        let anterior = self.linha;
+        let linha = stmt.linha();
+        if let Some(linha) = linha {
+            self.linha = linha;
+        }
+        let resultado = match (self.passo().and_then(|_| f(self)), linha) {
+            (Err(e), Some(linha)) => Err(self.situar_erro(e, linha)),
+            (resultado, _) => resultado,
+        };
+        self.linha = anterior;
+        resultado
+    }
+
+    /// Completa o erro de um comando com o rastro e a linha.
+    #[cold]
+    fn situar_erro(&self, mut erro: ErroExecucao, linha: usize) -> ErroExecucao {
+        if erro.rastro.is_none() {
+            erro.rastro = Some(Rc::new(self.rastro()));
+        }
+        erro.na_linha(linha)
+    }
+
+    /// Conta um comando executado, conferindo o orçamento de passos e o tempo.
+    pub(crate) fn passo(&mut self) -> Result<(), ErroExecucao> {
+        if !self.limites.conta_passos() {
+            return Ok(());
+        }
+        self.consumo.passo(&self.limites)
+    }
+
+    /// `valor.to_string_repr()`, dentro dos limites de tamanho de texto e de tempo.
+    pub(crate) fn texto(&mut self, valor: &Value) -> Result<String, ErroExecucao> {
+        valor.repr_limitado(&mut self.consumo.orcamento(&self.limites))
+    }
+
+    /// Escreve `texto` e uma quebra de linha na saída, dentro do limite de bytes.
+    pub(crate) fn escrever_linha(&mut self, texto: &str) -> Result<(), ErroExecucao> {
+        self.consumo.saida(&self.limites, texto.len() + 1)?;
+        self.saida
+            .write_all(format!("{}\n", texto).as_bytes())
+            .map_err(|e| format!("Erro ao escrever na saída: {}", e).into())
+    }
+
+    /// Escreve `texto` sem quebra de linha, como o prompt de `ler()`.
+    pub(crate) fn escrever(&mut self, texto: &str) -> Result<(), ErroExecucao> {
+        self.consumo.saida(&self.limites, texto.len())?;
+        self.saida
+            .write_all(texto.as_bytes())
+            .and_then(|_| self.saida.flush())
+            .map_err(|e| format!("Erro ao escrever na saída: {}", e).into())
+    }
+
+    /// Chama `funcao` registrando-a na pilha de chamadas. As nativas não
+    /// entram na pilha.
+    pub(crate) fn chamar(&mut self, funcao: Rc<Function>, args: Vec<Value>) -> Result<Value, ErroExecucao> {
+        if matches!(funcao.corpo, Corpo::Nativa | Corpo::Hospedeira(_)) {
+            return funcao.call(self, args);
+        }
+        if self.pilha_chamadas.len() >= self.profundidade_maxima {
+            return Err(ErroExecucao::recursao_maxima(self.profundidade_maxima));
+        }
+        self.pilha_chamadas.push(Chamada { funcao: funcao.clone(), linha: self.linha });
+        let resultado = stacker::maybe_grow(RESERVA_PILHA, SEGMENTO_PILHA, || self.executar_chamada(funcao, args));
+        self.pilha_chamadas.pop();
+        resultado
+    }
+
+    /// Executa a chamada que está no topo da pilha de chamadas, seguindo as
+    /// chamadas finais que ela fizer.
+    fn executar_chamada(&mut self, funcao: Rc<Function>, args: Vec<Value>) -> Result<Value, ErroExecucao> {
+        let mut funcao = funcao;
+        let mut args = args;
+        loop {
+            match funcao.executar(self, args) {
+                // A chamada final ocupa o lugar da atual na pilha de chamadas
+                Ok(Controle::ChamadaFinal(proxima, proximos, _)) if matches!(proxima.corpo, Corpo::Arvore(..)) => {
+                    self.pilha_chamadas.last_mut().expect("chamada em execução").funcao = proxima.clone();
+                    funcao = proxima;
+                    args = proximos;
+                }
+                Ok(Controle::ChamadaFinal(proxima, proximos, _)) => break proxima.call(self, proximos),
+                Ok(Controle::Retorno(v)) => break Ok(v),
+                Ok(controle) => break Err(controle.fora_de_laco()),
+                Err(e) => break Err(e),
+            }
+        }
     }
 
-    pub fn execute(&mut self, stmt: &Stmt) -> Result<Option<Value>, String> {
+    /// Faz ali mesmo a chamada final vinda de um bloco `tentar`, para que
+    /// seus erros sejam capturados e o `finalmente` rode depois dela.
+    fn concluir_chamada_final(
+        &mut self,
+        resultado: Result<Option<Controle>, ErroExecucao>,
+    ) -> Result<Option<Controle>, ErroExecucao> {
+        let Ok(Some(Controle::ChamadaFinal(funcao, args, linha))) = resultado else {
+            return resultado;
+        };
+        let anterior = std::mem::replace(&mut self.linha, linha);
+        let valor = self.chamar(funcao, args);
+        self.linha = anterior;
+        valor.map(|v| Some(Controle::Retorno(v)))
+    }
+
+    /// Avalia a função e os argumentos de uma chamada.
+    fn avaliar_chamada(&mut self, funcao: &Expr, args: &[Expr]) -> Result<(Rc<Function>, Vec<Value>), ErroExecucao> {
+        let funcao = self.evaluate(funcao)?;
+        let mut valores = Vec::with_capacity(args.len());
+        for a in args {
+            valores.push(self.evaluate(a)?);
+        }
+        match funcao {
+            Value::Function(f) => Ok((f, valores)),
+            _ => Err("Tentativa de chamar algo que não é função".into()),
+        }
+    }
+
+    /// Pilha de chamadas atual no formato de `ErroExecucao::rastro`.
+    pub(crate) fn rastro(&self) -> Vec<(String, usize)> {
+        let nomes = std::iter::once("<principal>").chain(self.pilha_chamadas.iter().map(|c| c.funcao.name.as_str()));
+        let linhas = self.pilha_chamadas.iter().map(|c| c.linha).chain(std::iter::once(self.linha));
+        nomes.zip(linhas).map(|(nome, linha)| (nome.to_string(), linha)).collect()
+    }
+
+    fn execute_stmt(&mut self, stmt: &Stmt) -> Result<Option<Controle>, ErroExecucao> {
         match stmt {
-            Stmt::ExprStmt(e) => { 
+            Stmt::ExprStmt(e, _) => { 
                 self.evaluate(e)?; 
                 Ok(None) 
             }
-            Stmt::Imprimir(e) => {
+            Stmt::Imprimir(e, _) => {
                 let v = self.evaluate(e)?;
-                println!("{}", v.to_string_repr());
+                let texto = self.texto(&v)?;
+                self.escrever_linha(&texto)?;
                 Ok(None)
             }
-            Stmt::VarDecl(name, init) => {
+            Stmt::VarDecl(name, init, _) => {
                 let v = self.evaluate(init)?;
-                self.env.borrow_mut().define(name.clone(), v);
+                self.declarar(name, v, false)?;
                 Ok(None)
             }
-            Stmt::Bloco(stmts) => {
-                let new_env = Rc::new(RefCell::new(Environment::with_enclosing(self.env.clone())));
-                let prev = self.env.clone();
-                self.env = new_env;
-                
-                for s in stmts {
-                    let result = self.execute(s)?;
-                    if result.is_some() {
-                        self.env = prev;
-                        return Ok(result);
-                    }
-                }
-                self.env = prev;
+            Stmt::Constante(name, init, _) => {
+                let v = self.evaluate(init)?;
+                self.declarar(name, v, true)?;
                 Ok(None)
             }
-            Stmt::If(cond, then_branch, else_branch) => {
+            Stmt::Bloco(stmts) if stmts.iter().any(Stmt::declara) => {
+                let env = self.novo_escopo();
+                self.execute_block(stmts, env)
+            }
+            Stmt::Bloco(stmts) => {
+                let env = self.env.clone();
+                self.execute_block(stmts, env)
+            }
+            Stmt::If(cond, then_branch, else_branch, _) => {
                 let c = self.evaluate(cond)?;
                 if c.is_truthy() {
                     self.execute(then_branch)
@@ -181,67 +901,164 @@ impl Interpreter {
                     Ok(None)
                 }
             }
-            Stmt::While(cond, body) => {
+            Stmt::While(cond, body, _) => {
                 while self.evaluate(cond)?.is_truthy() {
-                    if let Some(v) = self.execute(body)? {
-                        return Ok(Some(v));
+                    match self.execute(body)? {
+                        Some(Controle::Parar) => break,
+                        Some(Controle::Continuar) | None => {}
+                        Some(retorno) => return Ok(Some(retorno)),
                     }
                 }
                 Ok(None)
             }
             Stmt::For(init, cond, incr, body) => {
-                let new_env = Rc::new(RefCell::new(Environment::with_enclosing(self.env.clone())));
-                let prev = self.env.clone();
-                self.env = new_env;
-                
-                if let Some(init_stmt) = init {
-                    self.execute(init_stmt)?;
-                }
-                
-                loop {
-                    if let Some(cond_expr) = cond {
-                        if !self.evaluate(cond_expr)?.is_truthy() {
-                            break;
+                let env = self.novo_escopo();
+                let prev = std::mem::replace(&mut self.env, env);
+                let resultado = self.execute_for(init, cond, incr, body);
+                self.env = prev;
+                resultado
+            }
+            Stmt::ParaCada(indice, item, iteravel, body, _) => {
+                let mut iterador = Iterador::novo(&self.evaluate(iteravel)?)?;
+                let por_chave = iterador.por_chave();
+
+                while let Some((chave, valor)) = iterador.proximo()? {
+                    // Escopo novo por volta: closures capturam o item daquela iteração
+                    let env = self.novo_escopo();
+                    match indice {
+                        Some(nome_indice) => {
+                            env.borrow_mut().define(nome_indice.clone(), chave);
+                            env.borrow_mut().define(item.clone(), valor);
+                        }
+                        None => {
+                            let v = if por_chave { chave } else { valor };
+                            env.borrow_mut().define(item.clone(), v);
                         }
                     }
-                    
-                    if let Some(v) = self.execute(body)? {
-                        self.env = prev;
-                        return Ok(Some(v));
-                    }
-                    
-                    if let Some(incr_expr) = incr {
-                        self.evaluate(incr_expr)?;
+
+                    match self.execute_block(std::slice::from_ref(body), env)? {
+                        Some(Controle::Parar) => break,
+                        Some(Controle::Continuar) | None => {}
+                        Some(retorno) => return Ok(Some(retorno)),
                     }
                 }
-                
-                self.env = prev;
                 Ok(None)
             }
             Stmt::FuncDecl(name, params, body) => {
                 let func = Function {
                     name: name.clone(),
                     params: params.clone(),
-                    body: body.clone(),
-                    closure: self.env.clone(),
+                    corpo: Corpo::Arvore(body.clone(), self.env.clone()),
                 };
-                self.env.borrow_mut().define(name.clone(), Value::Function(Rc::new(func)));
+                self.declarar(name, Value::Function(Rc::new(func)), false)?;
                 Ok(None)
             }
-            Stmt::Return(expr_opt) => {
+            Stmt::Return(Some(Expr::Chamada(funcao, args)), linha) => {
+                let (funcao, args) = self.avaliar_chamada(funcao, args)?;
+                Ok(Some(Controle::ChamadaFinal(funcao, args, *linha)))
+            }
+            Stmt::Return(expr_opt, _) => {
                 let v = if let Some(e) = expr_opt { 
                     self.evaluate(e)? 
                 } else { 
                     Value::Nil 
                 };
-                Ok(Some(v))
+                Ok(Some(Controle::Retorno(v)))
+            }
+            Stmt::Tentar(corpo, captura, finalmente) => {
+                let env = self.novo_escopo();
+                let resultado = self.execute_block(corpo, env);
+                let mut resultado = self.concluir_chamada_final(resultado);
+
+                if let (Err(erro), Some(cap)) = (&resultado, captura) {
+                    if !self.limites.fatal(erro) {
+                        let env = self.novo_escopo();
+                        if let Some(nome) = &cap.nome {
+                            env.borrow_mut().define(nome.clone(), erro.to_value());
+                        }
+                        let capturado = self.execute_block(&cap.corpo, env);
+                        resultado = self.concluir_chamada_final(capturado);
+                    }
+                }
+
+                if let Some(fin) = finalmente {
+                    // Um `retornar` ou erro dentro de `finalmente` prevalece
+                    let env = self.novo_escopo();
+                    if let Some(controle) = self.execute_block(fin, env)? {
+                        return Ok(Some(controle));
+                    }
+                }
+                resultado
+            }
+            Stmt::Lancar(expr, _) => {
+                let v = self.evaluate(expr)?;
+                // A mensagem do erro é o valor como texto: confere antes o tamanho dela
+                self.texto(&v)?;
+                Err(ErroExecucao::lancado(v))
+            }
+            Stmt::Importar(caminho, apelido, _) => {
+                let modulo = self.carregar_modulo(caminho)?;
+                self.declarar(apelido, modulo, false)?;
+                Ok(None)
+            }
+            Stmt::DeImportar(caminho, nomes, _) => {
+                let modulo = self.carregar_modulo(caminho)?;
+                let Value::Dicionario(exportados) = modulo else { unreachable!() };
+                for nome in nomes {
+                    let valor = exportados.borrow().get(nome).cloned().ok_or_else(|| {
+                        ErroExecucao::novo(
+                            "ErroImportacao",
+                            format!("O módulo '{}' não exporta '{}'", caminho, nome),
+                        )
+                    })?;
+                    self.declarar(nome, valor, false)?;
+                }
+                Ok(None)
+            }
+            Stmt::Exportar(nomes, decl) => {
+                if let Some(decl) = decl {
+                    self.execute(decl)?;
+                }
+                self.marcar_exportados(nomes);
+                Ok(None)
+            }
+            Stmt::Break => Ok(Some(Controle::Parar)),
+            Stmt::Continue => Ok(Some(Controle::Continuar)),
+        }
+    }
+
+    fn declarar(&mut self, nome: &str, valor: Value, constante: bool) -> Result<(), ErroExecucao> {
+        let redeclarar = self.permitir_redeclaracao;
+        self.env.borrow_mut().declarar(nome, valor, constante, redeclarar)
+    }
+
+    fn execute_for(&mut self, init: &Option<Box<Stmt>>, cond: &Option<Expr>, incr: &Option<Expr>, body: &Stmt) -> Result<Option<Controle>, ErroExecucao> {
+        if let Some(init_stmt) = init {
+            self.execute(init_stmt)?;
+        }
+
+        loop {
+            if let Some(cond_expr) = cond {
+                if !self.evaluate(cond_expr)?.is_truthy() {
+                    break;
+                }
+            }
+
+            match self.execute(body)? {
+                Some(Controle::Parar) => break,
+                // `continuar` ainda executa o incremento
+                Some(Controle::Continuar) | None => {}
+                Some(retorno) => return Ok(Some(retorno)),
+            }
+
+            if let Some(incr_expr) = incr {
+                self.evaluate(incr_expr)?;
             }
-            Stmt::Break => Ok(None),
-            Stmt::Continue => Ok(None),
         }
+        Ok(None)
     }
 
-    pub fn evaluate(&mut self, expr: &Expr) -> Result<Value, String> {
+    pub(crate) fn evaluate(&mut self, expr: &Expr) -> Result<Value, ErroExecucao> {
         match expr {
             Expr::Numero(n) => Ok(Value::Number(*n)),
             Expr::Texto(s) => Ok(Value::Text(s.clone())),
@@ -254,199 +1071,245 @@ impl Interpreter {
                 }
                 Ok(Value::Array(Rc::new(RefCell::new(arr))))
             }
+            Expr::Dicionario(pares) => {
+                let mut mapa = BTreeMap::new();
+                for (chave_expr, valor_expr) in pares {
+                    let chave = match self.evaluate(chave_expr)? {
+                        Value::Text(s) => s,
+                        _ => return Err("Chaves de dicionário devem ser textos".into()),
+                    };
+                    let valor = self.evaluate(valor_expr)?;
+                    mapa.insert(chave, valor);
+                }
+                Ok(Value::Dicionario(Rc::new(RefCell::new(mapa))))
+            }
             Expr::Index(arr_expr, idx_expr) => {
                 let arr_val = self.evaluate(arr_expr)?;
                 let idx_val = self.evaluate(idx_expr)?;
-                
-                match (arr_val, idx_val) {
-                    (Value::Array(arr), Value::Number(idx)) => {
-                        let i = idx as usize;
-                        let borrowed = arr.borrow();
-                        if i < borrowed.len() {
-                            Ok(borrowed[i].clone())
-                        } else {
-                            Err(format!("Índice {} fora dos limites (tamanho: {})", i, borrowed.len()))
-                        }
-                    }
-                    (Value::Text(s), Value::Number(idx)) => {
-                        let i = idx as usize;
-                        if i < s.len() {
-                            Ok(Value::Text(s.chars().nth(i).unwrap().to_string()))
-                        } else {
-                            Err(format!("Índice {} fora dos limites (tamanho: {})", i, s.len()))
-                        }
-                    }
-                    _ => Err("Indexação requer array/string e índice numérico".into())
-                }
+                indexar(arr_val, idx_val)
             }
-            Expr::Atribuir(name, value_expr) => {
+            Expr::Atribuir(name, value_expr, resolucao) => {
                 let value = self.evaluate(value_expr)?;
-                self.env.borrow_mut().assign(name, value.clone())?;
+                match resolucao {
+                    Some((profundidade, posicao)) => {
+                        self.env.borrow_mut().assign_at(*profundidade, *posicao, name, value.clone())?
+                    }
+                    None => self.env.borrow_mut().assign(name, value.clone())?,
+                }
                 Ok(value)
             }
             Expr::AtribuirIndex(arr_expr, idx_expr, value_expr) => {
                 let arr_val = self.evaluate(arr_expr)?;
                 let idx_val = self.evaluate(idx_expr)?;
                 let new_val = self.evaluate(value_expr)?;
-                
-                match (arr_val, idx_val) {
-                    (Value::Array(arr), Value::Number(idx)) => {
-                        let i = idx as usize;
-                        let mut borrowed = arr.borrow_mut();
-                        if i < borrowed.len() {
-                            borrowed[i] = new_val.clone();
-                            Ok(new_val)
-                        } else {
-                            Err(format!("Índice {} fora dos limites", i))
-                        }
-                    }
-                    _ => Err("Atribuição de índice requer array e índice numérico".into())
+                self.limites.verificar_insercao(&arr_val, &idx_val)?;
+                atribuir_indice(arr_val, idx_val, new_val)
+            }
+            Expr::Var(name, resolucao) => {
+                let env = self.env.borrow();
+                match resolucao {
+                    Some((profundidade, posicao)) => env.get_at(*profundidade, *posicao, name),
+                    None => env.get(name),
                 }
+                .map_err(|e| ErroExecucao::novo("VariavelIndefinida", e))
             }
-            Expr::Var(name) => self.env.borrow().get(name),
             Expr::Unario(op, right) => {
                 let r = self.evaluate(right)?;
-                match op {
-                    UnarioOp::Neg => {
-                        if let Value::Number(n) = r { 
-                            Ok(Value::Number(-n)) 
-                        } else { 
-                            Err("Operador unário '-' espera número".into()) 
-                        }
-                    }
-                    UnarioOp::Nao => Ok(Value::Bool(!r.is_truthy()))
-                }
+                operar_unario(op, r)
             }
             Expr::Binario(left, op, right) => {
                 let l = self.evaluate(left)?;
                 let r = self.evaluate(right)?;
-                match op {
-                    BinOp::Add => {
-                        match (l, r) {
-                            (Value::Number(a), Value::Number(b)) => Ok(Value::Number(a + b)),
-                            (Value::Text(a), Value::Text(b)) => Ok(Value::Text(a + &b)),
-                            (Value::Text(a), b2) => Ok(Value::Text(a + &b2.to_string_repr())),
-                            (a2, Value::Text(b)) => Ok(Value::Text(a2.to_string_repr() + &b)),
-                            _ => Err("Operador '+' inválido para operandos".into())
-                        }
-                    }
-                    BinOp::Sub => {
-                        if let (Value::Number(a), Value::Number(b)) = (l, r) { 
-                            Ok(Value::Number(a - b)) 
-                        } else { 
-                            Err("'-' espera números".into()) 
-                        }
-                    }
-                    BinOp::Mul => {
-                        if let (Value::Number(a), Value::Number(b)) = (l, r) { 
-                            Ok(Value::Number(a * b)) 
-                        } else { 
-                            Err("'*' espera números".into()) 
-                        }
-                    }
-                    BinOp::Div => {
-                        if let (Value::Number(a), Value::Number(b)) = (l, r) { 
-                            if b == 0.0 {
-                                Err("Divisão por zero".into())
-                            } else {
-                                Ok(Value::Number(a / b))
-                            }
-                        } else { 
-                            Err("'/' espera números".into()) 
-                        }
-                    }
-                    BinOp::Eq => Ok(Value::Bool(self.is_equal(&l, &r))),
-                    BinOp::Neq => Ok(Value::Bool(!self.is_equal(&l, &r))),
-                    BinOp::Lt => if let (Value::Number(a), Value::Number(b)) = (l, r) { 
-                        Ok(Value::Bool(a < b)) 
-                    } else { 
-                        Err("'<' espera números".into()) 
-                    },
-                    BinOp::Gt => if let (Value::Number(a), Value::Number(b)) = (l, r) { 
-                        Ok(Value::Bool(a > b)) 
-                    } else { 
-                        Err("'>' espera números".into()) 
-                    },
-                    BinOp::Le => if let (Value::Number(a), Value::Number(b)) = (l, r) { 
-                        Ok(Value::Bool(a <= b)) 
-                    } else { 
-                        Err("'<=' espera números".into()) 
-                    },
-                    BinOp::Ge => if let (Value::Number(a), Value::Number(b)) = (l, r) { 
-                        Ok(Value::Bool(a >= b)) 
-                    } else { 
-                        Err("'>=' espera números".into()) 
-                    },
-                    BinOp::And => Ok(Value::Bool(l.is_truthy() && r.is_truthy())),
-                    BinOp::Or => Ok(Value::Bool(l.is_truthy() || r.is_truthy())),
-                }
+                operar_binario(op, l, r, &mut self.consumo, &self.limites).and_then(|v| self.limites.verificar(v))
+            }
+            Expr::Intervalo(inicio, fim, inclusivo) => {
+                let inicio = match inicio {
+                    Some(e) => Some(self.evaluate(e)?),
+                    None => None,
+                };
+                let fim = match fim {
+                    Some(e) => Some(self.evaluate(e)?),
+                    None => None,
+                };
+                criar_intervalo(inicio, fim, *inclusivo)
             }
             Expr::Chamada(callee_expr, args_exprs) => {
-                let callee = self.evaluate(callee_expr)?;
-                let mut args_vals = Vec::new();
-                for a in args_exprs { 
-                    args_vals.push(self.evaluate(a)?); 
-                }
-                
-                match callee {
-                    Value::Function(f_rc) => {
-                        let f = f_rc.clone();
-                        
-                        if f.name == "imprimir" {
-                            for (i, v) in args_vals.iter().enumerate() {
-                                if i > 0 { print!(" "); }
-                                print!("{}", v.to_string_repr());
-                            }
-                            println!();
-                            return Ok(Value::Nil);
-                        }
-                        
-                        if f.name == "comprimento" {
-                            if args_vals.len() != 1 {
-                                return Err("comprimento() espera 1 argumento".into());
-                            }
-                            match &args_vals[0] {
-                                Value::Text(s) => return Ok(Value::Number(s.len() as f64)),
-                                Value::Array(arr) => return Ok(Value::Number(arr.borrow().len() as f64)),
-                                _ => return Err("comprimento() espera texto ou array".into())
-                            }
-                        }
-                        
-                        if f.name == "maiuscula" {
-                            if args_vals.len() != 1 {
-                                return Err("maiuscula() espera 1 argumento".into());
-                            }
-                            if let Value::Text(s) = &args_vals[0] {
-                                return Ok(Value::Text(s.to_uppercase()));
-                            }
-                            return Err("maiuscula() espera texto".into());
-                        }
-                        
-                        if f.name == "minuscula" {
-                            if args_vals.len() != 1 {
-                                return Err("minuscula() espera 1 argumento".into());
-                            }
-                            if let Value::Text(s) = &args_vals[0] {
-                                return Ok(Value::Text(s.to_lowercase()));
-                            }
-                            return Err("minuscula() espera texto".into());
-                        }
-                        
-                        f.call(self, args_vals)
-                    }
-                    _ => Err("Tentativa de chamar algo que não é função".into())
+                let (funcao, args) = self.avaliar_chamada(callee_expr, args_exprs)?;
+                self.chamar(funcao, args)
+            }
+        }
+    }
+}
+
+/// Linhas do rastreamento exibido com um erro. Chamadas repetidas em
+/// sequência, comuns numa recursão sem fim, aparecem uma vez só.
+fn formatar_rastro(rastro: &[(String, usize)]) -> Vec<String> {
+    let mut linhas = Vec::new();
+    let mut i = 0;
+    while i < rastro.len() {
+        let (nome, linha) = &rastro[i];
+        let repeticoes = rastro[i..].iter().take_while(|q| *q == &rastro[i]).count();
+        linhas.push(match linha {
+            0 => format!("em {}", nome),
+            l => format!("em {} (linha {})", nome, l),
+        });
+        if repeticoes > 1 {
+            linhas.push(format!("... repetida mais {} vezes", repeticoes - 1));
+        }
+        i += repeticoes;
+    }
+    linhas
+}
+
+// Operações sobre valores, compartilhadas pelo interpretador de árvore e pela `Vm`.
+
+pub fn indexar(arr_val: Value, idx_val: Value) -> Result<Value, ErroExecucao> {
+    match (arr_val, idx_val) {
+        (Value::Array(arr), Value::Number(idx)) => {
+            let borrowed = arr.borrow();
+            let i = normalizar_indice(idx, borrowed.len())?;
+            Ok(borrowed[i].clone())
+        }
+        (Value::Text(s), Value::Number(idx)) => {
+            let chars: Vec<char> = s.chars().collect();
+            let i = normalizar_indice(idx, chars.len())?;
+            Ok(Value::Text(chars[i].to_string()))
+        }
+        (Value::Array(arr), Value::Intervalo(intervalo)) => {
+            let borrowed = arr.borrow();
+            let (inicio, fim, passo) = intervalo.limites_fatia(borrowed.len())?;
+            let fatia = borrowed[inicio..fim].iter().step_by(passo).cloned().collect();
+            Ok(Value::Array(Rc::new(RefCell::new(fatia))))
+        }
+        (Value::Text(s), Value::Intervalo(intervalo)) => {
+            let chars: Vec<char> = s.chars().collect();
+            let (inicio, fim, passo) = intervalo.limites_fatia(chars.len())?;
+            Ok(Value::Text(chars[inicio..fim].iter().step_by(passo).collect()))
+        }
+        (Value::Dicionario(dic), Value::Text(chave)) => {
+            dic.borrow()
+                .get(&chave)
+                .cloned()
+                .ok_or_else(|| ErroExecucao::novo("ChaveNaoEncontrada", format!("Chave '{}' não encontrada no dicionário", chave)))
+        }
+        _ => Err("Indexação requer array/string com índice numérico ou intervalo, ou dicionário com chave de texto".into())
+    }
+}
+
+pub fn atribuir_indice(arr_val: Value, idx_val: Value, new_val: Value) -> Result<Value, ErroExecucao> {
+    match (arr_val, idx_val) {
+        (Value::Array(arr), Value::Number(idx)) => {
+            let mut borrowed = arr.borrow_mut();
+            let i = normalizar_indice(idx, borrowed.len())?;
+            borrowed[i] = new_val.clone();
+            Ok(new_val)
+        }
+        (Value::Dicionario(dic), Value::Text(chave)) => {
+            dic.borrow_mut().insert(chave, new_val.clone());
+            Ok(new_val)
+        }
+        _ => Err("Atribuição de índice requer array com índice numérico ou dicionário com chave de texto".into())
+    }
+}
+
+pub fn operar_unario(op: &UnarioOp, r: Value) -> Result<Value, ErroExecucao> {
+    match op {
+        UnarioOp::Neg => {
+            if let Value::Number(n) = r { 
+                Ok(Value::Number(-n)) 
+            } else { 
+                Err("Operador unário '-' espera número".into()) 
+            }
+        }
+        UnarioOp::Nao => Ok(Value::Bool(!r.is_truthy()))
+    }
+}
+
+/// Operação binária; `consumo` e `limites` valem para o texto de um valor
+/// concatenado com `+`.
+pub fn operar_binario(op: &BinOp, l: Value, r: Value, consumo: &mut Consumo, limites: &Limites) -> Result<Value, ErroExecucao> {
+    match op {
+        BinOp::Add => {
+            match (l, r) {
+                (Value::Number(a), Value::Number(b)) => Ok(Value::Number(a + b)),
+                (Value::Text(a), Value::Text(b)) => Ok(Value::Text(a + &b)),
+                (Value::Text(a), b2) => Ok(Value::Text(a + &b2.repr_limitado(&mut consumo.orcamento(limites))?)),
+                (a2, Value::Text(b)) => Ok(Value::Text(a2.repr_limitado(&mut consumo.orcamento(limites))? + &b)),
+                _ => Err("Operador '+' inválido para operandos".into())
+            }
+        }
+        BinOp::Sub => {
+            if let (Value::Number(a), Value::Number(b)) = (l, r) { 
+                Ok(Value::Number(a - b)) 
+            } else { 
+                Err("'-' espera números".into()) 
+            }
+        }
+        BinOp::Mul => {
+            if let (Value::Number(a), Value::Number(b)) = (l, r) { 
+                Ok(Value::Number(a * b)) 
+            } else { 
+                Err("'*' espera números".into()) 
+            }
+        }
+        BinOp::Div => {
+            if let (Value::Number(a), Value::Number(b)) = (l, r) { 
+                if b == 0.0 {
+                    Err(ErroExecucao::novo("DivisaoPorZero", "Divisão por zero"))
+                } else {
+                    Ok(Value::Number(a / b))
                 }
+            } else { 
+                Err("'/' espera números".into()) 
             }
         }
+        BinOp::Eq => Ok(Value::Bool(valores_iguais(&l, &r))),
+        BinOp::Neq => Ok(Value::Bool(!valores_iguais(&l, &r))),
+        BinOp::Lt => if let (Value::Number(a), Value::Number(b)) = (l, r) { 
+            Ok(Value::Bool(a < b)) 
+        } else { 
+            Err("'<' espera números".into()) 
+        },
+        BinOp::Gt => if let (Value::Number(a), Value::Number(b)) = (l, r) { 
+            Ok(Value::Bool(a > b)) 
+        } else { 
+            Err("'>' espera números".into()) 
+        },
+        BinOp::Le => if let (Value::Number(a), Value::Number(b)) = (l, r) { 
+            Ok(Value::Bool(a <= b)) 
+        } else { 
+            Err("'<=' espera números".into()) 
+        },
+        BinOp::Ge => if let (Value::Number(a), Value::Number(b)) = (l, r) { 
+            Ok(Value::Bool(a >= b)) 
+        } else { 
+            Err("'>=' espera números".into()) 
+        },
+        BinOp::And => Ok(Value::Bool(l.is_truthy() && r.is_truthy())),
+        BinOp::Or => Ok(Value::Bool(l.is_truthy() || r.is_truthy())),
     }
+}
 
-    fn is_equal(&self, a: &Value, b: &Value) -> bool {
-        match (a, b) {
-            (Value::Number(x), Value::Number(y)) => (x - y).abs() < 1e-10,
-            (Value::Text(x), Value::Text(y)) => x == y,
-            (Value::Bool(x), Value::Bool(y)) => x == y,
-            (Value::Nil, Value::Nil) => true,
-            _ => false
+pub fn criar_intervalo(inicio: Option<Value>, fim: Option<Value>, inclusivo: bool) -> Result<Value, ErroExecucao> {
+    let limite = |v: Option<Value>| -> Result<Option<f64>, ErroExecucao> {
+        match v {
+            None => Ok(None),
+            Some(Value::Number(n)) => Ok(Some(n)),
+            Some(outro) => Err(ErroExecucao::novo("ErroDeTipo", format!("Limites de intervalo devem ser números, recebeu '{}'", outro.to_string_repr()))),
         }
+    };
+    let inicio = limite(inicio)?;
+    let fim = limite(fim)?;
+    Ok(Value::Intervalo(Rc::new(Intervalo::novo(inicio, fim, inclusivo, 1.0)?)))
+}
+
+pub fn valores_iguais(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => (x - y).abs() < 1e-10,
+        (Value::Text(x), Value::Text(y)) => x == y,
+        (Value::Bool(x), Value::Bool(y)) => x == y,
+        (Value::Nil, Value::Nil) => true,
+        (Value::Intervalo(x), Value::Intervalo(y)) => x == y,
+        _ => false
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,168 @@
+use crate::arquivos::Arquivo;
+use crate::interpreter::{ErroExecucao, Value};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Intervalo numérico preguiçoso criado por `a..b`, `a..=b` ou `intervalo(a, b, passo)`.
+/// Os limites são opcionais para permitir fatias como `texto[..5]` e `lista[2..]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Intervalo {
+    pub inicio: Option<f64>,
+    pub fim: Option<f64>,
+    pub inclusivo: bool,
+    pub passo: f64,
+}
+
+impl Intervalo {
+    pub fn novo(inicio: Option<f64>, fim: Option<f64>, inclusivo: bool, passo: f64) -> Result<Self, ErroExecucao> {
+        if passo == 0.0 || !passo.is_finite() {
+            return Err(ErroExecucao::novo("ErroExecucao", "O passo de um intervalo não pode ser zero"));
+        }
+        Ok(Self { inicio, fim, inclusivo, passo })
+    }
+
+    /// Quantidade de elementos, ou `None` se o intervalo não tem fim.
+    pub fn tamanho(&self) -> Option<usize> {
+        let inicio = self.inicio.unwrap_or(0.0);
+        let fim = self.fim?;
+        let passos = (fim - inicio) / self.passo;
+        let n = if self.inclusivo {
+            passos.floor() + 1.0
+        } else {
+            passos.ceil()
+        };
+        Some(n.max(0.0) as usize)
+    }
+
+    /// Converte o intervalo em posições `(inicio, fim, passo)` de uma fatia
+    /// sobre algo de tamanho `tamanho`. Índices negativos contam a partir do
+    /// fim e os limites são ajustados ao tamanho, como em Python.
+    pub fn limites_fatia(&self, tamanho: usize) -> Result<(usize, usize, usize), ErroExecucao> {
+        if self.passo < 0.0 || self.passo.fract() != 0.0 {
+            return Err(ErroExecucao::novo("ErroExecucao", "Fatias aceitam apenas passo inteiro positivo"));
+        }
+        let resolver = |i: f64| -> Result<usize, ErroExecucao> {
+            if i.fract() != 0.0 {
+                return Err(ErroExecucao::novo("ErroDeTipo", format!("Índice {} não é inteiro", i)));
+            }
+            let i = if i < 0.0 { tamanho as f64 + i } else { i };
+            Ok(i.clamp(0.0, tamanho as f64) as usize)
+        };
+
+        let inicio = resolver(self.inicio.unwrap_or(0.0))?;
+        let mut fim = match self.fim {
+            Some(f) => resolver(f)?,
+            None => tamanho,
+        };
+        if self.inclusivo && self.fim.is_some() {
+            fim = (fim + 1).min(tamanho);
+        }
+        Ok((inicio, fim.max(inicio), self.passo as usize))
+    }
+
+    pub fn to_string_repr(self) -> String {
+        let num = |n: Option<f64>| n.map(|v| Value::Number(v).to_string_repr()).unwrap_or_default();
+        let op = if self.inclusivo { "..=" } else { ".." };
+        let base = format!("{}{}{}", num(self.inicio), op, num(self.fim));
+        if self.passo == 1.0 {
+            base
+        } else {
+            format!("{} (passo {})", base, num(Some(self.passo)))
+        }
+    }
+}
+
+/// Converte um índice numérico (negativo conta a partir do fim) em posição válida.
+pub fn normalizar_indice(idx: f64, tamanho: usize) -> Result<usize, ErroExecucao> {
+    if idx.fract() != 0.0 {
+        return Err(ErroExecucao::novo("ErroDeTipo", format!("Índice {} não é inteiro", idx)));
+    }
+    let posicao = if idx < 0.0 { tamanho as f64 + idx } else { idx };
+    if posicao < 0.0 || posicao >= tamanho as f64 {
+        return Err(ErroExecucao::novo(
+            "IndiceForaDosLimites",
+            format!("Índice {} fora dos limites (tamanho: {})", idx, tamanho),
+        ));
+    }
+    Ok(posicao as usize)
+}
+
+/// Protocolo de iteração usado por `para cada`.
+///
+/// Cada passo produz um par `(indice, item)`; para dicionários o par é
+/// `(chave, valor)` e a forma com uma só variável recebe a chave.
+pub enum Iterador {
+    Lista(Rc<RefCell<Vec<Value>>>, usize),
+    Caracteres(Vec<char>, usize),
+    Intervalo(Intervalo, usize),
+    Dicionario(std::vec::IntoIter<(String, Value)>),
+    Linhas(Rc<RefCell<Arquivo>>, usize),
+}
+
+impl Iterador {
+    pub fn novo(valor: &Value) -> Result<Self, ErroExecucao> {
+        match valor {
+            Value::Array(arr) => Ok(Iterador::Lista(arr.clone(), 0)),
+            Value::Text(s) => Ok(Iterador::Caracteres(s.chars().collect(), 0)),
+            Value::Dicionario(dic) => {
+                let pares: Vec<(String, Value)> =
+                    dic.borrow().iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+                Ok(Iterador::Dicionario(pares.into_iter()))
+            }
+            Value::Arquivo(arq) => Ok(Iterador::Linhas(arq.clone(), 0)),
+            Value::Intervalo(intervalo) => Ok(Iterador::Intervalo(**intervalo, 0)),
+            outro => Err(ErroExecucao::novo(
+                "ErroDeTipo",
+                format!("Não é possível iterar sobre '{}'", outro.to_string_repr()),
+            )),
+        }
+    }
+
+    pub fn por_chave(&self) -> bool {
+        matches!(self, Iterador::Dicionario(_))
+    }
+
+    pub fn proximo(&mut self) -> Result<Option<(Value, Value)>, ErroExecucao> {
+        let par = match self {
+            // Relê o tamanho a cada passo: o corpo do laço pode alterar a lista
+            Iterador::Lista(arr, i) => {
+                let item = arr.borrow().get(*i).cloned();
+                item.map(|v| {
+                    *i += 1;
+                    (Value::Number((*i - 1) as f64), v)
+                })
+            }
+            Iterador::Caracteres(chars, i) => chars.get(*i).map(|c| {
+                *i += 1;
+                (Value::Number((*i - 1) as f64), Value::Text(c.to_string()))
+            }),
+            Iterador::Intervalo(intervalo, indice) => {
+                // Calculado a partir do índice para não acumular erro de ponto flutuante
+                let atual = intervalo.inicio.unwrap_or(0.0) + *indice as f64 * intervalo.passo;
+                // Sem fim, o intervalo é infinito e o laço termina com `parar`
+                let dentro = match (intervalo.fim, intervalo.passo > 0.0, intervalo.inclusivo) {
+                    (None, _, _) => true,
+                    (Some(fim), true, true) => atual <= fim,
+                    (Some(fim), true, false) => atual < fim,
+                    (Some(fim), false, true) => atual >= fim,
+                    (Some(fim), false, false) => atual > fim,
+                };
+                if dentro {
+                    *indice += 1;
+                    Some((Value::Number((*indice - 1) as f64), Value::Number(atual)))
+                } else {
+                    None
+                }
+            }
+            Iterador::Dicionario(pares) => pares.next().map(|(k, v)| (Value::Text(k), v)),
+            Iterador::Linhas(arq, i) => {
+                let linha = arq.borrow_mut().ler_linha()?;
+                linha.map(|l| {
+                    *i += 1;
+                    (Value::Number((*i - 1) as f64), Value::Text(l))
+                })
+            }
+        };
+        Ok(par)
+    }
+}
@@ -0,0 +1,402 @@
+use crate::interpreter::{ErroExecucao, Value};
+use crate::limites::Orcamento;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+/// Até quantos arrays e objetos aninhados `ler` aceita; mais que isso estouraria a pilha.
+const PROFUNDIDADE_MAXIMA: usize = 512;
+
+pub fn ler(texto: &str) -> Result<Value, String> {
+    let mut leitor = LeitorJson::new(texto);
+    leitor.pular_espacos();
+    let valor = leitor.valor()?;
+    leitor.pular_espacos();
+    if leitor.espiar().is_some() {
+        return Err(leitor.erro("conteúdo extra após o valor JSON"));
+    }
+    Ok(valor)
+}
+
+/// Converte `valor` em JSON, desistindo assim que o texto estoura o `orcamento`.
+pub fn escrever(valor: &Value, identado: bool, orcamento: &mut Orcamento) -> Result<String, ErroExecucao> {
+    let mut escritor = EscritorJson {
+        saida: String::new(),
+        identado,
+        visitados: Vec::new(),
+        orcamento,
+    };
+    escritor.valor(valor, 0)?;
+    Ok(escritor.saida)
+}
+
+struct LeitorJson {
+    src: Vec<char>,
+    pos: usize,
+    linha: usize,
+    coluna: usize,
+    /// Arrays e objetos abertos no ponto atual
+    profundidade: usize,
+}
+
+impl LeitorJson {
+    fn new(texto: &str) -> Self {
+        Self {
+            src: texto.chars().collect(),
+            pos: 0,
+            linha: 1,
+            coluna: 1,
+            profundidade: 0,
+        }
+    }
+
+    fn espiar(&self) -> Option<char> {
+        self.src.get(self.pos).copied()
+    }
+
+    fn avancar(&mut self) -> Option<char> {
+        let c = self.espiar()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.linha += 1;
+            self.coluna = 1;
+        } else {
+            self.coluna += 1;
+        }
+        Some(c)
+    }
+
+    fn erro(&self, msg: &str) -> String {
+        format!("JSON inválido na linha {}, coluna {}: {}", self.linha, self.coluna, msg)
+    }
+
+    fn esperar(&mut self, esperado: char) -> Result<(), String> {
+        match self.espiar() {
+            Some(c) if c == esperado => {
+                self.avancar();
+                Ok(())
+            }
+            Some(c) => Err(self.erro(&format!("esperado '{}' mas encontrado '{}'", esperado, c))),
+            None => Err(self.erro(&format!("esperado '{}' mas o texto terminou", esperado))),
+        }
+    }
+
+    fn pular_espacos(&mut self) {
+        while let Some(' ' | '\t' | '\n' | '\r') = self.espiar() {
+            self.avancar();
+        }
+    }
+
+    fn valor(&mut self) -> Result<Value, String> {
+        match self.espiar() {
+            Some('{' | '[') if self.profundidade >= PROFUNDIDADE_MAXIMA => {
+                Err(self.erro(&format!("mais de {} arrays e objetos aninhados", PROFUNDIDADE_MAXIMA)))
+            }
+            Some('{') => self.aninhado(Self::objeto),
+            Some('[') => self.aninhado(Self::lista),
+            Some('"') => self.texto().map(Value::Text),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.numero(),
+            Some(c) if c.is_alphabetic() => self.literal(),
+            Some(c) => Err(self.erro(&format!("caractere inesperado '{}'", c))),
+            None => Err(self.erro("o texto terminou antes do valor")),
+        }
+    }
+
+    fn aninhado(&mut self, ler: fn(&mut Self) -> Result<Value, String>) -> Result<Value, String> {
+        self.profundidade += 1;
+        let valor = ler(self);
+        self.profundidade -= 1;
+        valor
+    }
+
+    fn objeto(&mut self) -> Result<Value, String> {
+        self.avancar();
+        let mut mapa = BTreeMap::new();
+        self.pular_espacos();
+        if self.espiar() == Some('}') {
+            self.avancar();
+            return Ok(Value::Dicionario(Rc::new(RefCell::new(mapa))));
+        }
+
+        loop {
+            self.pular_espacos();
+            if self.espiar() != Some('"') {
+                return Err(self.erro("chaves de objetos devem ser textos entre aspas"));
+            }
+            let chave = self.texto()?;
+            self.pular_espacos();
+            self.esperar(':')?;
+            self.pular_espacos();
+            let valor = self.valor()?;
+            mapa.insert(chave, valor);
+            self.pular_espacos();
+
+            match self.avancar() {
+                Some(',') => continue,
+                Some('}') => break,
+                Some(c) => return Err(self.erro(&format!("esperado ',' ou '}}' mas encontrado '{}'", c))),
+                None => return Err(self.erro("objeto não foi fechado com '}'")),
+            }
+        }
+        Ok(Value::Dicionario(Rc::new(RefCell::new(mapa))))
+    }
+
+    fn lista(&mut self) -> Result<Value, String> {
+        self.avancar();
+        let mut itens = Vec::new();
+        self.pular_espacos();
+        if self.espiar() == Some(']') {
+            self.avancar();
+            return Ok(Value::Array(Rc::new(RefCell::new(itens))));
+        }
+
+        loop {
+            self.pular_espacos();
+            itens.push(self.valor()?);
+            self.pular_espacos();
+
+            match self.avancar() {
+                Some(',') => continue,
+                Some(']') => break,
+                Some(c) => return Err(self.erro(&format!("esperado ',' ou ']' mas encontrado '{}'", c))),
+                None => return Err(self.erro("lista não foi fechada com ']'")),
+            }
+        }
+        Ok(Value::Array(Rc::new(RefCell::new(itens))))
+    }
+
+    fn texto(&mut self) -> Result<String, String> {
+        self.avancar();
+        let mut s = String::new();
+        loop {
+            match self.avancar() {
+                Some('"') => return Ok(s),
+                Some('\\') => match self.avancar() {
+                    Some('"') => s.push('"'),
+                    Some('\\') => s.push('\\'),
+                    Some('/') => s.push('/'),
+                    Some('b') => s.push('\u{8}'),
+                    Some('f') => s.push('\u{c}'),
+                    Some('n') => s.push('\n'),
+                    Some('r') => s.push('\r'),
+                    Some('t') => s.push('\t'),
+                    Some('u') => s.push(self.escape_unicode()?),
+                    Some(c) => return Err(self.erro(&format!("escape inválido '\\{}'", c))),
+                    None => return Err(self.erro("texto não foi fechado com aspas")),
+                },
+                Some(c) if (c as u32) < 0x20 => {
+                    return Err(self.erro("caractere de controle dentro de texto"));
+                }
+                Some(c) => s.push(c),
+                None => return Err(self.erro("texto não foi fechado com aspas")),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let mut codigo = 0;
+        for _ in 0..4 {
+            let digito = self
+                .avancar()
+                .and_then(|c| c.to_digit(16))
+                .ok_or_else(|| self.erro("escape \\u espera 4 dígitos hexadecimais"))?;
+            codigo = codigo * 16 + digito;
+        }
+        Ok(codigo)
+    }
+
+    fn escape_unicode(&mut self) -> Result<char, String> {
+        let alto = self.hex4()?;
+        let codigo = if (0xD800..0xDC00).contains(&alto) {
+            // Par substituto UTF-16: espera o segundo "\uXXXX"
+            if self.avancar() != Some('\\') || self.avancar() != Some('u') {
+                return Err(self.erro("par substituto UTF-16 incompleto"));
+            }
+            let baixo = self.hex4()?;
+            if !(0xDC00..0xE000).contains(&baixo) {
+                return Err(self.erro("par substituto UTF-16 inválido"));
+            }
+            0x10000 + ((alto - 0xD800) << 10) + (baixo - 0xDC00)
+        } else {
+            alto
+        };
+        char::from_u32(codigo).ok_or_else(|| self.erro("código unicode inválido"))
+    }
+
+    fn numero(&mut self) -> Result<Value, String> {
+        let (linha, coluna) = (self.linha, self.coluna);
+        let mut num = String::new();
+        while let Some(c) = self.espiar() {
+            if c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E') {
+                num.push(c);
+                self.avancar();
+            } else {
+                break;
+            }
+        }
+        let invalido = || format!("JSON inválido na linha {}, coluna {}: número inválido '{}'", linha, coluna, num);
+        if !numero_valido(&num) {
+            return Err(invalido());
+        }
+        num.parse::<f64>().map(Value::Number).map_err(|_| invalido())
+    }
+
+    fn literal(&mut self) -> Result<Value, String> {
+        let (linha, coluna) = (self.linha, self.coluna);
+        let mut palavra = String::new();
+        while let Some(c) = self.espiar() {
+            if !c.is_alphanumeric() {
+                break;
+            }
+            palavra.push(c);
+            self.avancar();
+        }
+        match palavra.as_str() {
+            "true" => Ok(Value::Bool(true)),
+            "false" => Ok(Value::Bool(false)),
+            "null" => Ok(Value::Nil),
+            _ => Err(format!(
+                "JSON inválido na linha {}, coluna {}: valor desconhecido '{}'",
+                linha, coluna, palavra
+            )),
+        }
+    }
+}
+
+/// Se `num` segue a gramática de números do JSON (RFC 8259):
+/// `-`? (`0` | `[1-9][0-9]*`) (`.` `[0-9]+`)? ([`eE`] [`+-`]? `[0-9]+`)?
+fn numero_valido(num: &str) -> bool {
+    fn digitos(resto: &str) -> (usize, &str) {
+        let n = resto.bytes().take_while(u8::is_ascii_digit).count();
+        (n, &resto[n..])
+    }
+    let sem_sinal = num.strip_prefix('-').unwrap_or(num);
+    let (n, resto) = digitos(sem_sinal);
+    if n == 0 || (n > 1 && sem_sinal.starts_with('0')) {
+        return false;
+    }
+    let resto = match resto.strip_prefix('.') {
+        Some(fracao) => match digitos(fracao) {
+            (0, _) => return false,
+            (_, resto) => resto,
+        },
+        None => resto,
+    };
+    let resto = match resto.strip_prefix(['e', 'E']) {
+        Some(expoente) => match digitos(expoente.strip_prefix(['+', '-']).unwrap_or(expoente)) {
+            (0, _) => return false,
+            (_, resto) => resto,
+        },
+        None => resto,
+    };
+    resto.is_empty()
+}
+
+struct EscritorJson<'a> {
+    saida: String,
+    identado: bool,
+    /// Arrays e dicionários sendo escritos, para detectar ciclos
+    visitados: Vec<*const ()>,
+    orcamento: &'a mut Orcamento,
+}
+
+impl EscritorJson<'_> {
+    fn quebra(&mut self, nivel: usize) {
+        if self.identado {
+            self.saida.push('\n');
+            self.saida.push_str(&"  ".repeat(nivel));
+        }
+    }
+
+    fn entrar(&mut self, ptr: *const (), tipo: &str) -> Result<(), ErroExecucao> {
+        if self.visitados.contains(&ptr) {
+            return Err(format!("Não é possível converter {} cíclico para JSON", tipo).into());
+        }
+        self.visitados.push(ptr);
+        Ok(())
+    }
+
+    fn valor(&mut self, valor: &Value, nivel: usize) -> Result<(), ErroExecucao> {
+        match valor {
+            Value::Nil => self.saida.push_str("null"),
+            Value::Bool(b) => self.saida.push_str(if *b { "true" } else { "false" }),
+            Value::Number(n) => {
+                if !n.is_finite() {
+                    return Err(format!("Não é possível converter o número {} para JSON", n).into());
+                }
+                self.saida.push_str(&n.to_string());
+            }
+            Value::Text(s) => self.texto(s),
+            Value::Array(arr) => {
+                self.entrar(Rc::as_ptr(arr) as *const (), "array")?;
+                let itens = arr.borrow();
+                self.saida.push('[');
+                for (i, item) in itens.iter().enumerate() {
+                    if i > 0 {
+                        self.saida.push(',');
+                    }
+                    self.quebra(nivel + 1);
+                    self.valor(item, nivel + 1)?;
+                }
+                if !itens.is_empty() {
+                    self.quebra(nivel);
+                }
+                self.saida.push(']');
+                self.visitados.pop();
+            }
+            Value::Dicionario(dic) => {
+                self.entrar(Rc::as_ptr(dic) as *const (), "dicionário")?;
+                let mapa = dic.borrow();
+                self.saida.push('{');
+                for (i, (chave, item)) in mapa.iter().enumerate() {
+                    if i > 0 {
+                        self.saida.push(',');
+                    }
+                    self.quebra(nivel + 1);
+                    self.texto(chave);
+                    self.saida.push_str(if self.identado { ": " } else { ":" });
+                    self.valor(item, nivel + 1)?;
+                }
+                if !mapa.is_empty() {
+                    self.quebra(nivel);
+                }
+                self.saida.push('}');
+                self.visitados.pop();
+            }
+            Value::Intervalo(intervalo) => {
+                return Err(format!(
+                    "Não é possível converter o intervalo {} para JSON",
+                    intervalo.to_string_repr()
+                )
+                .into());
+            }
+            Value::Function(f) => {
+                return Err(format!("Não é possível converter a função '{}' para JSON", f.name).into());
+            }
+            Value::Arquivo(arq) => {
+                return Err(format!(
+                    "Não é possível converter o arquivo '{}' para JSON",
+                    arq.borrow().caminho
+                )
+                .into());
+            }
+        }
+        self.orcamento.conferir(self.saida.len())
+    }
+
+    fn texto(&mut self, s: &str) {
+        self.saida.push('"');
+        for c in s.chars() {
+            match c {
+                '"' => self.saida.push_str("\\\""),
+                '\\' => self.saida.push_str("\\\\"),
+                '\n' => self.saida.push_str("\\n"),
+                '\r' => self.saida.push_str("\\r"),
+                '\t' => self.saida.push_str("\\t"),
+                c if (c as u32) < 0x20 => self.saida.push_str(&format!("\\u{:04x}", c as u32)),
+                c => self.saida.push(c),
+            }
+        }
+        self.saida.push('"');
+    }
+}
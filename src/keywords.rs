@@ -5,6 +5,7 @@ pub fn palavras_chave() -> HashMap<&'static str, TokenType> {
     use TokenType::*;
     HashMap::from([
         ("variavel", Variavel),
+        ("constante", Constante),
         ("se", Se),
         ("senao", Senao),
         ("enquanto", Enquanto),
@@ -20,5 +21,11 @@ pub fn palavras_chave() -> HashMap<&'static str, TokenType> {
         ("nulo", Nulo),
         ("parar", Break),
         ("continuar", Continue),
+        ("tentar", Tentar),
+        ("capturar", Capturar),
+        ("finalmente", Finalmente),
+        ("lancar", Lancar),
+        ("importar", Importar),
+        ("exportar", Exportar),
     ])
 }
\ No newline at end of file
@@ -1,11 +1,25 @@
 use crate::tokens::{Token, TokenType};
 use crate::keywords::palavras_chave;
+use crate::resolver::{Diagnostico, ERRO_LEXICO};
+
+/// Comentário `//`, com o texto depois das barras.
+#[derive(Debug, Clone)]
+pub struct Comentario {
+    pub linha: usize,
+    pub texto: String,
+}
 
 pub struct Lexer {
     src: Vec<char>,
     pos: usize,
     linha: usize,
     coluna: usize,
+    /// Um texto chegou ao fim da fonte sem as aspas de fechamento
+    pub texto_aberto: bool,
+    /// Comentários encontrados por `tokenize`, na ordem da fonte
+    pub comentarios: Vec<Comentario>,
+    /// Caracteres desconhecidos e textos sem fechamento
+    pub erros: Vec<Diagnostico>,
 }
 
 impl Lexer {
@@ -15,6 +29,9 @@ impl Lexer {
             pos: 0,
             linha: 1,
             coluna: 1,
+            texto_aberto: false,
+            comentarios: Vec::new(),
+            erros: Vec::new(),
         }
     }
 
@@ -56,12 +73,18 @@ impl Lexer {
                 Some('/') => {
                     if let Some(next) = self.src.get(self.pos + 1) {
                         if *next == '/' {
+                            let linha = self.linha;
+                            self.pos += 2;
+                            self.coluna += 2;
+                            let mut texto = String::new();
                             while let Some(ch) = self.espiar() {
                                 if ch == '\n' {
                                     break;
                                 }
+                                texto.push(ch);
                                 self.avancar();
                             }
+                            self.comentarios.push(Comentario { linha, texto });
                         } else {
                             break;
                         }
@@ -87,8 +110,9 @@ impl Lexer {
 
             let ch = self.espiar().unwrap();
             let linha = self.linha;
+            let coluna = self.coluna;
 
-            let token = match ch {
+            let mut token = match ch {
                 '+' => {
                     self.avancar();
                     Token::new(Mais, "+".into(), linha)
@@ -137,6 +161,22 @@ impl Lexer {
                     self.avancar();
                     Token::new(Virgula, ",".into(), linha)
                 }
+                ':' => {
+                    self.avancar();
+                    Token::new(DoisPontos, ":".into(), linha)
+                }
+                '.' => {
+                    self.avancar();
+                    if self.combinar('.') {
+                        if self.combinar('=') {
+                            Token::new(PontoPontoIgual, "..=".into(), linha)
+                        } else {
+                            Token::new(PontoPonto, "..".into(), linha)
+                        }
+                    } else {
+                        Token::new(Ponto, ".".into(), linha)
+                    }
+                }
                 '=' => {
                     self.avancar();
                     if self.combinar('=') {
@@ -180,11 +220,31 @@ impl Lexer {
                             self.linha += 1;
                             self.coluna = 1;
                         }
-                        s.push(ch2);
                         self.avancar();
+                        if ch2 == '\\' {
+                            match self.avancar() {
+                                Some('n') => s.push('\n'),
+                                Some('t') => s.push('\t'),
+                                Some('r') => s.push('\r'),
+                                Some('"') => s.push('"'),
+                                Some('\\') => s.push('\\'),
+                                Some(outro) => {
+                                    s.push('\\');
+                                    s.push(outro);
+                                }
+                                None => s.push('\\'),
+                            }
+                            continue;
+                        }
+                        s.push(ch2);
                     }
                     if self.espiar() == Some('"') {
                         self.avancar();
+                    } else {
+                        self.texto_aberto = true;
+                        self.erros.push(
+                            Diagnostico::novo(ERRO_LEXICO, "Texto sem as aspas de fechamento".into(), linha).na_coluna(coluna),
+                        );
                     }
                     let lexema = format!("\"{}\"", s);
                     Token::new(Texto(s), lexema, linha)
@@ -192,7 +252,10 @@ impl Lexer {
                 d if d.is_ascii_digit() => {
                     let mut num = String::new();
                     while let Some(nd) = self.espiar() {
-                        if nd.is_ascii_digit() || nd == '.' {
+                        // Um '.' só faz parte do número se vier seguido de dígito (`1..10` é intervalo)
+                        let decimal = nd == '.'
+                            && self.src.get(self.pos + 1).is_some_and(|c| c.is_ascii_digit());
+                        if nd.is_ascii_digit() || decimal {
                             num.push(nd);
                             self.avancar();
                         } else {
@@ -220,16 +283,22 @@ impl Lexer {
                         Token::new(Ident(id.clone()), id, linha)
                     }
                 }
-                _ => {
+                outro => {
                     self.avancar();
+                    self.erros.push(
+                        Diagnostico::novo(ERRO_LEXICO, format!("Caractere inesperado '{}'", outro), linha).na_coluna(coluna),
+                    );
                     continue;
                 }
             };
 
+            token.coluna = coluna;
             tokens.push(token);
         }
 
-        tokens.push(Token::new(Fim, "".into(), self.linha));
+        let mut fim = Token::new(Fim, "".into(), self.linha);
+        fim.coluna = self.coluna;
+        tokens.push(fim);
         tokens
     }
 }
\ No newline at end of file
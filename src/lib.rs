@@ -0,0 +1,59 @@
+//! Lucas, uma linguagem de script em português.
+//!
+//! Para embutir o interpretador num programa Rust, crie um `Interpreter`,
+//! registre funções com `register_function`, troque valores com
+//! `set_global`/`get_global` e execute código com `eval` ou `call_function`.
+//! O texto impresso pelos scripts vai para o destino de `set_saida` e os
+//! erros para o de `set_saida_erros`; veja `Saida`. Scripts não confiáveis
+//! rodam sob `set_limites`; veja `Limites`.
+//!
+//! ```
+//! use lucas::{Interpreter, Saida, Value};
+//!
+//! let mut interpreter = Interpreter::new();
+//! interpreter.set_saida(Saida::Buffer(Vec::new()));
+//! interpreter.register_function("dobro", |args| match args {
+//!     [Value::Number(n)] => Ok(Value::from(n * 2.0)),
+//!     _ => Err("dobro() espera um número".into()),
+//! });
+//! interpreter.set_global("nome", Value::from("Ana"));
+//!
+//! let valor = interpreter.eval("imprimir(\"Olá, \" + nome); dobro(21)").unwrap();
+//! assert_eq!(valor.to_string_repr(), "42");
+//! assert_eq!(interpreter.saida_mut().capturado(), "Olá, Ana\n");
+//! ```
+
+mod tokens;
+mod keywords;
+mod lexer;
+mod ast;
+mod parser;
+mod resolver;
+mod bytecode;
+mod compilador;
+mod vm;
+#[cfg(feature = "repl")]
+mod editor;
+mod environment;
+mod interpreter;
+mod builtins;
+mod arquivos;
+mod json;
+mod iterador;
+mod limites;
+mod modulos;
+mod saida;
+mod errors;
+mod formatador;
+mod depuracao;
+#[cfg(feature = "repl")]
+pub mod repl;
+
+pub use arquivos::Arquivo;
+pub use depuracao::{print_ast, print_tokens};
+pub use errors::{ErrorType, LucasError, SourceLocation};
+pub use formatador::formatar;
+pub use interpreter::{ErroExecucao, Function, Interpreter, Value, SAIR};
+pub use iterador::Intervalo;
+pub use limites::{Limites, LIMITE_EXCEDIDO};
+pub use saida::Saida;
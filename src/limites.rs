@@ -0,0 +1,184 @@
+use crate::interpreter::{ErroExecucao, Value};
+use std::time::{Duration, Instant};
+
+/// Tipo dos erros de limite excedido.
+pub const LIMITE_EXCEDIDO: &str = "LimiteExcedido";
+
+/// De quantos em quantos passos o relógio é consultado.
+const PASSOS_ENTRE_RELOGIO: u64 = 1024;
+
+/// Limites para executar scripts não confiáveis, como trabalhos de alunos num
+/// servidor de correção. Cada limite é opcional; o padrão não limita nada.
+#[derive(Debug, Clone)]
+pub struct Limites {
+    /// Comandos executados por `Interpreter::run`
+    pub passos: Option<u64>,
+    /// Duração máxima de `Interpreter::run`
+    pub tempo: Option<Duration>,
+    /// Elementos de um array ou dicionário
+    pub tamanho_colecao: Option<usize>,
+    /// Bytes de um texto
+    pub tamanho_texto: Option<usize>,
+    /// Bytes escritos na saída
+    pub bytes_saida: Option<usize>,
+    /// Libera as nativas que acessam arquivos e o processo, e a importação de módulos
+    pub acesso_sistema: bool,
+    /// Erros de limite não podem ser capturados por `tentar`/`capturar`
+    /// (o pedido de `sair` nunca pode)
+    pub fatais: bool,
+}
+
+impl Default for Limites {
+    fn default() -> Self {
+        Self {
+            passos: None,
+            tempo: None,
+            tamanho_colecao: None,
+            tamanho_texto: None,
+            bytes_saida: None,
+            acesso_sistema: true,
+            fatais: false,
+        }
+    }
+}
+
+fn excedido(mensagem: String) -> ErroExecucao {
+    ErroExecucao::novo(LIMITE_EXCEDIDO, mensagem)
+}
+
+impl Limites {
+    /// Se a execução precisa contar passos (para o orçamento ou o relógio).
+    pub fn conta_passos(&self) -> bool {
+        self.passos.is_some() || self.tempo.is_some()
+    }
+
+    /// Recusa `acao` (como importar um módulo) quando o acesso ao sistema está desativado.
+    pub fn exigir_acesso_sistema(&self, acao: &str) -> Result<(), ErroExecucao> {
+        if self.acesso_sistema {
+            Ok(())
+        } else {
+            Err(excedido(format!("{} não é permitido sem acesso ao sistema", acao)))
+        }
+    }
+
+    /// Se `erro` deve atravessar os blocos `capturar`.
+    pub fn fatal(&self, erro: &ErroExecucao) -> bool {
+        erro.codigo_saida().is_some() || (self.fatais && erro.tipo == LIMITE_EXCEDIDO)
+    }
+
+    /// Confere o tamanho de um texto, array ou dicionário recém-criado.
+    pub fn verificar(&self, valor: Value) -> Result<Value, ErroExecucao> {
+        self.verificar_tamanho(&valor)?;
+        Ok(valor)
+    }
+
+    /// Confere, antes de `dic[chave] = valor`, se uma chave nova ainda cabe.
+    pub fn verificar_insercao(&self, alvo: &Value, indice: &Value) -> Result<(), ErroExecucao> {
+        if let (Some(limite), Value::Dicionario(dic), Value::Text(chave)) = (self.tamanho_colecao, alvo, indice) {
+            let dic = dic.borrow();
+            if dic.len() >= limite && !dic.contains_key(chave) {
+                return Err(excedido(format!(
+                    "Dicionário com {} elementos excede o limite de {}",
+                    dic.len() + 1,
+                    limite
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn verificar_tamanho(&self, valor: &Value) -> Result<(), ErroExecucao> {
+        let (tipo, tamanho, limite) = match valor {
+            Value::Text(s) => ("Texto", s.len(), self.tamanho_texto),
+            Value::Array(arr) => ("Array", arr.borrow().len(), self.tamanho_colecao),
+            Value::Dicionario(dic) => ("Dicionário", dic.borrow().len(), self.tamanho_colecao),
+            _ => return Ok(()),
+        };
+        match limite {
+            Some(limite) if tamanho > limite => {
+                let unidade = if tipo == "Texto" { "bytes" } else { "elementos" };
+                Err(excedido(format!("{} com {} {} excede o limite de {}", tipo, tamanho, unidade, limite)))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn tempo_excedido(tempo: Duration) -> ErroExecucao {
+    excedido(format!("Tempo limite de {}s excedido", tempo.as_secs_f64()))
+}
+
+/// Limites de um texto em construção (por `imprimir`, `+` ou `json_escrever`),
+/// conferidos enquanto ele cresce: um array pequeno que repete o mesmo
+/// sub-array vira um texto enorme antes de `Limites::verificar` vê-lo.
+#[derive(Default)]
+pub struct Orcamento {
+    bytes: Option<usize>,
+    prazo: Option<(Instant, Duration)>,
+    conferencias: u64,
+}
+
+impl Orcamento {
+    /// Confere o texto que já tem `tamanho` bytes; o relógio é consultado de tempos em tempos.
+    pub fn conferir(&mut self, tamanho: usize) -> Result<(), ErroExecucao> {
+        if let Some(limite) = self.bytes {
+            if tamanho > limite {
+                return Err(excedido(format!("Texto com mais de {} bytes excede o limite de {}", limite, limite)));
+            }
+        }
+        if let Some((prazo, tempo)) = self.prazo {
+            self.conferencias += 1;
+            if self.conferencias.is_multiple_of(PASSOS_ENTRE_RELOGIO) && Instant::now() > prazo {
+                return Err(tempo_excedido(tempo));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Quanto uma execução já consumiu dos `Limites`.
+#[derive(Default)]
+pub struct Consumo {
+    passos: u64,
+    prazo: Option<Instant>,
+    bytes_saida: usize,
+}
+
+impl Consumo {
+    pub fn passo(&mut self, limites: &Limites) -> Result<(), ErroExecucao> {
+        self.passos += 1;
+        if let Some(maximo) = limites.passos {
+            if self.passos > maximo {
+                return Err(excedido(format!("Limite de {} passos de execução excedido", maximo)));
+            }
+        }
+        if let Some(tempo) = limites.tempo {
+            let prazo = *self.prazo.get_or_insert_with(|| Instant::now() + tempo);
+            if self.passos.is_multiple_of(PASSOS_ENTRE_RELOGIO) && Instant::now() > prazo {
+                return Err(tempo_excedido(tempo));
+            }
+        }
+        Ok(())
+    }
+
+    /// Orçamento para montar um texto agora, com o tamanho máximo de texto e o
+    /// que resta do prazo.
+    pub fn orcamento(&mut self, limites: &Limites) -> Orcamento {
+        Orcamento {
+            bytes: limites.tamanho_texto,
+            prazo: limites.tempo.map(|tempo| (*self.prazo.get_or_insert_with(|| Instant::now() + tempo), tempo)),
+            conferencias: 0,
+        }
+    }
+
+    /// Registra `bytes` a escrever na saída, recusando-os se passarem do limite.
+    pub fn saida(&mut self, limites: &Limites, bytes: usize) -> Result<(), ErroExecucao> {
+        if let Some(maximo) = limites.bytes_saida {
+            if self.bytes_saida + bytes > maximo {
+                return Err(excedido(format!("Limite de saída de {} bytes excedido", maximo)));
+            }
+        }
+        self.bytes_saida += bytes;
+        Ok(())
+    }
+}
@@ -1,28 +1,86 @@
-mod tokens;
-mod keywords;
-mod lexer;
-mod ast;
-mod parser;
-mod environment;
-mod interpreter;
-mod errors;
-mod repl;
-
+#[cfg(feature = "repl")]
+use lucas::repl;
+use lucas::{print_ast, print_tokens, ErrorType, Interpreter, Value};
 use std::env;
 use std::fs;
+use std::io::{self, Read, Write};
 use std::process;
+use std::thread;
+
+/// Pilha da thread do interpretador. As chamadas de função Lucas crescem a
+/// pilha por conta própria; esta folga é para analisar código muito aninhado.
+const TAMANHO_PILHA: usize = 256 * 1024 * 1024;
+
+/// Código de saída quando o script falha durante a execução.
+const SAIDA_ERRO_EXECUCAO: i32 = 1;
+/// Código de saída quando o script tem erros léxicos, sintáticos ou
+/// semânticos e nem chega a executar.
+const SAIDA_ERRO_ANALISE: i32 = 2;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    
-    match args.len() {
-        1 => {
-            let mut repl = repl::Repl::new();
-            repl.run();
+    let executor = thread::Builder::new()
+        .stack_size(TAMANHO_PILHA)
+        .spawn(executar)
+        .unwrap_or_else(|err| {
+            eprintln!("Erro ao iniciar o interpretador: {}", err);
+            process::exit(1);
+        });
+    if executor.join().is_err() {
+        process::exit(101);
+    }
+}
+
+fn executar() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    // Opções antes do comando; o que vem depois do arquivo é do script
+    let mut usar_vm = false;
+    let mut resto = args.as_slice();
+    while let Some(opcao) = resto.first() {
+        match opcao.as_str() {
+            "--vm" => usar_vm = true,
+            _ => break,
+        }
+        resto = &resto[1..];
+    }
+
+    match resto {
+        [] => iniciar_repl(None),
+        [comando] if comando == "repl" => iniciar_repl(None),
+        [opcao] if opcao == "--versao" => println!("lucas {}", env!("CARGO_PKG_VERSION")),
+        [opcao] if opcao == "--ajuda" || opcao == "-h" => print_usage(),
+        [opcao, arquivo] if opcao == "-i" => iniciar_repl(Some(arquivo)),
+        [opcao, codigo, argumentos @ ..] if opcao == "-e" => {
+            run_source(codigo, None, argumentos, usar_vm);
         }
-        2 => {
-            let filename = &args[1];
-            run_file(filename);
+        [comando, arquivo, argumentos @ ..] if comando == "executar" => run_file(arquivo, argumentos, usar_vm),
+        [comando, arquivo] if comando == "verificar" => {
+            let mut interpreter = Interpreter::new();
+            interpreter.set_caminho(fs::canonicalize(arquivo).ok());
+            if !interpreter.verificar(&ler_fonte(arquivo)) {
+                process::exit(SAIDA_ERRO_ANALISE);
+            }
+            println!("{}: nenhum erro encontrado", arquivo);
+        }
+        // Um pipe fechado antes do fim (`| head`) só interrompe a listagem
+        [comando, opcoes @ ..] if comando == "fmt" => formatar(opcoes),
+        [comando, arquivos @ ..] if comando == "testar" => testar(arquivos, usar_vm),
+        // A listagem vai até onde der; os erros vêm depois, como em `verificar`
+        [comando, arquivo] if comando == "tokens" || comando == "ast" => {
+            let fonte = ler_fonte(arquivo);
+            if comando == "tokens" {
+                let _ = print_tokens(&fonte, &mut io::stdout());
+            } else {
+                let _ = print_ast(&fonte, &mut io::stdout());
+            }
+            let mut interpreter = Interpreter::new();
+            interpreter.set_caminho(fs::canonicalize(arquivo).ok());
+            if !interpreter.verificar_sintaxe(&fonte, comando == "ast") {
+                let _ = io::stdout().flush();
+                process::exit(SAIDA_ERRO_ANALISE);
+            }
+        }
+        [arquivo, argumentos @ ..] if arquivo == "-" || !arquivo.starts_with('-') => {
+            run_file(arquivo, argumentos, usar_vm)
         }
         _ => {
             print_usage();
@@ -31,23 +89,172 @@ fn main() {
     }
 }
 
-fn run_file(filename: &str) {
-    let contents = fs::read_to_string(filename).unwrap_or_else(|err| {
-        eprintln!("Erro ao ler arquivo '{}': {}", filename, err);
+/// `lucas fmt`: reescreve cada arquivo no estilo padrão. Com `--verificar`,
+/// só lista os que mudariam e sai com erro se houver algum. O arquivo `-` é
+/// lido da entrada padrão e o resultado vai para a saída padrão.
+fn formatar(opcoes: &[String]) {
+    let verificar = opcoes.iter().any(|o| o == "--verificar");
+    let arquivos: Vec<&String> = opcoes.iter().filter(|o| *o != "--verificar").collect();
+    if arquivos.is_empty() {
+        eprintln!("Uso: lucas fmt [--verificar] <arquivos...>");
+        process::exit(1);
+    }
+    let mut falhou = false;
+    for arquivo in arquivos {
+        let fonte = ler_fonte(arquivo);
+        let formatado = match lucas::formatar(&fonte) {
+            Ok(formatado) => formatado,
+            Err(erro) => {
+                eprintln!("Não foi possível formatar '{}':", arquivo);
+                erro.exibir();
+                falhou = true;
+                continue;
+            }
+        };
+        if verificar {
+            if formatado != fonte {
+                println!("{}: precisa ser formatado", arquivo);
+                falhou = true;
+            }
+        } else if arquivo == "-" {
+            print!("{}", formatado);
+        } else if formatado != fonte {
+            if let Err(err) = fs::write(arquivo, formatado) {
+                eprintln!("Erro ao escrever '{}': {}", arquivo, err);
+                falhou = true;
+            }
+        }
+    }
+    if falhou {
+        process::exit(1);
+    }
+}
+
+/// Inicia o REPL, depois de executar `arquivo` na sessão, se houver.
+#[cfg(feature = "repl")]
+fn iniciar_repl(arquivo: Option<&str>) {
+    let mut repl = repl::Repl::new();
+    if let Some(arquivo) = arquivo {
+        repl.carregar(arquivo);
+    }
+    repl.run();
+}
+
+#[cfg(not(feature = "repl"))]
+fn iniciar_repl(_arquivo: Option<&str>) {
+    eprintln!("Erro: este executável foi compilado sem o REPL (feature 'repl')");
+    process::exit(1);
+}
+
+/// Conteúdo do arquivo, ou da entrada padrão se `arquivo` for `-`.
+fn ler_fonte(arquivo: &str) -> String {
+    let lido = if arquivo == "-" {
+        let mut fonte = String::new();
+        io::stdin().read_to_string(&mut fonte).map(|_| fonte)
+    } else {
+        fs::read_to_string(arquivo)
+    };
+    lido.unwrap_or_else(|err| {
+        eprintln!("Erro ao ler arquivo '{}': {}", arquivo, err);
         process::exit(1);
-    });
+    })
+}
 
-    let mut interpreter = interpreter::Interpreter::new();
-    interpreter.run(&contents);
+fn run_file(filename: &str, argumentos: &[String], usar_vm: bool) {
+    let contents = ler_fonte(filename);
+    let caminho = if filename == "-" { None } else { Some(filename) };
+    run_source(&contents, caminho, argumentos, usar_vm);
+}
+
+/// Executa `contents`, com `argumentos` disponíveis no script como o array `argumentos`.
+/// `lucas testar`: executa cada arquivo e depois chama, sem argumentos, cada
+/// função global `teste_...` dele. Um teste falha se lançar um erro.
+fn testar(arquivos: &[String], usar_vm: bool) {
+    if arquivos.is_empty() {
+        eprintln!("Uso: lucas testar <arquivos...>");
+        process::exit(1);
+    }
+    let (mut total, mut falhas) = (0, 0);
+    for arquivo in arquivos {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_caminho(fs::canonicalize(arquivo).ok());
+        interpreter.set_usar_vm(usar_vm);
+        match interpreter.run(&ler_fonte(arquivo)) {
+            Ok(0) => {}
+            Ok(codigo) => {
+                println!("FALHOU {}: o arquivo saiu com o código {}", arquivo, codigo);
+                falhas += 1;
+                continue;
+            }
+            Err(_) => {
+                println!("FALHOU {}: o arquivo não executou até o fim", arquivo);
+                falhas += 1;
+                continue;
+            }
+        }
+        for nome in interpreter.funcoes_de_teste() {
+            total += 1;
+            match interpreter.call_function(&nome, Vec::new()) {
+                Ok(_) => println!("ok     {}: {}", arquivo, nome),
+                Err(erro) => {
+                    println!("FALHOU {}: {}", arquivo, nome);
+                    let _ = io::stdout().flush();
+                    erro.exibir();
+                    falhas += 1;
+                }
+            }
+        }
+    }
+    println!("\n{} testes, {} falhas", total, falhas);
+    if falhas > 0 {
+        process::exit(SAIDA_ERRO_EXECUCAO);
+    }
+}
+
+fn run_source(contents: &str, filename: Option<&str>, argumentos: &[String], usar_vm: bool) {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_caminho(filename.and_then(|f| fs::canonicalize(f).ok()));
+    interpreter.set_usar_vm(usar_vm);
+    let argumentos: Vec<Value> = argumentos.iter().map(|a| Value::from(a.as_str())).collect();
+    interpreter.set_global("argumentos", Value::from(argumentos));
+    let codigo = match interpreter.run(contents) {
+        Ok(codigo) => codigo,
+        Err(erro) if matches!(erro.tipo, ErrorType::RuntimeError) => SAIDA_ERRO_EXECUCAO,
+        Err(_) => SAIDA_ERRO_ANALISE,
+    };
+    if codigo != 0 {
+        // `process::exit` não esvazia o buffer da saída padrão
+        let _ = io::stdout().flush();
+        process::exit(codigo);
+    }
 }
 
 fn print_usage() {
     println!("🚀 Lucas - Lucas Language");
     println!("\nUso:");
-    println!("  lucas              - Inicia o REPL interativo");
-    println!("  lucas <arquivo>    - Executa um arquivo .lucas");
+    println!("  lucas                          - Inicia o REPL interativo");
+    println!("  lucas repl                     - Inicia o REPL interativo");
+    println!("  lucas <arquivo> [args...]      - Executa um arquivo .lucas");
+    println!("  lucas executar <arquivo> [args...] - Executa um arquivo .lucas");
+    println!("  lucas verificar <arquivo>      - Procura erros sem executar");
+    println!("  lucas fmt <arquivos...>        - Formata os arquivos no estilo padrão");
+    println!("  lucas fmt --verificar <arquivos...> - Só aponta os arquivos fora do estilo");
+    println!("  lucas testar <arquivos...>     - Executa as funções teste_... de cada arquivo");
+    println!("  lucas tokens <arquivo>         - Mostra os tokens do arquivo");
+    println!("  lucas ast <arquivo>            - Mostra a árvore sintática do arquivo");
+    println!("  lucas -e <código> [args...]    - Executa o código passado");
+    println!("  lucas -i <arquivo>             - Executa o arquivo e abre o REPL com as suas definições");
+    println!("  lucas --versao                 - Mostra a versão");
+    println!("\nUse '-' no lugar do arquivo para ler da entrada padrão.");
+    println!("Os argumentos depois do arquivo ficam no array 'argumentos' do script.");
+    println!("\nCódigos de saída: 0 sucesso, {} erro de execução, {} erro de sintaxe;", SAIDA_ERRO_EXECUCAO, SAIDA_ERRO_ANALISE);
+    println!("o script pode escolher outro com sair(codigo).");
+    println!("\nOpções:");
+    println!("  --vm                           - Executa compilando para bytecode");
     println!("\nExemplos:");
-    println!("  lucas                    # REPL");
-    println!("  lucas programa.lucas     # Executa arquivo");
-    println!("  lucas exemplos/ola.lucas # Executa exemplo");
-}
\ No newline at end of file
+    println!("  lucas                          # REPL");
+    println!("  lucas programa.lucas           # Executa arquivo");
+    println!("  lucas exemplos/ola.lucas       # Executa exemplo");
+    println!("  lucas -e 'imprimir(1 + 2)'     # Executa código");
+    println!("  echo 'imprimir(1)' | lucas -   # Lê da entrada padrão");
+}
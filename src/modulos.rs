@@ -0,0 +1,175 @@
+use crate::environment::Environment;
+use crate::interpreter::{ErroExecucao, Interpreter, Value};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Estado de importação do interpretador: módulos já carregados e a pilha
+/// de arquivos em carregamento, usada para detectar importações circulares.
+#[derive(Default)]
+pub struct Modulos {
+    /// Nomes exportados por módulo; cada importação recebe o seu dicionário
+    cache: BTreeMap<PathBuf, BTreeMap<String, Value>>,
+    /// Começa pelo arquivo que fez a primeira importação, se houver
+    carregando: Vec<PathBuf>,
+    /// Nomes marcados com `exportar` no módulo que está sendo executado
+    exportados: Option<Vec<String>>,
+}
+
+/// Procura o módulo relativo ao diretório `base` e depois em cada diretório de
+/// `LUCAS_PATH`. A extensão `.lucas` é opcional.
+pub fn resolver_caminho(nome: &str, base: Option<&Path>) -> Result<PathBuf, String> {
+    let mut diretorios: Vec<PathBuf> = Vec::new();
+    match base {
+        Some(b) => diretorios.push(b.to_path_buf()),
+        None => diretorios.push(PathBuf::from(".")),
+    }
+    if let Some(caminhos) = env::var_os("LUCAS_PATH") {
+        diretorios.extend(env::split_paths(&caminhos));
+    }
+
+    let candidatos = |dir: &Path| {
+        let caminho = dir.join(nome);
+        let mut opcoes = vec![caminho.clone()];
+        if caminho.extension().is_none() {
+            opcoes.push(caminho.with_extension("lucas"));
+        }
+        opcoes
+    };
+
+    for dir in &diretorios {
+        for candidato in candidatos(dir) {
+            if candidato.is_file() {
+                return candidato
+                    .canonicalize()
+                    .map_err(|e| format!("Erro ao abrir módulo '{}': {}", nome, crate::errors::descrever_erro_io(&e)));
+            }
+        }
+    }
+
+    let procurados: Vec<String> = diretorios.iter().map(|d| d.display().to_string()).collect();
+    Err(format!("Módulo '{}' não encontrado (procurado em: {})", nome, procurados.join(", ")))
+}
+
+fn nome_curto(caminho: &Path) -> String {
+    caminho
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| caminho.display().to_string())
+}
+
+impl Interpreter {
+    /// Carrega (ou reutiliza do cache) o módulo `nome` e devolve um dicionário
+    /// com os nomes exportados. Sem acesso ao sistema nada é lido do disco.
+    pub(crate) fn carregar_modulo(&mut self, nome: &str) -> Result<Value, ErroExecucao> {
+        self.limites.exigir_acesso_sistema(&format!("Importar o módulo '{}'", nome))?;
+        let base = self.caminho.as_ref().and_then(|c| c.parent()).map(Path::to_path_buf);
+        let caminho = resolver_caminho(nome, base.as_deref())
+            .map_err(|e| ErroExecucao::novo("ModuloNaoEncontrado", e))?;
+
+        if let Some(exportados) = self.modulos.cache.get(&caminho) {
+            return Ok(Value::Dicionario(Rc::new(RefCell::new(exportados.clone()))));
+        }
+
+        // Na primeira importação, quem importa é o arquivo principal, que
+        // também pode fazer parte de um ciclo
+        let principal = if self.modulos.carregando.is_empty() { self.caminho.clone() } else { None };
+        let pilha: Vec<&PathBuf> = principal.iter().chain(&self.modulos.carregando).collect();
+        if pilha.contains(&&caminho) {
+            let mut ciclo: Vec<String> = pilha.iter().map(|c| nome_curto(c)).collect();
+            ciclo.push(nome_curto(&caminho));
+            return Err(ErroExecucao::novo(
+                "ImportacaoCircular",
+                format!("Importação circular: {}", ciclo.join(" → ")),
+            ));
+        }
+
+        let fonte = fs::read_to_string(&caminho).map_err(|e| {
+            ErroExecucao::novo(
+                "ModuloNaoEncontrado",
+                format!("Erro ao ler módulo '{}': {}", nome, crate::errors::descrever_erro_io(&e)),
+            )
+        })?;
+
+        // O módulo enxerga apenas as funções nativas, não as globais de quem importa
+        let nativas = Rc::new(RefCell::new(Environment::new()));
+        crate::builtins::registrar(&nativas);
+        let escopo = Rc::new(RefCell::new(Environment::with_enclosing(nativas)));
+
+        let em_carregamento = self.modulos.carregando.len();
+        self.modulos.carregando.extend(principal);
+        self.modulos.carregando.push(caminho.clone());
+        let prev_env = std::mem::replace(&mut self.env, escopo.clone());
+        let prev_caminho = self.caminho.replace(caminho.clone());
+        let prev_exportados = self.modulos.exportados.take();
+
+        let resultado = match self.analisar(&fonte) {
+            Ok(stmts) => self.executar(&stmts),
+            Err(erros) => Err(erros[0].to_erro()),
+        };
+
+        let exportados = std::mem::replace(&mut self.modulos.exportados, prev_exportados);
+        self.caminho = prev_caminho;
+        self.env = prev_env;
+        self.modulos.carregando.truncate(em_carregamento);
+
+        resultado.map_err(|e| {
+            // A mensagem do ciclo já diz por onde a importação passou
+            if e.tipo == "ImportacaoCircular" {
+                return e;
+            }
+            let onde = e.linha.map_or(String::new(), |l| format!(", linha {}", l));
+            ErroExecucao {
+                mensagem: format!("{} (no módulo '{}'{})", e.mensagem, nome_curto(&caminho), onde),
+                linha: None,
+                rastro: None,
+                ..e
+            }
+        })?;
+
+        // Sem nenhum `exportar`, todos os nomes do nível principal ficam visíveis
+        let definidos = escopo.borrow().get_all_variables();
+        let mut visiveis = BTreeMap::new();
+        match exportados {
+            Some(nomes) => {
+                for nome_exportado in nomes {
+                    let valor = definidos
+                        .iter()
+                        .find(|(n, _)| *n == nome_exportado)
+                        .map(|(_, v)| v.clone())
+                        .ok_or_else(|| {
+                            ErroExecucao::novo(
+                                "ErroImportacao",
+                                format!(
+                                    "Nome exportado '{}' não está definido no módulo '{}'",
+                                    nome_exportado,
+                                    nome_curto(&caminho)
+                                ),
+                            )
+                        })?;
+                    visiveis.insert(nome_exportado, valor);
+                }
+            }
+            None => visiveis.extend(definidos),
+        }
+
+        self.modulos.cache.insert(caminho, visiveis.clone());
+        Ok(Value::Dicionario(Rc::new(RefCell::new(visiveis))))
+    }
+
+    /// Registra nomes de `exportar` no módulo em execução.
+    pub(crate) fn marcar_exportados(&mut self, nomes: &[String]) {
+        self.modulos.exportados.get_or_insert_with(Vec::new).extend(nomes.iter().cloned());
+    }
+}
+
+/// Nome padrão do módulo para `importar "util.lucas";` sem `como`.
+pub fn nome_padrao(caminho: &str) -> String {
+    Path::new(caminho)
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| caminho.to_string())
+}
@@ -1,44 +1,201 @@
 use crate::tokens::{Token, TokenType};
-use crate::ast::{Expr, Stmt, UnarioOp, BinOp};
-use std::iter::Peekable;
-use std::vec::IntoIter;
+use crate::ast::{Expr, Stmt, UnarioOp, BinOp, Captura};
+use crate::resolver::{Diagnostico, ERRO_SINTATICO};
 
 pub struct Parser {
-    tokens: Peekable<IntoIter<Token>>,
+    tokens: Vec<Token>,
+    pos: usize,
+    /// Primeiro erro de sintaxe; a análise para no comando onde ele ocorreu
+    pub erro: Option<Diagnostico>,
+    /// Índice do primeiro token de cada comando devolvido por `parse`
+    pub inicios: Vec<usize>,
+    /// Índice do primeiro token de cada comando do programa ou de um bloco
+    /// entre chaves, em ordem
+    pub comandos: Vec<usize>,
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
-        Self { tokens: tokens.into_iter().peekable() }
+        Self { tokens, pos: 0, erro: None, inicios: Vec::new(), comandos: Vec::new() }
     }
 
-    fn peek(&mut self) -> Option<Token> { self.tokens.peek().cloned() }
-    fn advance(&mut self) -> Option<Token> { self.tokens.next() }
+    /// Registra um erro de sintaxe em `tok`, se for o primeiro.
+    fn erro(&mut self, tok: &Token, mensagem: &str) {
+        if self.erro.is_some() { return; }
+        if tok.tipo != TokenType::Fim {
+            let texto = format!("{} perto de '{}'", mensagem, tok.lexema);
+            self.erro = Some(Diagnostico::novo(ERRO_SINTATICO, texto, tok.linha).na_coluna(tok.coluna));
+            return;
+        }
+        // O fim pode estar numa linha vazia; aponta logo depois do último token
+        let texto = format!("{} no fim do código", mensagem);
+        let (linha, coluna) = match self.tokens.len().checked_sub(2).map(|i| &self.tokens[i]) {
+            Some(ultimo) => (ultimo.linha, ultimo.coluna + ultimo.lexema.chars().count()),
+            None => (tok.linha, tok.coluna),
+        };
+        self.erro = Some(Diagnostico::novo(ERRO_SINTATICO, texto, linha).na_coluna(coluna));
+    }
+
+    /// Consome o próximo token, registrando um erro se não for `tipo`.
+    fn fechar(&mut self, tipo: TokenType, mensagem: &str) {
+        if let Some(t) = self.advance() { if t.tipo != tipo { self.erro(&t, mensagem); } }
+    }
+
+    fn peek(&mut self) -> Option<Token> { self.tokens.get(self.pos).cloned() }
+    fn peek_at(&self, offset: usize) -> Option<&Token> { self.tokens.get(self.pos + offset) }
+    fn advance(&mut self) -> Option<Token> {
+        let tok = self.tokens.get(self.pos).cloned();
+        if tok.is_some() { self.pos += 1; }
+        tok
+    }
+
+    fn is_ident(tok: Option<&Token>, texto: &str) -> bool {
+        matches!(tok, Some(Token { tipo: TokenType::Ident(n), .. }) if n == texto)
+    }
+
+    fn is_nome(tok: Option<&Token>) -> bool {
+        matches!(tok, Some(Token { tipo: TokenType::Ident(_) | TokenType::E, .. }))
+    }
+
+    /// Depois de `(`, verifica se o cabeçalho é `item em ...` ou `i, item em ...`.
+    fn is_para_cada(&self) -> bool {
+        let simples = Self::is_nome(self.peek_at(1)) && Self::is_ident(self.peek_at(2), "em");
+        let com_indice = Self::is_nome(self.peek_at(1))
+            && matches!(self.peek_at(2), Some(Token { tipo: TokenType::Virgula, .. }))
+            && Self::is_nome(self.peek_at(3))
+            && Self::is_ident(self.peek_at(4), "em");
+        simples || com_indice
+    }
+
+    /// Com `(` no token atual, diz se a expressão segue depois do `)` que o
+    /// fecha, como em `imprimir (a + b) * 2`.
+    fn expressao_continua_apos_parenteses(&self) -> bool {
+        use TokenType::*;
+        let mut profundidade = 0;
+        for (i, tok) in self.tokens.iter().enumerate().skip(self.pos) {
+            match tok.tipo {
+                AbrePar => profundidade += 1,
+                FechaPar => profundidade -= 1,
+                Fim => return false,
+                _ => {}
+            }
+            if profundidade == 0 {
+                return matches!(
+                    self.tokens.get(i + 1).map(|t| &t.tipo),
+                    Some(
+                        Mais | Menos | Multiplica | Divide | IgualIgual | Diferente | Menor | MenorIgual | Maior
+                            | MaiorIgual | E | Ou | Ponto | AbreColchete | PontoPonto | PontoPontoIgual
+                    )
+                );
+            }
+        }
+        false
+    }
+
+    /// Nome de variável ou parâmetro. Aceita também `e`, que só é o operador
+    /// lógico em posição infixa (veja `tokens::e_operador`); assim valem
+    /// `capturar (e)` e `e.mensagem`, e `a e b` é sempre a conjunção.
+    fn nome(tok: Token) -> Option<String> {
+        match tok.tipo {
+            TokenType::Ident(name) => Some(name),
+            TokenType::E => Some(tok.lexema),
+            _ => None,
+        }
+    }
 
     pub fn parse(&mut self) -> Vec<Stmt> {
         let mut stmts = vec![];
         while let Some(t) = self.peek() {
             if t.tipo == TokenType::Fim { break; }
-            if let Some(s) = self.declaration() { stmts.push(s); } else { break; }
+            let inicio = self.pos;
+            self.comandos.push(inicio);
+            if let Some(s) = self.declaration() { stmts.push(s); self.inicios.push(inicio); } else { self.comando_invalido(); break; }
         }
         stmts
     }
 
+    /// Aponta o token onde um comando deixou de ser reconhecido.
+    fn comando_invalido(&mut self) {
+        let ultimo = self.tokens.len().saturating_sub(1);
+        if let Some(tok) = self.tokens.get(self.pos.saturating_sub(1).min(ultimo)).cloned() {
+            self.erro(&tok, "Sintaxe inválida");
+        }
+    }
+
     fn declaration(&mut self) -> Option<Stmt> {
         if let Some(t) = self.peek() {
             match t.tipo {
-                TokenType::Variavel => { self.advance(); return self.var_declaration(); }
+                TokenType::Variavel => { self.advance(); return self.var_declaration(t.linha); }
+                TokenType::Constante => {
+                    self.advance();
+                    return match self.var_declaration(t.linha)? {
+                        Stmt::VarDecl(name, init, linha) => Some(Stmt::Constante(name, init, linha)),
+                        _ => None,
+                    };
+                }
                 TokenType::Funcao => { self.advance(); return self.func_declaration(); }
+                TokenType::Importar => { self.advance(); return self.importar(t.linha); }
+                TokenType::Exportar => { self.advance(); return self.exportar(); }
                 _ => {}
             }
         }
+        if Self::is_ident(self.peek_at(0), "de") && matches!(self.peek_at(1), Some(Token { tipo: TokenType::Texto(_), .. })) {
+            return self.de_importar();
+        }
         self.statement()
     }
 
-    fn var_declaration(&mut self) -> Option<Stmt> {
-        let name = if let Some(tok) = self.advance() {
-            if let TokenType::Ident(_) = tok.tipo { tok.lexema } else { return None; }
-        } else { return None; };
+    fn caminho_modulo(&mut self) -> Option<String> {
+        if let TokenType::Texto(caminho) = self.advance()?.tipo { Some(caminho) } else { None }
+    }
+
+    fn lista_nomes(&mut self) -> Option<Vec<String>> {
+        let mut nomes = vec![Self::nome(self.advance()?)?];
+        while let Some(p) = self.peek() {
+            if p.tipo != TokenType::Virgula { break; }
+            self.advance();
+            nomes.push(Self::nome(self.advance()?)?);
+        }
+        if let Some(p) = self.peek() { if p.tipo == TokenType::PontoVirgula { self.advance(); } }
+        Some(nomes)
+    }
+
+    fn importar(&mut self, linha: usize) -> Option<Stmt> {
+        let caminho = self.caminho_modulo()?;
+        let apelido = if Self::is_ident(self.peek_at(0), "como") {
+            self.advance();
+            Self::nome(self.advance()?)?
+        } else {
+            crate::modulos::nome_padrao(&caminho)
+        };
+        if let Some(p) = self.peek() { if p.tipo == TokenType::PontoVirgula { self.advance(); } }
+        Some(Stmt::Importar(caminho, apelido, linha))
+    }
+
+    fn de_importar(&mut self) -> Option<Stmt> {
+        let linha = self.advance()?.linha;
+        let caminho = self.caminho_modulo()?;
+        if self.advance()?.tipo != TokenType::Importar { return None; }
+        Some(Stmt::DeImportar(caminho, self.lista_nomes()?, linha))
+    }
+
+    fn exportar(&mut self) -> Option<Stmt> {
+        let t = self.peek()?;
+        match t.tipo {
+            TokenType::Variavel | TokenType::Constante | TokenType::Funcao => {
+                let decl = self.declaration()?;
+                let nome = match &decl {
+                    Stmt::VarDecl(nome, _, _) | Stmt::Constante(nome, _, _) | Stmt::FuncDecl(nome, _, _) => nome.clone(),
+                    _ => return None,
+                };
+                Some(Stmt::Exportar(vec![nome], Some(Box::new(decl))))
+            }
+            _ => Some(Stmt::Exportar(self.lista_nomes()?, None)),
+        }
+    }
+
+    fn var_declaration(&mut self, linha: usize) -> Option<Stmt> {
+        let name = Self::nome(self.advance()?)?;
 
         if let Some(tok) = self.advance() {
             if tok.tipo != TokenType::Igual { return None; }
@@ -49,7 +206,7 @@ impl Parser {
         if let Some(peek) = self.peek() {
             if peek.tipo == TokenType::PontoVirgula { self.advance(); }
         }
-        Some(Stmt::VarDecl(name, init))
+        Some(Stmt::VarDecl(name, init, linha))
     }
 
     fn func_declaration(&mut self) -> Option<Stmt> {
@@ -57,16 +214,13 @@ impl Parser {
             if let TokenType::Ident(_) = tok.tipo { tok.lexema } else { return None; }
         } else { return None; };
 
-        let tok = self.advance(); if tok.is_none() { return None; }
-        if tok.unwrap().tipo != TokenType::AbrePar { return None; }
+        if self.advance()?.tipo != TokenType::AbrePar { return None; }
 
         let mut params = Vec::new();
         if let Some(p) = self.peek() {
             if p.tipo != TokenType::FechaPar {
                 loop {
-                    if let Some(tok) = self.advance() {
-                        if let TokenType::Ident(_) = tok.tipo { params.push(tok.lexema); } else { return None }
-                    } else { return None; }
+                    params.push(Self::nome(self.advance()?)?);
                     if let Some(peek) = self.peek() {
                         if peek.tipo == TokenType::Virgula { self.advance(); continue; } else { break; }
                     }
@@ -82,15 +236,24 @@ impl Parser {
     }
 
     fn statement(&mut self) -> Option<Stmt> {
+        let linha = self.peek().map_or(0, |t| t.linha);
         if let Some(tok) = self.peek() {
             match tok.tipo {
                 TokenType::Imprimir => { 
                     self.advance(); 
+                    if let Some(p) = self.peek() {
+                        if p.tipo == TokenType::AbrePar && !self.expressao_continua_apos_parenteses() {
+                            // `imprimir(a, b)` chama a função nativa, que aceita vários argumentos
+                            let call = self.postfix(Expr::Var("imprimir".into(), None));
+                            if let Some(s) = self.peek() { if s.tipo == TokenType::PontoVirgula { self.advance(); } }
+                            return Some(Stmt::ExprStmt(call, linha));
+                        }
+                    }
                     let expr = self.expression(); 
                     if let Some(s) = self.peek() { 
                         if s.tipo == TokenType::PontoVirgula { self.advance(); } 
                     } 
-                    return Some(Stmt::Imprimir(expr)); 
+                    return Some(Stmt::Imprimir(expr, linha)); 
                 }
                 TokenType::AbreChave => return self.block(),
                 TokenType::Se => {
@@ -103,7 +266,7 @@ impl Parser {
                     if let Some(peek) = self.peek() {
                         if peek.tipo == TokenType::Senao { self.advance(); if let Some(s) = self.statement() { else_branch = Some(Box::new(s)); } }
                     }
-                    return Some(Stmt::If(cond, then_branch, else_branch));
+                    return Some(Stmt::If(cond, then_branch, else_branch, linha));
                 }
                 TokenType::Enquanto => {
                     self.advance();
@@ -111,10 +274,13 @@ impl Parser {
                     let cond = self.expression();
                     if let Some(t) = self.advance() { if t.tipo != TokenType::FechaPar { return None; } } else { return None; }
                     let body = self.statement().map(Box::new)?;
-                    return Some(Stmt::While(cond, body));
+                    return Some(Stmt::While(cond, body, linha));
                 }
                 TokenType::Para => {
                     self.advance();
+                    let cada = Self::is_ident(self.peek_at(0), "cada");
+                    if cada { self.advance(); }
+                    if cada || self.is_para_cada() { return self.para_cada(linha); }
                     if let Some(t) = self.advance() { if t.tipo != TokenType::AbrePar { return None; } } else { return None; }
 
                     let init = if let Some(peek) = self.peek() {
@@ -124,41 +290,99 @@ impl Parser {
                     let cond = if let Some(peek) = self.peek() {
                         if peek.tipo == TokenType::PontoVirgula { None } else { Some(self.expression()) }
                     } else { None };
+                    if let Some(p) = self.peek() { if p.tipo == TokenType::PontoVirgula { self.advance(); } }
 
                     let incr = if let Some(peek) = self.peek() {
                         if peek.tipo == TokenType::FechaPar { None } else { Some(self.expression()) }
                     } else { None };
 
                     if let Some(t) = self.advance() { if t.tipo != TokenType::FechaPar {return None; } } else { return None; }
-                    let body = self.statement().map(|s| s).unwrap_or(Stmt::Bloco(vec![]));
+                    let body = self.statement().unwrap_or(Stmt::Bloco(vec![]));
 
-                    let mut stmts = Vec::new();
-                    if let Some(init_stmt) = init { stmts.push(init_stmt); }
-                    let while_cond = cond.unwrap_or(Expr::Bool(true));
-
-                    let mut inner = Vec::new();
-                    inner.push(body);
-                    if let Some(inc_expr) = incr {
-                        inner.push(Stmt::ExprStmt(inc_expr));
-                    }
-
-                    let while_stmt = Stmt::While(while_cond, Box::new(Stmt::Bloco(inner)));
-                    stmts.push(while_stmt);
-                    return Some(Stmt::Bloco(stmts));
+                    return Some(Stmt::For(init.map(Box::new), cond, incr, Box::new(body)));
+                }
+                TokenType::Break => {
+                    self.advance();
+                    if let Some(p) = self.peek() { if p.tipo == TokenType::PontoVirgula { self.advance(); } }
+                    return Some(Stmt::Break);
+                }
+                TokenType::Continue => {
+                    self.advance();
+                    if let Some(p) = self.peek() { if p.tipo == TokenType::PontoVirgula { self.advance(); } }
+                    return Some(Stmt::Continue);
                 }
                 TokenType::Retornar => {
                     self.advance();
 
                     let expr = if let Some(p) = self.peek() { if p.tipo == TokenType::PontoVirgula { None } else { Some(self.expression()) } } else { None };
                     if let Some(p) = self.peek() { if p.tipo == TokenType::PontoVirgula { self.advance(); } }
-                    return Some(Stmt::Return(expr));
+                    return Some(Stmt::Return(expr, linha));
+                }
+                TokenType::Tentar => {
+                    self.advance();
+                    let corpo = if let Some(Stmt::Bloco(stmts)) = self.block() { stmts } else { return None; };
+
+                    let mut captura = None;
+                    if let Some(p) = self.peek() {
+                        if p.tipo == TokenType::Capturar {
+                            self.advance();
+                            let mut nome = None;
+                            if let Some(p2) = self.peek() {
+                                if p2.tipo == TokenType::AbrePar {
+                                    self.advance();
+                                    nome = Some(Self::nome(self.advance()?)?);
+                                    if let Some(t) = self.advance() { if t.tipo != TokenType::FechaPar { return None; } } else { return None; }
+                                }
+                            }
+                            let corpo = if let Some(Stmt::Bloco(stmts)) = self.block() { stmts } else { return None; };
+                            captura = Some(Captura { nome, corpo });
+                        }
+                    }
+
+                    let mut finalmente = None;
+                    if let Some(p) = self.peek() {
+                        if p.tipo == TokenType::Finalmente {
+                            self.advance();
+                            if let Some(Stmt::Bloco(stmts)) = self.block() { finalmente = Some(stmts); } else { return None; }
+                        }
+                    }
+
+                    if captura.is_none() && finalmente.is_none() { return None; }
+                    return Some(Stmt::Tentar(corpo, captura, finalmente));
+                }
+                TokenType::Lancar => {
+                    self.advance();
+                    let expr = self.expression();
+                    if let Some(p) = self.peek() { if p.tipo == TokenType::PontoVirgula { self.advance(); } }
+                    return Some(Stmt::Lancar(expr, linha));
                 }
                 _ => {}
             }
         }
         let expr = self.expression();
         if let Some(p) = self.peek() { if p.tipo == TokenType::PontoVirgula { self.advance(); } }
-        Some(Stmt::ExprStmt(expr))
+        Some(Stmt::ExprStmt(expr, linha))
+    }
+
+    fn para_cada(&mut self, linha: usize) -> Option<Stmt> {
+        if self.advance()?.tipo != TokenType::AbrePar { return None; }
+
+        let mut item = Self::nome(self.advance()?)?;
+        let mut indice = None;
+        if let Some(p) = self.peek() {
+            if p.tipo == TokenType::Virgula {
+                self.advance();
+                indice = Some(item);
+                item = Self::nome(self.advance()?)?;
+            }
+        }
+        if !Self::is_ident(self.advance().as_ref(), "em") { return None; }
+
+        let iteravel = self.expression();
+
+        if self.advance()?.tipo != TokenType::FechaPar { return None; }
+        let body = self.statement().map(Box::new)?;
+        Some(Stmt::ParaCada(indice, item, iteravel, body, linha))
     }
 
     fn block(&mut self) -> Option<Stmt> {
@@ -168,14 +392,35 @@ impl Parser {
         let mut stmts = Vec::new();
         while let Some(peek) = self.peek() {
             if peek.tipo == TokenType::FechaChave { self.advance(); return Some(Stmt::Bloco(stmts)); }
-            if peek.tipo == TokenType::Fim { return None; }
-            if let Some(s) = self.declaration() { stmts.push(s); } else { break; }
+            if peek.tipo == TokenType::Fim { self.erro(&peek, "Esperado '}'"); return None; }
+            self.comandos.push(self.pos);
+            if let Some(s) = self.declaration() { stmts.push(s); } else { self.comando_invalido(); break; }
         }
         None
     }
 
     fn expression(&mut self) -> Expr {
-        self.or()
+        self.assignment()
+    }
+
+    fn assignment(&mut self) -> Expr {
+        let expr = self.or();
+        if let Some(peek) = self.peek() {
+            if peek.tipo == TokenType::Igual {
+                self.advance();
+                let value = self.assignment();
+                return match expr {
+                    Expr::Var(name, _) => Expr::Atribuir(name, Box::new(value), None),
+                    Expr::Index(alvo, idx) => Expr::AtribuirIndex(alvo, idx, Box::new(value)),
+                    // `1 = 2`, `f() = 3`: o erro aponta o `=`
+                    outro => {
+                        self.erro(&peek, "Alvo de atribuição inválido");
+                        outro
+                    }
+                };
+            }
+        }
+        expr
     }
 
     fn or(&mut self) -> Expr {
@@ -196,58 +441,68 @@ impl Parser {
 
     fn equality(&mut self) -> Expr {
         let mut expr = self.comparison();
-        loop {
-            if let Some(peek) = self.peek() {
-                match peek.tipo {
-                    TokenType::IgualIgual => { self.advance(); let right = self.comparison(); expr = Expr::Binario(Box::new(expr), BinOp::Eq, Box::new(right)); }
-                    TokenType::Diferente => { self.advance(); let right = self.comparison(); expr = Expr::Binario(Box::new(expr), BinOp::Neq, Box::new(right)); }
-                    _ => break
-                }
-            } else { break; }
+        while let Some(peek) = self.peek() {
+            match peek.tipo {
+                TokenType::IgualIgual => { self.advance(); let right = self.comparison(); expr = Expr::Binario(Box::new(expr), BinOp::Eq, Box::new(right)); }
+                TokenType::Diferente => { self.advance(); let right = self.comparison(); expr = Expr::Binario(Box::new(expr), BinOp::Neq, Box::new(right)); }
+                _ => break
+            }
         }
         expr
     }
 
     fn comparison(&mut self) -> Expr {
-        let mut expr = self.term();
-        loop {
-            if let Some(peek) = self.peek() {
-                match peek.tipo {
-                    TokenType::Maior => { self.advance(); let r = self.term(); expr = Expr::Binario(Box::new(expr), BinOp::Gt, Box::new(r)); }
-                    TokenType::MaiorIgual => { self.advance(); let r = self.term(); expr = Expr::Binario(Box::new(expr), BinOp::Ge, Box::new(r)); }
-                    TokenType::Menor => { self.advance(); let r = self.term(); expr = Expr::Binario(Box::new(expr), BinOp::Lt, Box::new(r)); }
-                    TokenType::MenorIgual => { self.advance(); let r = self.term(); expr = Expr::Binario(Box::new(expr), BinOp::Le, Box::new(r)); }
-                    _ => break
-                }
-            } else { break; }
+        let mut expr = self.range();
+        while let Some(peek) = self.peek() {
+            match peek.tipo {
+                TokenType::Maior => { self.advance(); let r = self.range(); expr = Expr::Binario(Box::new(expr), BinOp::Gt, Box::new(r)); }
+                TokenType::MaiorIgual => { self.advance(); let r = self.range(); expr = Expr::Binario(Box::new(expr), BinOp::Ge, Box::new(r)); }
+                TokenType::Menor => { self.advance(); let r = self.range(); expr = Expr::Binario(Box::new(expr), BinOp::Lt, Box::new(r)); }
+                TokenType::MenorIgual => { self.advance(); let r = self.range(); expr = Expr::Binario(Box::new(expr), BinOp::Le, Box::new(r)); }
+                _ => break
+            }
         }
         expr
     }
 
+    fn is_range_op(tok: Option<&Token>) -> bool {
+        matches!(tok, Some(Token { tipo: TokenType::PontoPonto | TokenType::PontoPontoIgual, .. }))
+    }
+
+    /// `a..b`, `a..=b` e as formas abertas `..b` e `a..` usadas em fatias.
+    fn range(&mut self) -> Expr {
+        let inicio = if Self::is_range_op(self.peek_at(0)) { None } else { Some(self.term()) };
+        if !Self::is_range_op(self.peek_at(0)) {
+            return inicio.unwrap_or(Expr::Nulo);
+        }
+        let inclusivo = self.advance().is_some_and(|t| t.tipo == TokenType::PontoPontoIgual);
+
+        let fecha = matches!(self.peek_at(0).map(|t| &t.tipo),
+            None | Some(TokenType::FechaColchete | TokenType::FechaPar | TokenType::Virgula | TokenType::PontoVirgula | TokenType::FechaChave | TokenType::Fim));
+        let fim = if fecha { None } else { Some(self.term()) };
+        Expr::Intervalo(inicio.map(Box::new), fim.map(Box::new), inclusivo)
+    }
+
     fn term(&mut self) -> Expr {
         let mut expr = self.factor();
-        loop {
-            if let Some(peek) = self.peek() {
-                match peek.tipo {
-                    TokenType::Mais => { self.advance(); let r = self.factor(); expr = Expr::Binario(Box::new(expr), BinOp::Add, Box::new(r)); }
-                    TokenType::Menos => { self.advance(); let r = self.factor(); expr = Expr::Binario(Box::new(expr), BinOp::Sub, Box::new(r)); }
-                    _ => break
-                }
-            } else { break; }
+        while let Some(peek) = self.peek() {
+            match peek.tipo {
+                TokenType::Mais => { self.advance(); let r = self.factor(); expr = Expr::Binario(Box::new(expr), BinOp::Add, Box::new(r)); }
+                TokenType::Menos => { self.advance(); let r = self.factor(); expr = Expr::Binario(Box::new(expr), BinOp::Sub, Box::new(r)); }
+                _ => break
+            }
         }
         expr
     }
 
     fn factor(&mut self) -> Expr {
         let mut expr = self.unary();
-        loop {
-            if let Some(peek) = self.peek() {
-                match peek.tipo {
-                    TokenType::Multiplica => { self.advance(); let r = self.unary(); expr = Expr::Binario(Box::new(expr), BinOp::Mul, Box::new(r)); }
-                    TokenType::Divide => { self.advance(); let r = self.unary(); expr = Expr::Binario(Box::new(expr), BinOp::Div, Box::new(r)); }
-                    _ => break
-                }
-            } else { break; }
+        while let Some(peek) = self.peek() {
+            match peek.tipo {
+                TokenType::Multiplica => { self.advance(); let r = self.unary(); expr = Expr::Binario(Box::new(expr), BinOp::Mul, Box::new(r)); }
+                TokenType::Divide => { self.advance(); let r = self.unary(); expr = Expr::Binario(Box::new(expr), BinOp::Div, Box::new(r)); }
+                _ => break
+            }
         }
         expr
     }
@@ -264,7 +519,11 @@ impl Parser {
     }
 
     fn call(&mut self) -> Expr {
-        let mut expr = self.primary();
+        let expr = self.primary();
+        self.postfix(expr)
+    }
+
+    fn postfix(&mut self, mut expr: Expr) -> Expr {
         loop {
             if let Some(peek) = self.peek() {
                 if peek.tipo == TokenType::AbrePar {
@@ -282,10 +541,29 @@ impl Parser {
                             }
                         }
                     }
-                    if let Some(t) = self.advance() { if t.tipo != TokenType::FechaPar { } }
+                    self.fechar(TokenType::FechaPar, "Esperado ')'");
                     expr = Expr::Chamada(Box::new(expr), args);
                     continue;
                 }
+                if peek.tipo == TokenType::AbreColchete {
+                    self.advance();
+                    let idx = self.expression();
+                    self.fechar(TokenType::FechaColchete, "Esperado ']'");
+                    expr = Expr::Index(Box::new(expr), Box::new(idx));
+                    continue;
+                }
+                if peek.tipo == TokenType::Ponto {
+                    // `obj.campo` é açúcar para `obj["campo"]`
+                    self.advance();
+                    if let Some(tok) = self.advance() {
+                        if let TokenType::Ident(campo) = tok.tipo {
+                            expr = Expr::Index(Box::new(expr), Box::new(Expr::Texto(campo)));
+                            continue;
+                        }
+                        self.erro(&tok, "Nome do campo esperado");
+                    }
+                    break;
+                }
             }
             break;
         }
@@ -300,13 +578,44 @@ impl Parser {
                 TokenType::Verdadeiro => Expr::Bool(true),
                 TokenType::Falso => Expr::Bool(false),
                 TokenType::Nulo => Expr::Nulo,
-                TokenType::Ident(name) => Expr::Var(name),
+                TokenType::Ident(name) => Expr::Var(name, None),
+                // No início de um operando, `e` é a variável
+                TokenType::E => Expr::Var(tok.lexema, None),
                 TokenType::AbrePar => {
                     let e = self.expression();
-                    if let Some(t) = self.advance() { if t.tipo != TokenType::FechaPar { } }
+                    self.fechar(TokenType::FechaPar, "Esperado ')'");
                     e
                 }
-                _ => Expr::Nulo,
+                TokenType::AbreColchete => {
+                    let mut elementos = Vec::new();
+                    while let Some(p) = self.peek() {
+                        if p.tipo == TokenType::FechaColchete || p.tipo == TokenType::Fim { break; }
+                        elementos.push(self.expression());
+                        if let Some(nxt) = self.peek() {
+                            if nxt.tipo == TokenType::Virgula { self.advance(); continue; }
+                        }
+                        break;
+                    }
+                    self.fechar(TokenType::FechaColchete, "Esperado ']'");
+                    Expr::Array(elementos)
+                }
+                TokenType::AbreChave => {
+                    let mut pares = Vec::new();
+                    while let Some(p) = self.peek() {
+                        if p.tipo == TokenType::FechaChave || p.tipo == TokenType::Fim { break; }
+                        let chave = self.expression();
+                        if let Some(t) = self.advance() { if t.tipo != TokenType::DoisPontos { self.erro(&t, "Esperado ':'"); break; } }
+                        let valor = self.expression();
+                        pares.push((chave, valor));
+                        if let Some(nxt) = self.peek() {
+                            if nxt.tipo == TokenType::Virgula { self.advance(); continue; }
+                        }
+                        break;
+                    }
+                    self.fechar(TokenType::FechaChave, "Esperado '}'");
+                    Expr::Dicionario(pares)
+                }
+                _ => { self.erro(&tok, "Expressão esperada"); Expr::Nulo }
             }
         } else {
             Expr::Nulo
@@ -1,62 +1,123 @@
-use crate::interpreter::Interpreter;
+use crate::ast::{Expr, Stmt};
+use crate::depuracao::{print_ast, print_tokens};
+use crate::editor::Auxiliar;
+use crate::interpreter::{ErroExecucao, Interpreter, Value};
+use crate::keywords::palavras_chave;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::tokens::{e_operador, Token, TokenType};
+use rustyline::error::ReadlineError;
+use rustyline::history::DefaultHistory;
+use colored::*;
+use rustyline::Editor;
+use std::collections::{HashMap, HashSet};
+use std::fs;
 use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::Instant;
 
 pub struct Repl {
     interpreter: Interpreter,
     history: Vec<String>,
+    /// Entradas e arquivos carregados executados sem erro, gravados por `:salvar`
+    sessao: Vec<String>,
+    /// Nomes que o código gravado na sessão já declarou no escopo global
+    declarados: HashSet<String>,
+    /// Comentários que precedem as funções declaradas na sessão, por nome
+    documentacao: HashMap<String, String>,
+    comentarios_pendentes: String,
+    /// O código chamou `sair()`
+    encerrar: bool,
+}
+
+impl Default for Repl {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Repl {
     pub fn new() -> Self {
         Self {
-            interpreter: Interpreter::new(),
+            interpreter: novo_interpretador(),
             history: Vec::new(),
+            sessao: Vec::new(),
+            declarados: HashSet::new(),
+            documentacao: HashMap::new(),
+            comentarios_pendentes: String::new(),
+            encerrar: false,
         }
     }
 
     pub fn run(&mut self) {
+        let mut editor: Editor<Auxiliar, DefaultHistory> = match Editor::new() {
+            Ok(editor) => editor,
+            Err(erro) => {
+                eprintln!("Erro ao iniciar o editor de linha: {}", erro);
+                return;
+            }
+        };
+        editor.set_helper(Some(Auxiliar::default()));
+        let arquivo_historico = arquivo_historico();
+        if let Some(caminho) = &arquivo_historico {
+            // Na primeira sessão o arquivo ainda não existe
+            let _ = editor.load_history(caminho);
+        }
+
         self.print_welcome();
 
+        // Linhas de um comando ainda incompleto
+        let mut pendente = String::new();
+
         loop {
-            print!(">>> ");
-            io::stdout().flush().unwrap();
+            if self.encerrar {
+                self.print_goodbye();
+                break;
+            }
+            self.atualizar_nomes(&mut editor);
+            let prompt = if pendente.is_empty() { ">>> " } else { "... " };
 
-            let mut input = String::new();
-            match io::stdin().read_line(&mut input) {
-                Ok(_) => {
+            match editor.readline(prompt) {
+                // Ctrl+C ou Ctrl+D no meio de um comando descarta as linhas pendentes
+                Err(ReadlineError::Interrupted | ReadlineError::Eof) if !pendente.is_empty() => {
+                    pendente.clear();
+                }
+                Err(ReadlineError::Interrupted) => continue,
+                Err(ReadlineError::Eof) => {
+                    self.print_goodbye();
+                    break;
+                }
+                Ok(input) if !pendente.is_empty() => {
+                    let _ = editor.add_history_entry(input.trim());
+                    pendente.push_str(&input);
+                    pendente.push('\n');
+                    if !entrada_incompleta(&pendente) {
+                        let codigo = std::mem::take(&mut pendente);
+                        self.executar_entrada(codigo.trim());
+                    }
+                }
+                Ok(input) => {
                     let input = input.trim();
 
                     if input.is_empty() {
                         continue;
                     }
+                    let _ = editor.add_history_entry(input);
 
-                    if input == "sair" || input == "exit" {
-                        self.print_goodbye();
-                        break;
-                    }
-
-                    if input == "ajuda" || input == "help" || input == "quit" {
-                        self.print_help();
+                    if let Some(comando) = input.strip_prefix(':') {
+                        if !self.comando(comando) {
+                            self.print_goodbye();
+                            break;
+                        }
                         continue;
                     }
 
-                    if input == "limpar" || input == "clear" {
-                        self.clear_screen();
+                    if entrada_incompleta(input) {
+                        pendente = format!("{}\n", input);
                         continue;
                     }
 
-                    if input == "historico" || input == "history" {
-                        self.print_history();
-                        continue;
-                    }
-
-                    if input == "variaveis" || input == "vars" {
-                        self.print_variables();
-                        continue;
-                    }
-
-                    self.history.push(input.to_string());
-                    self.execute(input);
+                    self.executar_entrada(input);
                 }
                 Err(error) => {
                     eprintln!("Erro ao ler entrada: {}", error);
@@ -64,23 +125,209 @@ impl Repl {
                 }
             }
         }
+
+        if let Some(caminho) = &arquivo_historico {
+            if let Err(erro) = editor.save_history(caminho) {
+                eprintln!("Erro ao salvar o histórico em '{}': {}", caminho.display(), erro);
+            }
+        }
+    }
+
+    /// Nomes completados com Tab (palavras-chave, nativas e variáveis da
+    /// sessão) e funções destacadas ao colorir a entrada.
+    fn atualizar_nomes(&self, editor: &mut Editor<Auxiliar, DefaultHistory>) {
+        let Some(auxiliar) = editor.helper_mut() else { return };
+        let variaveis = self.interpreter.globals.borrow().get_all_variables();
+        let mut funcoes: HashSet<String> = crate::builtins::nomes().map(String::from).collect();
+        funcoes.extend(variaveis.iter().filter(|(_, v)| matches!(v, Value::Function(_))).map(|(nome, _)| nome.clone()));
+
+        let mut nomes: Vec<String> = palavras_chave().into_keys().map(String::from).collect();
+        nomes.extend(crate::builtins::nomes().map(String::from));
+        nomes.extend(variaveis.into_iter().map(|(nome, _)| nome));
+        nomes.sort();
+        nomes.dedup();
+        auxiliar.nomes = nomes;
+        auxiliar.funcoes = funcoes;
+    }
+
+    /// Executa `code`. Se o último comando for uma expressão (que não seja
+    /// atribuição), mostra o valor e o guarda em `_`. Devolve se deu certo
+    /// e, nesse caso, se o valor foi mostrado.
+    fn execute(&mut self, code: &str) -> Option<bool> {
+        self.interpreter.fonte = code.to_string();
+        let stmts = match self.interpreter.analisar(code) {
+            Ok(stmts) => stmts,
+            Err(erros) => {
+                for erro in &erros {
+                    self.interpreter.reportar_diagnostico(erro);
+                }
+                return None;
+            }
+        };
+        // Linhas só com comentários ficam guardadas para documentar a próxima função
+        if stmts.is_empty() {
+            self.comentarios_pendentes.push_str(code);
+            self.comentarios_pendentes.push('\n');
+            return Some(false);
+        }
+        let fonte = std::mem::take(&mut self.comentarios_pendentes) + code;
+        self.documentacao.extend(documentacao(&fonte));
+
+        match self.interpreter.executar_com_valor(&stmts) {
+            Ok(valor) if mostrar(&stmts, &valor) => {
+                println!("{}", colorir_valor(&valor));
+                self.interpreter.set_global("_", valor);
+                Some(true)
+            }
+            Ok(_) => Some(false),
+            Err(e) => {
+                self.tratar_erro(&e);
+                None
+            }
+        }
     }
 
-    fn execute(&mut self, code: &str) {
-        let code = if !code.ends_with(';') && !code.starts_with("variavel") && !code.starts_with("funcao") && !code.starts_with("se") && !code.starts_with("enquanto") && !code.starts_with("para") {
-            format!("imprimir({});", code)
+    /// Mostra o erro, a menos que seja o pedido de `sair()`, que encerra o REPL.
+    fn tratar_erro(&mut self, erro: &ErroExecucao) {
+        if erro.codigo_saida().is_some() {
+            self.encerrar = true;
         } else {
-            code.to_string()
+            self.interpreter.reportar_erro(erro);
+        }
+    }
+
+    /// Guarda `codigo` no histórico e o executa; o que deu certo entra na sessão.
+    fn executar_entrada(&mut self, codigo: &str) {
+        self.history.push(codigo.to_string());
+        if let Some(mostrou) = self.execute(codigo) {
+            self.sessao.push(como_script(codigo, mostrou, &mut self.declarados));
+        }
+    }
+
+    /// Executa o arquivo no ambiente da sessão, deixando as suas definições
+    /// disponíveis. Devolve se deu certo.
+    pub fn carregar(&mut self, arquivo: &str) -> bool {
+        let codigo = match fs::read_to_string(arquivo) {
+            Ok(codigo) => codigo,
+            Err(erro) => {
+                eprintln!("Erro ao ler arquivo '{}': {}", arquivo, erro);
+                return false;
+            }
         };
+        // Importações e mensagens de erro se referem ao arquivo carregado
+        let caminho = std::mem::replace(&mut self.interpreter.caminho, fs::canonicalize(arquivo).ok());
+        let ok = self.avaliar(&codigo).is_some();
+        self.interpreter.caminho = caminho;
+        if ok {
+            self.documentacao.extend(documentacao(&codigo));
+            let codigo = como_script(codigo.trim_end(), false, &mut self.declarados);
+            self.sessao.push(format!("// :carregar {}\n{}", arquivo, codigo));
+        }
+        ok
+    }
+
+    fn salvar(&self, arquivo: &str) {
+        let mut conteudo = self.sessao.join("\n");
+        conteudo.push('\n');
+        match fs::write(arquivo, conteudo) {
+            Ok(()) => println!("Sessão salva em '{}' ({} comandos)", arquivo, self.sessao.len()),
+            Err(erro) => eprintln!("Erro ao salvar a sessão em '{}': {}", arquivo, erro),
+        }
+    }
 
-        let tokens = crate::lexer::Lexer::new(&code).tokenize();
-        let mut parser = crate::parser::Parser::new(tokens);
-        let stmts = parser.parse();
+    /// Descarta variáveis, funções e a sessão, mantendo o histórico de comandos.
+    fn reiniciar(&mut self) {
+        self.interpreter = novo_interpretador();
+        self.documentacao.clear();
+        self.comentarios_pendentes.clear();
+        self.sessao.clear();
+        self.declarados.clear();
+        println!("Ambiente reiniciado");
+    }
+
+    /// Executa um comando `:nome argumento`. Devolve falso para encerrar o REPL.
+    fn comando(&mut self, comando: &str) -> bool {
+        let (nome, argumento) = match comando.split_once(char::is_whitespace) {
+            Some((nome, argumento)) => (nome, argumento.trim()),
+            None => (comando, ""),
+        };
+        match (nome, argumento) {
+            ("sair", _) => return false,
+            ("ajuda", "") => self.print_help(),
+            ("ajuda", funcao) => self.print_function_help(funcao),
+            ("limpar", _) => self.clear_screen(),
+            ("historico", _) => self.print_history(),
+            ("variaveis", argumento) => self.print_variables(argumento),
+            ("reiniciar", _) => self.reiniciar(),
+            ("carregar" | "salvar", "") => println!("Uso: :{} <arquivo>", nome),
+            ("carregar", arquivo) => {
+                if self.carregar(arquivo) {
+                    println!("Arquivo '{}' carregado", arquivo);
+                }
+            }
+            ("salvar", arquivo) => self.salvar(arquivo),
+            ("tokens" | "ast" | "tipo" | "tempo", "") => println!("Uso: :{} <código>", nome),
+            ("tokens", codigo) => {
+                let _ = print_tokens(codigo, &mut io::stdout());
+            }
+            ("ast", codigo) => {
+                let _ = print_ast(codigo, &mut io::stdout());
+            }
+            ("tipo", codigo) => {
+                if let Some(valor) = self.avaliar(codigo) {
+                    println!("{}", valor.nome_tipo().cyan());
+                }
+            }
+            ("tempo", codigo) => {
+                let inicio = Instant::now();
+                self.execute(codigo);
+                println!("{}", format!("Tempo: {:.3} ms", inicio.elapsed().as_secs_f64() * 1000.0).dimmed());
+            }
+            _ => println!("Comando desconhecido ':{}'. Digite :ajuda para ver os comandos.", nome),
+        }
+        true
+    }
+
+    /// Executa `codigo` e devolve o valor do último comando, mostrando os erros.
+    fn avaliar(&mut self, codigo: &str) -> Option<Value> {
+        self.interpreter.fonte = codigo.to_string();
+        let stmts = match self.interpreter.analisar(codigo) {
+            Ok(stmts) => stmts,
+            Err(erros) => {
+                for erro in &erros {
+                    self.interpreter.reportar_diagnostico(erro);
+                }
+                return None;
+            }
+        };
+        match self.interpreter.executar_com_valor(&stmts) {
+            Ok(valor) => Some(valor),
+            Err(e) => {
+                self.tratar_erro(&e);
+                None
+            }
+        }
+    }
 
-        for stmt in stmts {
-            if let Err(e) = self.interpreter.execute(&stmt) {
-                eprintln!("Erro: {}", e);
+    fn print_function_help(&self, nome: &str) {
+        if let Some((params, descricao)) = crate::builtins::ajuda(nome) {
+            println!("{}({}) {}", nome.yellow(), params.join(", "), "(nativa)".dimmed());
+            println!("  {}", descricao);
+            return;
+        }
+        match self.interpreter.get_global(nome) {
+            Some(Value::Function(funcao)) => {
+                println!("{}", funcao.assinatura().yellow());
+                match self.documentacao.get(nome) {
+                    Some(doc) => {
+                        for linha in doc.lines() {
+                            println!("  {}", linha);
+                        }
+                    }
+                    None => println!("  {}", "(sem documentação)".dimmed()),
+                }
             }
+            _ => println!("'{}' não é uma função conhecida", nome),
         }
     }
 
@@ -88,8 +335,8 @@ impl Repl {
         println!("\n╔═══════════════════════════════════════════════════╗");
         println!("║            Lucas Language REPL v0.1.0             ║");
         println!("╚═══════════════════════════════════════════════════╝");
-        println!("\nDigite 'ajuda' para ver comandos disponíveis");
-        println!("Digite 'sair' para encerrar\n");
+        println!("\nDigite ':ajuda' para ver comandos disponíveis");
+        println!("Digite ':sair' ou Ctrl+D para encerrar\n");
     }
 
     fn print_goodbye(&self) {
@@ -98,16 +345,31 @@ impl Repl {
 
     fn print_help(&self) {
         println!("\nComandos Disponíveis:");
-        println!("  ajuda, help        - Mostra esta mensagem");
-        println!("  sair, exit, quit   - Sai do REPL");
-        println!("  limpar, clear      - Limpa a tela");
-        println!("  historico, history - Mostra histórico de comandos");
-        println!("  variaveis, vars    - Mostra variáveis definidas");
+        println!("  :ajuda             - Mostra esta mensagem");
+        println!("  :ajuda <função>    - Mostra os parâmetros e a documentação da função");
+        println!("  :sair              - Sai do REPL");
+        println!("  :limpar            - Limpa a tela");
+        println!("  :historico         - Mostra histórico de comandos");
+        println!("  :variaveis [padrão] - Mostra as variáveis cujo nome contém o padrão");
+        println!("  :variaveis --todas - Inclui as funções nativas na lista");
+        println!("  :carregar <arquivo> - Executa um arquivo na sessão atual");
+        println!("  :salvar <arquivo>  - Grava os comandos executados sem erro");
+        println!("  :reiniciar         - Descarta variáveis e funções da sessão");
+        println!("  :tokens <código>   - Mostra os tokens do código");
+        println!("  :ast <código>      - Mostra a árvore sintática do código");
+        println!("  :tipo <expressão>  - Mostra o tipo do valor da expressão");
+        println!("  :tempo <código>    - Executa o código e mostra quanto tempo levou");
+        println!("\nComentários '//' logo acima de uma função servem de documentação para ':ajuda'.");
+        println!("\nComandos incompletos continuam na linha seguinte, após '... '.");
+        println!("Ctrl+C ou Ctrl+D numa linha '... ' descarta o comando pendente.");
+        println!("Setas recuperam comandos anteriores, Ctrl+R busca no histórico e Tab completa nomes.");
         println!("\nExemplos:");
         println!("  >>> 2 + 2");
         println!("  >>> variavel x = 10");
-        println!("  >>> imprimir(x * 2)");
+        println!("  >>> x * 2");
+        println!("  >>> _ + 1              # '_' guarda o último valor mostrado");
         println!("  >>> funcao somar(a, b) {{ retornar a + b; }}");
+        println!("  >>> variavel nome = ler(\"Seu nome: \")");
         println!();
     }
 
@@ -129,19 +391,257 @@ impl Repl {
         println!();
     }
 
-    fn print_variables(&self) {
+    /// Tabela com nome, tipo e valor das variáveis da sessão cujo nome
+    /// contém `padrao`. Com `--todas`, inclui as funções nativas.
+    fn print_variables(&self, argumento: &str) {
+        let (todas, padrao) = match argumento.strip_prefix("--todas") {
+            Some(resto) => (true, resto.trim()),
+            None => (false, argumento),
+        };
+        let mut vars = self.interpreter.globals.borrow().get_all_variables();
+        if todas {
+            let nativas = crate::builtins::nomes().filter(|nome| vars.iter().all(|(n, _)| n != nome)).collect::<Vec<_>>();
+            vars.extend(nativas.into_iter().filter_map(|nome| Some((nome.to_string(), self.interpreter.get_global(nome)?))));
+            vars.sort_by(|a, b| a.0.cmp(&b.0));
+        }
+        vars.retain(|(nome, _)| nome.contains(padrao));
+
         println!("\nVariáveis Globais:");
+        if vars.is_empty() {
+            println!("  (nenhuma variável definida)\n");
+            return;
+        }
+        let largura_nome = vars.iter().map(|(nome, _)| nome.chars().count()).max().unwrap_or(0).max(4);
+        let largura_tipo = vars.iter().map(|(_, v)| v.nome_tipo().chars().count()).max().unwrap_or(0).max(4);
+        println!("  {}", format!("{:<largura_nome$}  {:<largura_tipo$}  Valor", "Nome", "Tipo").bold());
+        for (nome, valor) in &vars {
+            let descricao = match valor {
+                Value::Function(funcao) => funcao.assinatura(),
+                Value::Text(s) => format!("{:?}", s),
+                _ => valor.to_string_repr(),
+            };
+            println!("  {:<largura_nome$}  {:<largura_tipo$}  {}", nome, valor.nome_tipo(), truncar(&descricao, 50));
+        }
+        println!();
+    }
+}
 
-        let globals = self.interpreter.globals.borrow();
-        let vars = globals.get_all_variables();
+fn novo_interpretador() -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    // Distingue a espera de `ler()` do prompt de comandos
+    interpreter.prompt_leitura = Some("<<< ".into());
+    interpreter.permitir_redeclaracao = true;
+    interpreter
+}
 
-        if vars.is_empty() {
-            println!("  (nenhuma variável definida)");
-        } else {
-            for (name, value) in vars {
-                println!("  {} = {}", name, value.to_string_repr());
+/// Documentação das funções declaradas em `codigo`: os comentários `//` nas
+/// linhas logo acima de `funcao nome`.
+fn documentacao(codigo: &str) -> Vec<(String, String)> {
+    let mut lexer = Lexer::new(codigo);
+    let tokens = lexer.tokenize();
+    let mut docs = Vec::new();
+    for par in tokens.windows(2) {
+        let (TokenType::Funcao, TokenType::Ident(nome)) = (&par[0].tipo, &par[1].tipo) else {
+            continue;
+        };
+        let mut linhas = Vec::new();
+        let mut linha = par[0].linha;
+        while let Some(comentario) = lexer.comentarios.iter().find(|c| c.linha + 1 == linha) {
+            linhas.push(comentario.texto.trim());
+            linha = comentario.linha;
+        }
+        if !linhas.is_empty() {
+            linhas.reverse();
+            docs.push((nome.clone(), linhas.join("\n")));
+        }
+    }
+    docs
+}
+
+/// Valor mostrado pelo REPL, colorido pelo tipo. Textos aparecem entre aspas.
+fn colorir_valor(valor: &Value) -> String {
+    match valor {
+        Value::Text(s) => format!("{:?}", s).green().to_string(),
+        Value::Number(_) => valor.to_string_repr().cyan().to_string(),
+        Value::Bool(_) => valor.to_string_repr().yellow().to_string(),
+        Value::Nil => "nulo".dimmed().to_string(),
+        Value::Array(arr) => {
+            let itens: Vec<String> = arr.borrow().iter().map(colorir_valor).collect();
+            format!("[{}]", itens.join(", "))
+        }
+        Value::Dicionario(dic) => {
+            let itens: Vec<String> = dic.borrow().iter().map(|(k, v)| format!("{}: {}", k, colorir_valor(v))).collect();
+            format!("{{{}}}", itens.join(", "))
+        }
+        Value::Function(_) => valor.to_string_repr().magenta().to_string(),
+        Value::Arquivo(_) | Value::Intervalo(_) => valor.to_string_repr(),
+    }
+}
+
+/// Se o valor de uma entrada deve ser mostrado: só quando ela termina numa
+/// expressão que não é atribuição. `nulo` é omitido quando vem de uma chamada,
+/// como `imprimir(x)`, mas aparece para `x` ou `d["chave"]`.
+fn mostrar(stmts: &[Stmt], valor: &Value) -> bool {
+    match stmts.last() {
+        Some(Stmt::ExprStmt(Expr::Atribuir(..) | Expr::AtribuirIndex(..), _)) => false,
+        Some(Stmt::ExprStmt(Expr::Chamada(..), _)) => !matches!(valor, Value::Nil),
+        Some(Stmt::ExprStmt(..)) => true,
+        _ => false,
+    }
+}
+
+/// Reescreve uma entrada da sessão para que rode num script: o REPL aceita
+/// redeclarar variáveis e funções e guarda em `_` o valor mostrado, e um
+/// script não. `declarados` acumula os nomes globais que a sessão já criou.
+fn como_script(codigo: &str, mostrou: bool, declarados: &mut HashSet<String>) -> String {
+    let tokens = Lexer::new(codigo).tokenize();
+    let mut parser = Parser::new(tokens.clone());
+    let stmts = parser.parse();
+    let posicao = |i: usize| deslocamento(codigo, &tokens[i]);
+    // (início, fim, texto novo), sem sobreposição e em ordem
+    let mut trocas: Vec<(usize, usize, String)> = Vec::new();
+    for (i, (stmt, &inicio)) in stmts.iter().zip(&parser.inicios).enumerate() {
+        match stmt {
+            // `variavel x = 2;` de novo vira `x = 2;`
+            Stmt::VarDecl(nome, _, _) if declarados.contains(nome) => {
+                trocas.push((posicao(inicio), posicao(inicio + 1), String::new()));
+            }
+            // Sem funções anônimas, a nova versão ganha outro nome e é atribuída
+            Stmt::FuncDecl(nome, _, _) if declarados.contains(nome) => {
+                let novo = (2..).map(|n| format!("{}_{}", nome, n)).find(|n| !declarados.contains(n)).unwrap();
+                trocas.push((posicao(inicio + 1), posicao(inicio + 2), novo.clone()));
+                let fim = parser.inicios.get(i + 1).map_or(codigo.len(), |&proximo| posicao(proximo));
+                let atribuicao = if fim == codigo.len() {
+                    format!("\n{} = {};", nome, novo)
+                } else {
+                    format!("{} = {};\n", nome, novo)
+                };
+                trocas.push((fim, fim, atribuicao));
+                declarados.insert(novo);
+            }
+            Stmt::ExprStmt(..) if mostrou && i + 1 == stmts.len() => {
+                let declaracao = if declarados.insert("_".to_string()) { "variavel _ = " } else { "_ = " };
+                trocas.push((posicao(inicio), posicao(inicio), declaracao.to_string()));
             }
+            _ => {}
         }
-        println!();
+        let mut nomes = Vec::new();
+        stmt.declaracoes(&mut nomes);
+        declarados.extend(nomes.into_iter().map(|(nome, _)| nome.to_string()));
+    }
+    let mut script = codigo.to_string();
+    for (inicio, fim, texto) in trocas.into_iter().rev() {
+        script.replace_range(inicio..fim, &texto);
     }
-}
\ No newline at end of file
+    script
+}
+
+/// Posição em bytes de `token` dentro de `codigo`.
+fn deslocamento(codigo: &str, token: &Token) -> usize {
+    if token.tipo == TokenType::Fim {
+        return codigo.len();
+    }
+    let linha = codigo.split_inclusive('\n').take(token.linha - 1).map(str::len).sum::<usize>();
+    linha + codigo[linha..].char_indices().nth(token.coluna - 1).map_or(0, |(i, _)| i)
+}
+
+/// `texto` com no máximo `limite` caracteres, terminando em `…` se cortado.
+fn truncar(texto: &str, limite: usize) -> String {
+    if texto.chars().count() <= limite {
+        return texto.to_string();
+    }
+    let mut cortado: String = texto.chars().take(limite - 1).collect();
+    cortado.push('…');
+    cortado
+}
+
+/// Histórico guardado entre sessões, no diretório do usuário.
+fn arquivo_historico() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"))?;
+    Some(PathBuf::from(home).join(".lucas_historico"))
+}
+
+/// Se `codigo` ainda não terminou: há parênteses, chaves ou colchetes abertos,
+/// um texto sem as aspas de fechamento ou um operador no final.
+fn entrada_incompleta(codigo: &str) -> bool {
+    use TokenType::*;
+    let mut lexer = Lexer::new(codigo);
+    let tokens = lexer.tokenize();
+    if lexer.texto_aberto {
+        return true;
+    }
+
+    let mut abertos = 0i32;
+    for token in &tokens {
+        match token.tipo {
+            AbrePar | AbreChave | AbreColchete => abertos += 1,
+            FechaPar | FechaChave | FechaColchete => abertos -= 1,
+            _ => {}
+        }
+    }
+    // Fechamentos a mais são erro de sintaxe, não entrada incompleta
+    if abertos > 0 {
+        return true;
+    }
+
+    let Some(ultimo) = tokens.iter().rposition(|t| t.tipo != Fim) else {
+        return false;
+    };
+    match tokens[ultimo].tipo {
+        // `x e` espera o outro operando; `imprimir e` já está completo
+        E => e_operador(&tokens, ultimo),
+        Mais | Menos | Multiplica | Divide | Igual | IgualIgual | Diferente | Menor | MenorIgual | Maior
+        | MaiorIgual | Ou | Nao | Virgula | DoisPontos | Ponto | PontoPonto | PontoPontoIgual => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{colorir_valor, entrada_incompleta, mostrar};
+    use crate::interpreter::Value;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn mostra(codigo: &str, valor: Value) -> bool {
+        mostrar(&Parser::new(Lexer::new(codigo).tokenize()).parse(), &valor)
+    }
+
+    #[test]
+    fn entrada_incompleta_por_delimitador_ou_operador() {
+        assert!(entrada_incompleta("funcao f() {"));
+        assert!(entrada_incompleta("imprimir(1,"));
+        assert!(entrada_incompleta("variavel x = 1 +"));
+        assert!(entrada_incompleta("imprimir \"sem fim"));
+        assert!(entrada_incompleta("variavel d = {\"x\": [1,"));
+        assert!(entrada_incompleta("se (a <"));
+        assert!(entrada_incompleta("variavel t = \"a\" +\n"));
+        assert!(!entrada_incompleta("imprimir 1;"));
+        assert!(!entrada_incompleta("}"));
+        assert!(!entrada_incompleta("variavel d = {\"x\": [1, 2]};"));
+        assert!(!entrada_incompleta("imprimir \"(\";"));
+    }
+
+    #[test]
+    fn e_no_fim_da_linha_so_continua_como_operador() {
+        assert!(entrada_incompleta("x e"));
+        assert!(entrada_incompleta("f(1) e"));
+        assert!(entrada_incompleta("e e"));
+        assert!(!entrada_incompleta("e"));
+        assert!(!entrada_incompleta("imprimir e"));
+        assert!(!entrada_incompleta("variavel y = e"));
+        assert!(!entrada_incompleta("x e e"));
+    }
+
+    #[test]
+    fn eco_de_nulo_so_fora_de_chamadas() {
+        assert!(mostra("x", Value::Nil));
+        assert!(mostra("d[\"chave\"]", Value::Nil));
+        assert!(mostra("nulo", Value::Nil));
+        assert!(!mostra("imprimir(1)", Value::Nil));
+        assert!(mostra("f()", Value::from(1.0)));
+        assert!(!mostra("x = 1", Value::from(1.0)));
+        assert!(!mostra("variavel x = 1;", Value::Nil));
+        assert!(colorir_valor(&Value::Nil).contains("nulo"));
+    }
+}
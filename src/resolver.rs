@@ -0,0 +1,483 @@
+use crate::ast::{Expr, Resolucao, Stmt};
+use crate::errors::{sugerir_similar, ErrorType, LucasError, SourceLocation};
+use crate::interpreter::ErroExecucao;
+use std::collections::HashSet;
+
+/// Erro ou aviso encontrado antes da execução.
+#[derive(Debug, Clone)]
+pub struct Diagnostico {
+    pub tipo: &'static str,
+    pub mensagem: String,
+    pub linha: usize,
+    /// Coluna do token com problema, quando conhecida
+    pub coluna: Option<usize>,
+    pub sugestao: Option<String>,
+}
+
+/// Tipo dos diagnósticos do lexer.
+pub const ERRO_LEXICO: &str = "ErroLexico";
+/// Tipo dos diagnósticos do parser.
+pub const ERRO_SINTATICO: &str = "ErroSintatico";
+
+impl Diagnostico {
+    pub fn novo(tipo: &'static str, mensagem: String, linha: usize) -> Self {
+        Self { tipo, mensagem, linha, coluna: None, sugestao: None }
+    }
+
+    pub fn na_coluna(mut self, coluna: usize) -> Self {
+        self.coluna = Some(coluna);
+        self
+    }
+
+    pub fn to_erro(&self) -> ErroExecucao {
+        let mensagem = match &self.sugestao {
+            Some(sugestao) => format!("{}. {}", self.mensagem, sugestao),
+            None => self.mensagem.clone(),
+        };
+        let mut erro = ErroExecucao::novo(self.tipo, mensagem);
+        erro.linha = Some(self.linha);
+        erro
+    }
+
+    /// Erro para exibir, com o trecho de `fonte` onde o problema está.
+    pub fn to_lucas(&self, fonte: &str) -> LucasError {
+        let tipo = match self.tipo {
+            ERRO_LEXICO => ErrorType::LexicoError,
+            ERRO_SINTATICO => ErrorType::SintaticoError,
+            _ => ErrorType::SemanticoError,
+        };
+        let mut lucas_erro = LucasError::novo(tipo, self.mensagem.clone());
+        if let Some(mut loc) = SourceLocation::na_linha(fonte, self.linha) {
+            if let Some(coluna) = self.coluna {
+                loc.coluna = coluna;
+            }
+            lucas_erro = lucas_erro.com_localizacao(loc);
+        }
+        if let Some(sugestao) = &self.sugestao {
+            lucas_erro = lucas_erro.com_sugestao(sugestao.clone());
+        }
+        lucas_erro
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TipoLocal {
+    Variavel,
+    Constante,
+    Funcao,
+    /// Parâmetros, variáveis de laço e de `capturar`: não geram aviso de uso
+    Parametro,
+}
+
+struct Local {
+    nome: String,
+    tipo: TipoLocal,
+    linha: usize,
+    usado: bool,
+}
+
+#[derive(Default)]
+struct Escopo {
+    locais: Vec<Local>,
+    /// Nomes buscados pelo nome enquanto este escopo estava aberto; podem se
+    /// referir a um local declarado depois (uma função que usa `y` antes de `variavel y`)
+    dinamicos: HashSet<String>,
+    /// Nomes sem declaração visível buscados neste escopo. Ao fechar o escopo,
+    /// os que foram declarados nele depois somem; os outros sobem para o escopo externo
+    pendentes: Vec<(String, usize)>,
+    /// Tudo o que o escopo declara, inclusive mais adiante
+    declara: HashSet<String>,
+    /// Funções abertas quando o escopo começou
+    funcoes: usize,
+}
+
+/// Análise estática feita entre o parser e a execução. Resolve cada variável
+/// local para `(profundidade, posição)`, aponta nomes indefinidos e `retornar`
+/// fora de funções, e avisa sobre locais não usados e nomes escondidos.
+///
+/// Os escopos espelham os criados pelo interpretador: um por bloco com
+/// declarações, por chamada de função (parâmetros e corpo juntos), por
+/// `para`, por volta de `para cada` e por bloco de
+/// `tentar`/`capturar`/`finalmente`. O nível principal não tem escopo local:
+/// globais são buscadas pelo nome, mas uma global declarada duas vezes no
+/// mesmo programa já é apontada aqui.
+///
+/// Dentro de uma função, um nome declarado mais adiante num escopo que contém
+/// a função também é buscado pelo nome: em
+/// `{ variavel x = 1; { funcao f() { retornar x; } variavel x = 2; f(); } }`
+/// a função pode ser chamada antes ou depois da segunda declaração, e vê o `x`
+/// que existir naquele momento (aqui, 2).
+pub struct Resolver {
+    escopos: Vec<Escopo>,
+    /// Nomes que já existem no ambiente global (nativas, entradas anteriores do REPL)
+    globais: HashSet<String>,
+    /// Declarações do nível principal deste programa
+    globais_programa: HashSet<String>,
+    constantes_globais: HashSet<String>,
+    /// Declarações do nível principal já analisadas, para apontar as repetidas
+    declarados_principal: HashSet<String>,
+    /// O REPL aceita redeclarar nomes do nível principal
+    pub redeclarar_globais: bool,
+    /// Nomes declarados em qualquer escopo do programa, usados nas sugestões
+    declarados: HashSet<String>,
+    /// Nomes que chegaram ao nível principal sem declaração, verificados ao final da análise
+    pendentes: Vec<(String, usize)>,
+    funcoes: usize,
+    linha: usize,
+    pub erros: Vec<Diagnostico>,
+    pub avisos: Vec<Diagnostico>,
+}
+
+impl Resolver {
+    pub fn novo(globais: Vec<String>) -> Self {
+        Self {
+            escopos: Vec::new(),
+            globais: globais.into_iter().collect(),
+            globais_programa: HashSet::new(),
+            constantes_globais: HashSet::new(),
+            declarados_principal: HashSet::new(),
+            redeclarar_globais: false,
+            declarados: HashSet::new(),
+            pendentes: Vec::new(),
+            funcoes: 0,
+            linha: 0,
+            erros: Vec::new(),
+            avisos: Vec::new(),
+        }
+    }
+
+    pub fn resolver(&mut self, stmts: &mut [Stmt]) {
+        for stmt in stmts.iter() {
+            self.registrar_global(stmt);
+        }
+        for stmt in stmts.iter_mut() {
+            self.stmt(stmt);
+        }
+
+        let mut conhecidos: Vec<String> = self.globais.iter().chain(&self.declarados).cloned().collect();
+        conhecidos.sort();
+        let mut vistos = HashSet::new();
+        for (nome, linha) in std::mem::take(&mut self.pendentes) {
+            if !vistos.insert((nome.clone(), linha)) {
+                continue;
+            }
+            let mut erro = Diagnostico::novo("VariavelIndefinida", format!("Variável '{}' não definida", nome), linha);
+            erro.sugestao = if self.declarados.contains(&nome) {
+                Some(format!("'{}' foi declarada num escopo que não é visível aqui", nome))
+            } else {
+                sugerir_similar(&nome, &conhecidos)
+            };
+            self.erros.push(erro);
+        }
+        self.erros.sort_by_key(|d| d.linha);
+        self.avisos.sort_by_key(|d| d.linha);
+    }
+
+    fn registrar_global(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::VarDecl(nome, _, _) | Stmt::FuncDecl(nome, _, _) | Stmt::Importar(_, nome, _) => {
+                self.globais_programa.insert(nome.clone());
+            }
+            Stmt::Constante(nome, _, _) => {
+                self.globais_programa.insert(nome.clone());
+                self.constantes_globais.insert(nome.clone());
+            }
+            Stmt::DeImportar(_, nomes, _) => self.globais_programa.extend(nomes.iter().cloned()),
+            Stmt::Exportar(_, Some(decl)) => self.registrar_global(decl),
+            _ => {}
+        }
+    }
+
+    /// Abre um escopo que vai receber as declarações de `stmts`.
+    fn abrir_escopo(&mut self, stmts: &[Stmt]) {
+        let mut decls = Vec::new();
+        for stmt in stmts {
+            stmt.declaracoes(&mut decls);
+        }
+        self.escopos.push(Escopo {
+            declara: decls.into_iter().map(|(nome, _)| nome.to_string()).collect(),
+            funcoes: self.funcoes,
+            ..Escopo::default()
+        });
+    }
+
+    fn fechar_escopo(&mut self) {
+        let Some(escopo) = self.escopos.pop() else { return };
+        let restantes = escopo.pendentes.into_iter().filter(|(nome, _)| !escopo.locais.iter().any(|l| &l.nome == nome));
+        match self.escopos.last_mut() {
+            Some(externo) => externo.pendentes.extend(restantes),
+            None => self.pendentes.extend(restantes),
+        }
+        for local in escopo.locais {
+            if local.usado || local.tipo == TipoLocal::Parametro || local.nome.starts_with('_') {
+                continue;
+            }
+            if escopo.dinamicos.contains(&local.nome) {
+                continue;
+            }
+            let oque = if local.tipo == TipoLocal::Funcao { "Função" } else { "Variável" };
+            self.avisos.push(Diagnostico::novo(
+                "NaoUsada",
+                format!("{} '{}' declarada mas nunca usada", oque, local.nome),
+                local.linha,
+            ));
+        }
+    }
+
+    fn declarar(&mut self, nome: &str, tipo: TipoLocal) {
+        self.declarados.insert(nome.to_string());
+        let linha = self.linha;
+        let Some(atual) = self.escopos.last() else {
+            if !self.declarados_principal.insert(nome.to_string()) && !self.redeclarar_globais {
+                self.erros.push(Diagnostico::novo(
+                    "Redeclaracao",
+                    format!("Variável '{}' já foi declarada neste escopo", nome),
+                    linha,
+                ));
+            }
+            return;
+        };
+
+        if atual.locais.iter().any(|l| l.nome == nome) {
+            self.erros.push(Diagnostico::novo(
+                "Redeclaracao",
+                format!("Variável '{}' já foi declarada neste escopo", nome),
+                linha,
+            ));
+            return;
+        }
+
+        if tipo != TipoLocal::Parametro && !nome.starts_with('_') {
+            let externo = self.escopos[..self.escopos.len() - 1]
+                .iter()
+                .any(|e| e.locais.iter().any(|l| l.nome == nome))
+                || self.globais_programa.contains(nome);
+            if externo {
+                self.avisos.push(Diagnostico::novo(
+                    "Sombreamento",
+                    format!("A declaração de '{}' esconde uma variável de mesmo nome de um escopo externo", nome),
+                    linha,
+                ));
+            }
+        }
+
+        if let Some(atual) = self.escopos.last_mut() {
+            atual.locais.push(Local { nome: nome.to_string(), tipo, linha, usado: false });
+        }
+    }
+
+    /// Procura `nome` nos escopos locais, do mais interno para o mais externo.
+    fn buscar(&mut self, nome: &str) -> Option<(usize, usize, TipoLocal)> {
+        for (profundidade, escopo) in self.escopos.iter().rev().enumerate() {
+            if let Some(posicao) = escopo.locais.iter().position(|l| l.nome == nome) {
+                return Some((profundidade, posicao, escopo.locais[posicao].tipo));
+            }
+            // A função atual pode rodar depois dessa declaração: fica para a execução
+            if escopo.funcoes < self.funcoes && escopo.declara.contains(nome) {
+                break;
+            }
+        }
+        for escopo in &mut self.escopos {
+            escopo.dinamicos.insert(nome.to_string());
+        }
+        if !self.globais.contains(nome) && !self.globais_programa.contains(nome) {
+            let pendente = (nome.to_string(), self.linha);
+            match self.escopos.last_mut() {
+                Some(atual) => atual.pendentes.push(pendente),
+                None => self.pendentes.push(pendente),
+            }
+        }
+        None
+    }
+
+    fn usar(&mut self, nome: &str) -> Resolucao {
+        let (profundidade, posicao, _) = self.buscar(nome)?;
+        let indice = self.escopos.len() - 1 - profundidade;
+        self.escopos[indice].locais[posicao].usado = true;
+        Some((profundidade, posicao))
+    }
+
+    fn bloco(&mut self, stmts: &mut [Stmt]) {
+        for stmt in stmts {
+            self.stmt(stmt);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &mut Stmt) {
+        if let Some(linha) = stmt.linha() {
+            self.linha = linha;
+        }
+        match stmt {
+            Stmt::ExprStmt(e, _) | Stmt::Imprimir(e, _) | Stmt::Lancar(e, _) => self.expr(e),
+            Stmt::VarDecl(nome, init, _) => {
+                self.expr(init);
+                self.declarar(nome, TipoLocal::Variavel);
+            }
+            Stmt::Constante(nome, init, _) => {
+                self.expr(init);
+                self.declarar(nome, TipoLocal::Constante);
+            }
+            // Como no interpretador, um bloco sem declarações não tem escopo próprio
+            Stmt::Bloco(stmts) if stmts.iter().any(Stmt::declara) => {
+                self.abrir_escopo(stmts);
+                self.bloco(stmts);
+                self.fechar_escopo();
+            }
+            Stmt::Bloco(stmts) => self.bloco(stmts),
+            Stmt::If(cond, entao, senao, _) => {
+                self.expr(cond);
+                self.stmt(entao);
+                if let Some(senao) = senao {
+                    self.stmt(senao);
+                }
+            }
+            Stmt::While(cond, corpo, _) => {
+                self.expr(cond);
+                self.stmt(corpo);
+            }
+            Stmt::For(init, cond, incr, corpo) => {
+                self.abrir_escopo(init.as_deref().map(std::slice::from_ref).unwrap_or_default());
+                if let Some(init) = init {
+                    self.stmt(init);
+                }
+                if let Some(cond) = cond {
+                    self.expr(cond);
+                }
+                self.stmt(corpo);
+                if let Some(incr) = incr {
+                    self.expr(incr);
+                }
+                self.fechar_escopo();
+            }
+            Stmt::ParaCada(indice, item, iteravel, corpo, _) => {
+                self.expr(iteravel);
+                self.abrir_escopo(&[]);
+                if let Some(indice) = indice {
+                    self.declarar(indice, TipoLocal::Parametro);
+                }
+                self.declarar(item, TipoLocal::Parametro);
+                self.stmt(corpo);
+                self.fechar_escopo();
+            }
+            Stmt::FuncDecl(nome, params, corpo) => {
+                // Declarada antes do corpo para permitir recursão
+                self.declarar(nome, TipoLocal::Funcao);
+                self.funcoes += 1;
+                self.abrir_escopo(corpo);
+                for param in params.iter() {
+                    self.declarar(param, TipoLocal::Parametro);
+                }
+                self.bloco(corpo);
+                self.fechar_escopo();
+                self.funcoes -= 1;
+            }
+            Stmt::Return(valor, linha) => {
+                if self.funcoes == 0 {
+                    self.erros.push(Diagnostico::novo(
+                        "RetornoForaDeFuncao",
+                        "'retornar' usado fora de uma função".into(),
+                        *linha,
+                    ));
+                }
+                if let Some(valor) = valor {
+                    self.expr(valor);
+                }
+            }
+            Stmt::Tentar(corpo, captura, finalmente) => {
+                self.abrir_escopo(corpo);
+                self.bloco(corpo);
+                self.fechar_escopo();
+                if let Some(captura) = captura {
+                    self.abrir_escopo(&captura.corpo);
+                    if let Some(nome) = &captura.nome {
+                        self.declarar(nome, TipoLocal::Parametro);
+                    }
+                    self.bloco(&mut captura.corpo);
+                    self.fechar_escopo();
+                }
+                if let Some(finalmente) = finalmente {
+                    self.abrir_escopo(finalmente);
+                    self.bloco(finalmente);
+                    self.fechar_escopo();
+                }
+            }
+            Stmt::Importar(_, apelido, _) => self.declarar(apelido, TipoLocal::Variavel),
+            Stmt::DeImportar(_, nomes, _) => {
+                for nome in nomes.iter() {
+                    self.declarar(nome, TipoLocal::Variavel);
+                }
+            }
+            Stmt::Exportar(nomes, decl) => match decl {
+                Some(decl) => self.stmt(decl),
+                None => {
+                    for nome in nomes.iter() {
+                        self.usar(nome);
+                    }
+                }
+            },
+            Stmt::Break | Stmt::Continue => {}
+        }
+    }
+
+    fn expr(&mut self, expr: &mut Expr) {
+        match expr {
+            Expr::Numero(_) | Expr::Texto(_) | Expr::Bool(_) | Expr::Nulo => {}
+            Expr::Var(nome, resolucao) => *resolucao = self.usar(nome),
+            Expr::Atribuir(nome, valor, resolucao) => {
+                self.expr(valor);
+                let local = self.buscar(nome);
+                let constante = match local {
+                    Some((_, _, tipo)) => tipo == TipoLocal::Constante,
+                    None => self.constantes_globais.contains(nome.as_str()),
+                };
+                if constante {
+                    self.erros.push(Diagnostico::novo(
+                        "AtribuicaoConstante",
+                        format!("Não é possível alterar a constante '{}'", nome),
+                        self.linha,
+                    ));
+                }
+                *resolucao = local.map(|(profundidade, posicao, _)| (profundidade, posicao));
+            }
+            Expr::Array(itens) => {
+                for item in itens {
+                    self.expr(item);
+                }
+            }
+            Expr::Dicionario(pares) => {
+                for (chave, valor) in pares {
+                    self.expr(chave);
+                    self.expr(valor);
+                }
+            }
+            Expr::Index(alvo, indice) => {
+                self.expr(alvo);
+                self.expr(indice);
+            }
+            Expr::AtribuirIndex(alvo, indice, valor) => {
+                self.expr(alvo);
+                self.expr(indice);
+                self.expr(valor);
+            }
+            Expr::Unario(_, e) => self.expr(e),
+            Expr::Binario(esq, _, dir) => {
+                self.expr(esq);
+                self.expr(dir);
+            }
+            Expr::Chamada(funcao, args) => {
+                self.expr(funcao);
+                for arg in args {
+                    self.expr(arg);
+                }
+            }
+            Expr::Intervalo(inicio, fim, _) => {
+                if let Some(inicio) = inicio {
+                    self.expr(inicio);
+                }
+                if let Some(fim) = fim {
+                    self.expr(fim);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use std::io::{self, Write};
+
+/// Destino do texto escrito pelo interpretador: a saída de `imprimir` e
+/// `ler()` em `Interpreter::saida`, e as mensagens de erro em
+/// `Interpreter::saida_erros`.
+#[derive(Default)]
+pub enum Saida {
+    /// Saída padrão do processo
+    #[default]
+    Padrao,
+    /// Saída de erros do processo
+    ErroPadrao,
+    /// Acumula o texto em memória; veja `Saida::capturado`
+    Buffer(Vec<u8>),
+    /// Entrega cada trecho escrito a uma closure do programa que embute o interpretador
+    Funcao(Box<dyn FnMut(&str)>),
+}
+
+impl Saida {
+    /// Retira o texto acumulado num `Saida::Buffer` (vazio nos outros destinos).
+    pub fn capturado(&mut self) -> String {
+        match self {
+            Saida::Buffer(bytes) => String::from_utf8_lossy(&std::mem::take(bytes)).into_owned(),
+            _ => String::new(),
+        }
+    }
+}
+
+impl Write for Saida {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Saida::Padrao => io::stdout().lock().write(buf),
+            Saida::ErroPadrao => io::stderr().lock().write(buf),
+            Saida::Buffer(bytes) => bytes.write(buf),
+            Saida::Funcao(funcao) => {
+                funcao(&String::from_utf8_lossy(buf));
+                Ok(buf.len())
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Saida::Padrao => io::stdout().flush(),
+            Saida::ErroPadrao => io::stderr().flush(),
+            Saida::Buffer(_) | Saida::Funcao(_) => Ok(()),
+        }
+    }
+}
@@ -4,6 +4,7 @@ pub enum TokenType {
     Texto(String),
     Ident(String),
     Variavel,
+    Constante,
     Funcao,
     Se,
     Senao,
@@ -19,6 +20,12 @@ pub enum TokenType {
     Nao,
     Break,
     Continue,
+    Tentar,
+    Capturar,
+    Finalmente,
+    Lancar,
+    Importar,
+    Exportar,
     Mais,
     Menos,
     Multiplica,
@@ -37,6 +44,10 @@ pub enum TokenType {
     AbreColchete,
     FechaColchete,
     Virgula,
+    DoisPontos,
+    Ponto,
+    PontoPonto,
+    PontoPontoIgual,
     PontoVirgula,
     Fim,
 }
@@ -53,8 +64,23 @@ impl Token {
     pub fn new(tipo: TokenType, lexema: String, linha: usize) -> Self {
         Self { tipo, lexema, linha, coluna: 0 }
     }
-    
-    pub fn with_column(tipo: TokenType, lexema: String, linha: usize, coluna: usize) -> Self {
-        Self { tipo, lexema, linha, coluna }
+}
+
+/// `e` só é o operador lógico logo depois de um operando completo, como em
+/// `a e b`. Onde começa um operando ou se espera um nome (`capturar (e)`,
+/// `imprimir e.mensagem`, `funcao f(e)`) ele é uma variável comum. Diz se o
+/// `e` em `tokens[i]` é o operador.
+pub fn e_operador(tokens: &[Token], i: usize) -> bool {
+    i > 0 && termina_operando(tokens, i - 1)
+}
+
+/// Se `tokens[i]` pode encerrar um operando. Um `}` conta como fim de bloco,
+/// não de dicionário.
+fn termina_operando(tokens: &[Token], i: usize) -> bool {
+    use TokenType::*;
+    match tokens[i].tipo {
+        Ident(_) | Numero(_) | Texto(_) | Verdadeiro | Falso | Nulo | FechaPar | FechaColchete => true,
+        E => !e_operador(tokens, i),
+        _ => false,
     }
-}
\ No newline at end of file
+}
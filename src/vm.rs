@@ -0,0 +1,818 @@
+use crate::ast::BinOp;
+use crate::bytecode::{InfoTentativa, Op, Prototipo, SEM_POSICAO};
+use crate::environment::EnvRef;
+use crate::interpreter::{
+    atribuir_indice, criar_intervalo, indexar, operar_binario, operar_unario, Controle, Corpo,
+    ErroExecucao, Function, Interpreter, Value,
+};
+use crate::iterador::Iterador;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+/// Variável local capturada por uma função; `None` enquanto a declaração
+/// ainda não foi executada.
+pub type Celula = Rc<RefCell<Option<Value>>>;
+
+#[derive(Clone)]
+enum Slot {
+    Vazio,
+    Valor(Value),
+    /// Variável capturada: passa a viver numa célula compartilhada
+    Celula(Celula),
+}
+
+struct Quadro {
+    /// Função em execução; `None` no programa principal
+    funcao: Option<Rc<Function>>,
+    /// Código e posição do quadro enquanto ele espera uma chamada terminar
+    proto: Option<Rc<Prototipo>>,
+    ip: usize,
+    base_pilha: usize,
+    base_locais: usize,
+    base_iteradores: usize,
+    base_tentativas: usize,
+}
+
+#[derive(PartialEq)]
+enum Fase {
+    Corpo,
+    Captura,
+    Finalmente,
+}
+
+/// O que fazer quando o `finalmente` em execução terminar.
+enum Pendente {
+    Nenhum,
+    Retorno(Value),
+    Erro(ErroExecucao),
+    /// `parar`/`continuar`: blocos `tentar` que ainda faltam sair e o destino
+    Saltar(u32, u32),
+    ForaDeLaco(bool),
+}
+
+struct Tentativa {
+    info: InfoTentativa,
+    fase: Fase,
+    pilha: usize,
+    iteradores: usize,
+    pendente: Pendente,
+}
+
+/// Máquina de pilha que executa o bytecode gerado pelo `Compilador`.
+///
+/// Chamadas entre funções compiladas não usam a pilha do Rust: cada uma
+/// empilha um `Quadro`. Funções nativas e da árvore são chamadas normalmente.
+pub struct Vm<'a> {
+    interp: &'a mut Interpreter,
+    /// Globais do programa principal; funções usam as do módulo onde foram criadas
+    globais: EnvRef,
+    pilha: Vec<Value>,
+    locais: Vec<Slot>,
+    quadros: Vec<Quadro>,
+    iteradores: Vec<Iterador>,
+    tentativas: Vec<Tentativa>,
+    /// Estado do quadro no topo, fora do `Quadro` para o laço principal não
+    /// precisar buscá-lo a cada instrução
+    proto: Rc<Prototipo>,
+    ip: usize,
+    base_locais: usize,
+}
+
+impl<'a> Vm<'a> {
+    pub fn novo(interp: &'a mut Interpreter) -> Self {
+        Self {
+            globais: interp.env.clone(),
+            interp,
+            pilha: Vec::new(),
+            locais: Vec::new(),
+            quadros: Vec::new(),
+            iteradores: Vec::new(),
+            tentativas: Vec::new(),
+            proto: Rc::default(),
+            ip: 0,
+            base_locais: 0,
+        }
+    }
+
+    /// Executa o programa principal nas globais do interpretador.
+    pub fn executar_programa(&mut self, proto: Rc<Prototipo>) -> Result<(), ErroExecucao> {
+        self.empilhar_quadro(None, proto, 0);
+        self.executar(0).map(|_| ())
+    }
+
+    /// Chama uma função compilada a partir de código de fora da `Vm`.
+    pub fn chamar(&mut self, funcao: &Function, args: Vec<Value>) -> Result<Value, ErroExecucao> {
+        let Corpo::Bytecode(proto, capturas, globais) = &funcao.corpo else {
+            return funcao.call(self.interp, args);
+        };
+        self.verificar_profundidade()?;
+        verificar_aridade(proto, args.len())?;
+        let funcao = Rc::new(Function {
+            name: funcao.name.clone(),
+            params: funcao.params.clone(),
+            corpo: Corpo::Bytecode(proto.clone(), capturas.clone(), globais.clone()),
+        });
+        let base = self.quadros.len();
+        let base_pilha = self.pilha.len();
+        self.empilhar_quadro(Some(funcao), proto.clone(), base_pilha);
+        for (i, arg) in args.into_iter().enumerate() {
+            self.locais[self.base_locais + i] = Slot::Valor(arg);
+        }
+        self.executar(base)
+    }
+
+    fn empilhar_quadro(&mut self, funcao: Option<Rc<Function>>, proto: Rc<Prototipo>, base_pilha: usize) {
+        let base_locais = self.locais.len();
+        self.locais.resize(base_locais + proto.locais.len(), Slot::Vazio);
+        let anterior = std::mem::replace(&mut self.proto, proto);
+        if let Some(quadro) = self.quadros.last_mut() {
+            quadro.proto = Some(anterior);
+            quadro.ip = self.ip;
+        }
+        self.ip = 0;
+        self.base_locais = base_locais;
+        self.quadros.push(Quadro {
+            funcao,
+            proto: None,
+            ip: 0,
+            base_pilha,
+            base_locais,
+            base_iteradores: self.iteradores.len(),
+            base_tentativas: self.tentativas.len(),
+        });
+    }
+
+    fn desempilhar_quadro(&mut self) {
+        let quadro = self.quadros.pop().expect("quadro em execução");
+        self.pilha.truncate(quadro.base_pilha);
+        self.locais.truncate(quadro.base_locais);
+        self.iteradores.truncate(quadro.base_iteradores);
+        self.tentativas.truncate(quadro.base_tentativas);
+        if let Some(anterior) = self.quadros.last_mut() {
+            self.proto = anterior.proto.take().expect("código do quadro suspenso");
+            self.ip = anterior.ip;
+            self.base_locais = anterior.base_locais;
+        }
+    }
+
+    fn quadro(&self) -> &Quadro {
+        self.quadros.last().expect("quadro em execução")
+    }
+
+    fn capturas(&self) -> &[Celula] {
+        match self.quadro().funcao.as_deref() {
+            Some(Function { corpo: Corpo::Bytecode(_, capturas, _), .. }) => capturas,
+            _ => &[],
+        }
+    }
+
+    fn globais(&self) -> &EnvRef {
+        match self.quadro().funcao.as_deref() {
+            Some(Function { corpo: Corpo::Bytecode(_, _, globais), .. }) => globais,
+            _ => &self.globais,
+        }
+    }
+
+    fn saltar(&mut self, alvo: u32) {
+        self.ip = alvo as usize;
+    }
+
+    fn pop(&mut self) -> Value {
+        self.pilha.pop().expect("pilha da vm vazia")
+    }
+
+    fn topo(&self) -> Value {
+        self.pilha.last().cloned().expect("pilha da vm vazia")
+    }
+
+    /// Executa até o quadro de índice `base` retornar.
+    fn executar(&mut self, base: usize) -> Result<Value, ErroExecucao> {
+        loop {
+            match self.rodar(base) {
+                Ok(valor) => return Ok(valor),
+                Err(erro) => self.tratar_erro(erro, base)?,
+            }
+        }
+    }
+
+    fn rodar(&mut self, base: usize) -> Result<Value, ErroExecucao> {
+        loop {
+            let op = self.proto.codigo[self.ip];
+            self.ip += 1;
+
+            match op {
+                Op::Constante(i) => {
+                    let valor = self.proto.constantes[i as usize].clone();
+                    self.pilha.push(valor);
+                }
+                Op::Nulo => self.pilha.push(Value::Nil),
+                Op::Pop => {
+                    self.pop();
+                }
+                Op::Dup => {
+                    let valor = self.topo();
+                    self.pilha.push(valor);
+                }
+
+                Op::LerLocal(pos, alvo) => {
+                    let valor = match &self.locais[self.base_locais + pos as usize] {
+                        Slot::Valor(v) => Some(v.clone()),
+                        Slot::Celula(c) => c.borrow().clone(),
+                        Slot::Vazio => None,
+                    };
+                    if let Some(valor) = valor {
+                        self.pilha.push(valor);
+                        self.saltar(alvo);
+                    }
+                }
+                Op::EscreverLocal(pos, alvo, descartar) => {
+                    let i = self.base_locais + pos as usize;
+                    let definida = match &self.locais[i] {
+                        Slot::Valor(_) => true,
+                        Slot::Celula(c) => c.borrow().is_some(),
+                        Slot::Vazio => false,
+                    };
+                    if definida {
+                        let info = &self.proto.locais[pos as usize];
+                        if info.constante {
+                            return Err(erro_constante(&info.nome));
+                        }
+                        let valor = if descartar { self.pop() } else { self.topo() };
+                        match &mut self.locais[i] {
+                            Slot::Celula(c) => *c.borrow_mut() = Some(valor),
+                            slot => *slot = Slot::Valor(valor),
+                        }
+                        self.saltar(alvo);
+                    }
+                }
+                Op::LerCaptura(i, alvo) => {
+                    let valor = self.capturas()[i as usize].borrow().clone();
+                    if let Some(valor) = valor {
+                        self.pilha.push(valor);
+                        self.saltar(alvo);
+                    }
+                }
+                Op::EscreverCaptura(i, alvo, descartar) => {
+                    let celula = self.capturas()[i as usize].clone();
+                    if celula.borrow().is_some() {
+                        let info = &self.proto.capturas[i as usize];
+                        if info.constante {
+                            return Err(erro_constante(&info.nome));
+                        }
+                        let valor = if descartar { self.pop() } else { self.topo() };
+                        *celula.borrow_mut() = Some(valor);
+                        self.saltar(alvo);
+                    }
+                }
+                Op::LerGlobal(i) => {
+                    let valor = self.ler_global(i as usize)?;
+                    self.pilha.push(valor);
+                }
+                Op::EscreverGlobal(i, descartar) => {
+                    let valor = if descartar { self.pop() } else { self.topo() };
+                    self.globais().borrow_mut().assign(&self.proto.nomes[i as usize], valor)?;
+                }
+                Op::DefinirLocal(pos) => {
+                    let valor = self.pop();
+                    self.definir_local(pos, valor)?;
+                }
+                Op::DeclararGlobal(i, constante) => {
+                    let valor = self.pop();
+                    let redeclarar = self.interp.permitir_redeclaracao;
+                    self.globais()
+                        .borrow_mut()
+                        .declarar(&self.proto.nomes[i as usize], valor, constante, redeclarar)?;
+                }
+                Op::LimparLocais(inicio, fim) => {
+                    let base = self.base_locais;
+                    for slot in &mut self.locais[base + inicio as usize..base + fim as usize] {
+                        *slot = Slot::Vazio;
+                    }
+                }
+
+                Op::Passo => self.interp.passo()?,
+
+                Op::Unario(op) => {
+                    let valor = self.pop();
+                    self.pilha.push(operar_unario(&op, valor)?);
+                }
+                Op::Binario(op) => {
+                    let dir = self.pop();
+                    let esq = self.pop();
+                    self.pilha.push(binario(op, esq, dir, self.interp)?);
+                }
+                Op::BinarioConstante(op, c) => {
+                    let esq = self.pop();
+                    let dir = self.proto.constantes[c as usize].clone();
+                    self.pilha.push(binario(op, esq, dir, self.interp)?);
+                }
+                Op::CompararSaltar(op, alvo) => {
+                    let dir = self.pop();
+                    let esq = self.pop();
+                    if !binario(op, esq, dir, self.interp)?.is_truthy() {
+                        self.saltar(alvo);
+                    }
+                }
+                Op::CompararConstanteSaltar(op, c, alvo) => {
+                    let esq = self.pop();
+                    let dir = self.proto.constantes[c as usize].clone();
+                    if !binario(op, esq, dir, self.interp)?.is_truthy() {
+                        self.saltar(alvo);
+                    }
+                }
+                Op::LocalBinarioConstante(op, pos, c) => {
+                    let valor = match self.local_e_constante(op, pos, c) {
+                        Some(valor) => valor,
+                        None => {
+                            let esq = self.local_inicializada(pos);
+                            let dir = self.proto.constantes[c as usize].clone();
+                            binario(op, esq, dir, self.interp)?
+                        }
+                    };
+                    self.pilha.push(valor);
+                }
+                Op::CompararLocalConstanteSaltar(op, pos, c, alvo) => {
+                    let valor = match self.local_e_constante(op, pos, c) {
+                        Some(valor) => valor,
+                        None => {
+                            let esq = self.local_inicializada(pos);
+                            let dir = self.proto.constantes[c as usize].clone();
+                            binario(op, esq, dir, self.interp)?
+                        }
+                    };
+                    if !valor.is_truthy() {
+                        self.saltar(alvo);
+                    }
+                }
+                Op::AtualizarLocal(op, pos, c) => {
+                    let valor = match self.local_e_constante(op, pos, c) {
+                        Some(valor) => valor,
+                        None => {
+                            let esq = self.local_inicializada(pos);
+                            let dir = self.proto.constantes[c as usize].clone();
+                            binario(op, esq, dir, self.interp)?
+                        }
+                    };
+                    match &mut self.locais[self.base_locais + pos as usize] {
+                        Slot::Celula(c) => *c.borrow_mut() = Some(valor),
+                        slot => *slot = Slot::Valor(valor),
+                    }
+                }
+                Op::Array(n) => {
+                    let itens = self.pilha.split_off(self.pilha.len() - n as usize);
+                    self.pilha.push(Value::Array(Rc::new(RefCell::new(itens))));
+                }
+                Op::Dicionario(n) => {
+                    let pares = self.pilha.split_off(self.pilha.len() - 2 * n as usize);
+                    let mut mapa = BTreeMap::new();
+                    let mut pares = pares.into_iter();
+                    while let (Some(Value::Text(chave)), Some(valor)) = (pares.next(), pares.next()) {
+                        mapa.insert(chave, valor);
+                    }
+                    self.pilha.push(Value::Dicionario(Rc::new(RefCell::new(mapa))));
+                }
+                Op::ChaveDicionario => {
+                    if !matches!(self.pilha.last(), Some(Value::Text(_))) {
+                        return Err("Chaves de dicionário devem ser textos".into());
+                    }
+                }
+                Op::Indexar => {
+                    let indice = self.pop();
+                    let alvo = self.pop();
+                    self.pilha.push(indexar(alvo, indice)?);
+                }
+                Op::AtribuirIndice => {
+                    let valor = self.pop();
+                    let indice = self.pop();
+                    let alvo = self.pop();
+                    self.interp.limites.verificar_insercao(&alvo, &indice)?;
+                    self.pilha.push(atribuir_indice(alvo, indice, valor)?);
+                }
+                Op::Intervalo(tem_inicio, tem_fim, inclusivo) => {
+                    let fim = if tem_fim { Some(self.pop()) } else { None };
+                    let inicio = if tem_inicio { Some(self.pop()) } else { None };
+                    self.pilha.push(criar_intervalo(inicio, fim, inclusivo)?);
+                }
+
+                Op::Saltar(alvo) => self.saltar(alvo),
+                Op::SaltarSeFalso(alvo) => {
+                    if !self.pop().is_truthy() {
+                        self.saltar(alvo);
+                    }
+                }
+                Op::Chamar(argc) => self.chamar_valor(argc as usize)?,
+                Op::ChamarFinal(argc) => {
+                    let indice = self.pilha.len() - 1 - argc as usize;
+                    if matches!(&self.pilha[indice], Value::Function(f) if matches!(f.corpo, Corpo::Bytecode(..))) {
+                        // Leva função e argumentos para o início do quadro, que é descartado
+                        let base_pilha = self.quadro().base_pilha;
+                        self.pilha.drain(base_pilha..indice);
+                        self.quadros.last_mut().expect("quadro em execução").base_pilha = base_pilha + argc as usize + 1;
+                        self.desempilhar_quadro();
+                    }
+                    self.chamar_valor(argc as usize)?;
+                }
+                Op::Funcao(i) => {
+                    let funcao = self.criar_funcao(i as usize);
+                    self.pilha.push(funcao);
+                }
+                Op::Retornar => {
+                    let valor = self.pop();
+                    if self.tentativas.len() == self.quadro().base_tentativas {
+                        // Sem `finalmente` pendente: retorno direto
+                        self.desempilhar_quadro();
+                        if self.quadros.len() == base {
+                            return Ok(valor);
+                        }
+                        self.pilha.push(valor);
+                    } else if let Some(valor) = self.concluir(Pendente::Retorno(valor), base)? {
+                        return Ok(valor);
+                    }
+                }
+                Op::Imprimir => {
+                    let valor = self.pop();
+                    let texto = self.interp.texto(&valor)?;
+                    self.interp.escrever_linha(&texto)?;
+                }
+                Op::Lancar => {
+                    let valor = self.pop();
+                    self.interp.texto(&valor)?;
+                    return Err(ErroExecucao::lancado(valor));
+                }
+
+                Op::IterarInicio => {
+                    let valor = self.pop();
+                    self.iteradores.push(Iterador::novo(&valor)?);
+                }
+                Op::IterarProximo(fim, pos_indice, pos_item) => {
+                    let base = self.base_locais;
+                    let iterador = self.iteradores.last_mut().expect("laço em execução");
+                    let por_chave = iterador.por_chave();
+                    match iterador.proximo()? {
+                        Some((chave, valor)) if pos_indice != SEM_POSICAO => {
+                            self.locais[base + pos_indice as usize] = Slot::Valor(chave);
+                            self.locais[base + pos_item as usize] = Slot::Valor(valor);
+                        }
+                        Some((chave, valor)) => {
+                            let item = if por_chave { chave } else { valor };
+                            self.locais[base + pos_item as usize] = Slot::Valor(item);
+                        }
+                        None => self.saltar(fim),
+                    }
+                }
+                Op::IterarFim => {
+                    self.iteradores.pop();
+                }
+
+                Op::TentarInicio(i) => {
+                    let info = self.proto.tentativas[i as usize];
+                    self.tentativas.push(Tentativa {
+                        info,
+                        fase: Fase::Corpo,
+                        pilha: self.pilha.len(),
+                        iteradores: self.iteradores.len(),
+                        pendente: Pendente::Nenhum,
+                    });
+                }
+                Op::TentarConcluir => {
+                    let tentativa = self.tentativas.last_mut().expect("bloco tentar em execução");
+                    match tentativa.info.finalmente {
+                        Some(finalmente) if tentativa.fase != Fase::Finalmente => {
+                            tentativa.fase = Fase::Finalmente;
+                            self.saltar(finalmente);
+                        }
+                        _ => {
+                            let fim = tentativa.info.fim;
+                            self.tentativas.pop();
+                            self.saltar(fim);
+                        }
+                    }
+                }
+                Op::FimFinalmente => {
+                    let tentativa = self.tentativas.pop().expect("bloco tentar em execução");
+                    if let Some(valor) = self.concluir(tentativa.pendente, base)? {
+                        return Ok(valor);
+                    }
+                }
+                Op::SaltarSaindo(blocos, alvo) => {
+                    self.concluir(Pendente::Saltar(blocos, alvo), base)?;
+                }
+                Op::ForaDeLaco(parar) => {
+                    if let Some(valor) = self.concluir(Pendente::ForaDeLaco(parar), base)? {
+                        return Ok(valor);
+                    }
+                }
+
+                Op::CarregarModulo(caminho) => {
+                    let caminho = self.proto.nomes[caminho as usize].clone();
+                    let modulo = self.interp.carregar_modulo(&caminho)?;
+                    self.pilha.push(modulo);
+                }
+                Op::ExtrairExportado(caminho, nome) => {
+                    let modulo = self.pop();
+                    let proto = &self.proto;
+                    let Value::Dicionario(exportados) = modulo else { unreachable!() };
+                    let valor = exportados.borrow().get(&proto.nomes[nome as usize]).cloned().ok_or_else(|| {
+                        ErroExecucao::novo(
+                            "ErroImportacao",
+                            format!(
+                                "O módulo '{}' não exporta '{}'",
+                                proto.nomes[caminho as usize], proto.nomes[nome as usize]
+                            ),
+                        )
+                    })?;
+                    self.pilha.push(valor);
+                }
+                Op::MarcarExportados(inicio, n) => {
+                    let proto = self.proto.clone();
+                    self.interp.marcar_exportados(&proto.nomes[inicio as usize..(inicio + n) as usize]);
+                }
+            }
+        }
+    }
+
+    /// Recusa uma chamada nova quando as funções em execução, nesta `Vm` e
+    /// no interpretador, já chegaram ao limite.
+    fn verificar_profundidade(&self) -> Result<(), ErroExecucao> {
+        let principal = self.quadros.first().is_some_and(|q| q.funcao.is_none());
+        let profundidade = self.interp.pilha_chamadas.len() + self.quadros.len() - usize::from(principal);
+        if profundidade >= self.interp.profundidade_maxima {
+            return Err(ErroExecucao::recursao_maxima(self.interp.profundidade_maxima));
+        }
+        Ok(())
+    }
+
+    /// Pilha de chamadas no formato de `ErroExecucao::rastro`, com a linha da
+    /// instrução em execução em cada quadro.
+    fn rastro(&self) -> Vec<(String, usize)> {
+        let principal = self.quadros.first().is_some_and(|q| q.funcao.is_none());
+        let mut rastro = if principal { Vec::new() } else { self.interp.rastro() };
+        // A chamada que entrou nesta `Vm` já está no topo da pilha do
+        // interpretador; quem a mostra, com a linha certa, é o primeiro quadro
+        if !principal && !self.interp.pilha_chamadas.is_empty() {
+            rastro.pop();
+        }
+        for (i, quadro) in self.quadros.iter().enumerate() {
+            let (proto, ip) = match &quadro.proto {
+                Some(proto) if i + 1 < self.quadros.len() => (proto, quadro.ip),
+                _ => (&self.proto, self.ip),
+            };
+            let nome = quadro.funcao.as_ref().map_or("<principal>", |f| f.name.as_str());
+            rastro.push((nome.to_string(), proto.linhas[ip.saturating_sub(1)]));
+        }
+        rastro
+    }
+
+    fn ler_global(&self, i: usize) -> Result<Value, ErroExecucao> {
+        let nome = &self.proto.nomes[i];
+        let cache = &self.proto.cache_globais[i];
+        let globais = self.globais().borrow();
+        if let Some(valor) = globais.valor_em(cache.get(), nome) {
+            return Ok(valor);
+        }
+        if let Some(posicao) = globais.posicao(nome) {
+            cache.set(posicao);
+        }
+        globais.get(nome).map_err(|e| ErroExecucao::novo("VariavelIndefinida", e))
+    }
+
+    /// `local op constante` quando as duas são números, sem clonar nenhuma delas.
+    fn local_e_constante(&self, op: BinOp, pos: u32, c: u32) -> Option<Value> {
+        match (&self.locais[self.base_locais + pos as usize], &self.proto.constantes[c as usize]) {
+            (Slot::Valor(Value::Number(a)), Value::Number(b)) => numerico(op, *a, *b),
+            _ => None,
+        }
+    }
+
+    /// Valor de uma local que o compilador sabe estar inicializada.
+    fn local_inicializada(&self, pos: u32) -> Value {
+        let valor = match &self.locais[self.base_locais + pos as usize] {
+            Slot::Valor(v) => Some(v.clone()),
+            Slot::Celula(c) => c.borrow().clone(),
+            Slot::Vazio => None,
+        };
+        valor.expect("local inicializada antes do uso")
+    }
+
+    fn definir_local(&mut self, pos: u32, valor: Value) -> Result<(), ErroExecucao> {
+        let i = self.base_locais + pos as usize;
+        let definida = match &self.locais[i] {
+            Slot::Valor(_) => true,
+            Slot::Celula(c) => c.borrow().is_some(),
+            Slot::Vazio => false,
+        };
+        if definida {
+            let info = &self.proto.locais[pos as usize];
+            if info.constante || !self.interp.permitir_redeclaracao {
+                let tipo = if info.constante { "Constante" } else { "Variável" };
+                return Err(ErroExecucao::novo(
+                    "Redeclaracao",
+                    format!("{} '{}' já foi declarada neste escopo", tipo, info.nome),
+                ));
+            }
+        }
+        match &mut self.locais[i] {
+            Slot::Celula(c) => *c.borrow_mut() = Some(valor),
+            slot => *slot = Slot::Valor(valor),
+        }
+        Ok(())
+    }
+
+    fn criar_funcao(&mut self, indice: usize) -> Value {
+        let proto = self.proto.funcoes[indice].clone();
+        let mut capturas = Vec::with_capacity(proto.capturas.len());
+        for info in &proto.capturas {
+            if !info.local {
+                capturas.push(self.capturas()[info.indice as usize].clone());
+                continue;
+            }
+            let slot = &mut self.locais[self.base_locais + info.indice as usize];
+            let celula = match slot {
+                Slot::Celula(c) => c.clone(),
+                Slot::Valor(v) => Rc::new(RefCell::new(Some(v.clone()))),
+                Slot::Vazio => Rc::new(RefCell::new(None)),
+            };
+            *slot = Slot::Celula(celula.clone());
+            capturas.push(celula);
+        }
+        Value::Function(Rc::new(Function {
+            name: proto.nome.clone(),
+            params: proto.params.clone(),
+            corpo: Corpo::Bytecode(proto, capturas, self.globais().clone()),
+        }))
+    }
+
+    fn chamar_valor(&mut self, argc: usize) -> Result<(), ErroExecucao> {
+        let indice = self.pilha.len() - 1 - argc;
+        let funcao = match &self.pilha[indice] {
+            Value::Function(f) => f.clone(),
+            _ => return Err("Tentativa de chamar algo que não é função".into()),
+        };
+        if let Corpo::Bytecode(proto, _, _) = &funcao.corpo {
+            self.verificar_profundidade()?;
+            verificar_aridade(proto, argc)?;
+            let proto = proto.clone();
+            self.empilhar_quadro(Some(funcao), proto, indice);
+            for i in (0..argc).rev() {
+                let arg = self.pop();
+                self.locais[self.base_locais + i] = Slot::Valor(arg);
+            }
+        } else {
+            let args = self.pilha.split_off(indice + 1);
+            self.pilha.pop();
+            let resultado = funcao.call(self.interp, args)?;
+            self.pilha.push(resultado);
+        }
+        Ok(())
+    }
+
+    /// Encerra o bloco atual por `retornar`, `parar`/`continuar` ou fim de um
+    /// `finalmente`, passando pelos `finalmente` pendentes do quadro. Devolve o
+    /// valor quando o quadro `base` retorna.
+    fn concluir(&mut self, mut pendente: Pendente, base: usize) -> Result<Option<Value>, ErroExecucao> {
+        // Um erro ainda pode cair num `capturar` mais externo; quem o procura é `tratar_erro`
+        if let Pendente::Erro(erro) = pendente {
+            return Err(erro);
+        }
+        loop {
+            if let Pendente::Saltar(0, alvo) = pendente {
+                self.saltar(alvo);
+                return Ok(None);
+            }
+            if self.tentativas.len() > self.quadro().base_tentativas {
+                let tentativa = self.tentativas.last_mut().expect("bloco tentar em execução");
+                if let Pendente::Saltar(blocos, alvo) = pendente {
+                    pendente = Pendente::Saltar(blocos - 1, alvo);
+                }
+                match tentativa.info.finalmente {
+                    Some(finalmente) if tentativa.fase != Fase::Finalmente => {
+                        tentativa.fase = Fase::Finalmente;
+                        tentativa.pendente = pendente;
+                        let (pilha, iteradores) = (tentativa.pilha, tentativa.iteradores);
+                        self.pilha.truncate(pilha);
+                        self.iteradores.truncate(iteradores);
+                        self.saltar(finalmente);
+                        return Ok(None);
+                    }
+                    _ => {
+                        self.tentativas.pop();
+                        continue;
+                    }
+                }
+            }
+            return match pendente {
+                Pendente::Nenhum => Ok(None),
+                Pendente::Saltar(_, alvo) => {
+                    self.saltar(alvo);
+                    Ok(None)
+                }
+                Pendente::Erro(_) => unreachable!("erro devolvido antes do laço"),
+                Pendente::Retorno(valor) => {
+                    self.desempilhar_quadro();
+                    if self.quadros.len() == base {
+                        return Ok(Some(valor));
+                    }
+                    self.pilha.push(valor);
+                    Ok(None)
+                }
+                Pendente::ForaDeLaco(parar) => {
+                    let controle = if parar { Controle::Parar } else { Controle::Continuar };
+                    let mut erro = controle.fora_de_laco();
+                    // Numa função o erro aponta a chamada; no programa, o próprio comando
+                    if self.quadro().funcao.is_none() {
+                        erro.linha = Some(self.proto.linhas[self.ip.saturating_sub(1)]);
+                        erro.rastro = Some(Rc::new(self.rastro()));
+                    }
+                    self.desempilhar_quadro();
+                    Err(erro)
+                }
+            };
+        }
+    }
+
+    /// Procura um `capturar` ou `finalmente` para o erro, desempilhando os
+    /// quadros até `base`. Devolve o erro se nenhum bloco o tratar.
+    fn tratar_erro(&mut self, mut erro: ErroExecucao, base: usize) -> Result<(), ErroExecucao> {
+        if erro.rastro.is_none() {
+            erro.rastro = Some(Rc::new(self.rastro()));
+        }
+        let capturavel = !self.interp.limites.fatal(&erro);
+        while self.quadros.len() > base {
+            if erro.linha.is_none() {
+                let linha = self.proto.linhas[self.ip.saturating_sub(1)];
+                if linha > 0 {
+                    erro.linha = Some(linha);
+                }
+            }
+            while self.tentativas.len() > self.quadro().base_tentativas {
+                let tentativa = self.tentativas.last_mut().expect("bloco tentar em execução");
+                let (pilha, iteradores) = (tentativa.pilha, tentativa.iteradores);
+                match (&tentativa.fase, tentativa.info.captura, tentativa.info.finalmente) {
+                    (Fase::Corpo, Some(captura), _) if capturavel => {
+                        tentativa.fase = Fase::Captura;
+                        self.pilha.truncate(pilha);
+                        self.iteradores.truncate(iteradores);
+                        self.pilha.push(erro.to_value());
+                        self.saltar(captura);
+                        return Ok(());
+                    }
+                    (Fase::Corpo | Fase::Captura, _, Some(finalmente)) => {
+                        tentativa.fase = Fase::Finalmente;
+                        tentativa.pendente = Pendente::Erro(erro);
+                        self.pilha.truncate(pilha);
+                        self.iteradores.truncate(iteradores);
+                        self.saltar(finalmente);
+                        return Ok(());
+                    }
+                    _ => {
+                        self.tentativas.pop();
+                    }
+                }
+            }
+            self.desempilhar_quadro();
+        }
+        Err(erro)
+    }
+}
+
+/// Caminho rápido das operações entre números; o resto (e a divisão, que
+/// pode falhar) fica com `operar_binario`.
+fn binario(op: BinOp, esq: Value, dir: Value, interp: &mut Interpreter) -> Result<Value, ErroExecucao> {
+    let (a, b) = match (&esq, &dir) {
+        (Value::Number(a), Value::Number(b)) => (*a, *b),
+        _ => {
+            return operar_binario(&op, esq, dir, &mut interp.consumo, &interp.limites)
+                .and_then(|v| interp.limites.verificar(v))
+        }
+    };
+    match numerico(op, a, b) {
+        Some(valor) => Ok(valor),
+        None => operar_binario(&op, esq, dir, &mut interp.consumo, &interp.limites),
+    }
+}
+
+/// Operações entre números que não podem falhar.
+fn numerico(op: BinOp, a: f64, b: f64) -> Option<Value> {
+    Some(match op {
+        BinOp::Add => Value::Number(a + b),
+        BinOp::Sub => Value::Number(a - b),
+        BinOp::Mul => Value::Number(a * b),
+        BinOp::Lt => Value::Bool(a < b),
+        BinOp::Gt => Value::Bool(a > b),
+        BinOp::Le => Value::Bool(a <= b),
+        BinOp::Ge => Value::Bool(a >= b),
+        _ => return None,
+    })
+}
+
+fn verificar_aridade(proto: &Prototipo, argc: usize) -> Result<(), ErroExecucao> {
+    if argc != proto.params.len() {
+        return Err(format!("Esperado {} argumentos mas recebeu {}", proto.params.len(), argc).into());
+    }
+    Ok(())
+}
+
+fn erro_constante(nome: &str) -> ErroExecucao {
+    ErroExecucao::novo("AtribuicaoConstante", format!("Não é possível alterar a constante '{}'", nome))
+}
@@ -0,0 +1,82 @@
+use lucas::{Interpreter, Saida, Value};
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn diretorio_temporario(nome: &str) -> PathBuf {
+    let diretorio = std::env::temp_dir().join(format!("lucas_{}_{}", nome, std::process::id()));
+    fs::create_dir_all(&diretorio).unwrap();
+    diretorio
+}
+
+fn interpretador() -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_saida(Saida::Buffer(Vec::new()));
+    interpreter.set_saida_erros(Saida::Buffer(Vec::new()));
+    interpreter
+}
+
+#[test]
+fn escrever_anexar_ler_e_remover() {
+    let diretorio = diretorio_temporario("arquivos");
+    let mut interpreter = interpretador();
+    interpreter.set_global("dir", Value::from(diretorio.display().to_string()));
+    let codigo = r#"
+        variavel caminho = dir + "/notas.txt";
+        escrever_arquivo(caminho, "linha 1\n");
+        anexar_arquivo(caminho, "linha 2\r\n");
+        anexar_arquivo(dir + "/novo.txt", 42);
+        imprimir(existe_arquivo(caminho), listar_diretorio(dir));
+        imprimir ler_arquivo(caminho);
+
+        variavel arquivo = abrir(caminho);
+        variavel linha = ler_linha(arquivo);
+        enquanto (linha != nulo) {
+            imprimir "[" + linha + "]";
+            linha = ler_linha(arquivo);
+        }
+        fechar(arquivo);
+        imprimir arquivo;
+        tentar { ler_linha(arquivo); } capturar (e) { imprimir e.mensagem; }
+
+        remover_arquivo(caminho);
+        imprimir(existe_arquivo(caminho), ler_arquivo(dir + "/novo.txt"));
+    "#;
+    interpreter.eval(codigo).unwrap();
+    let caminho = diretorio.join("notas.txt").display().to_string();
+    assert_eq!(
+        interpreter.saida_mut().capturado(),
+        format!(
+            "true [notas.txt, novo.txt]\nlinha 1\nlinha 2\r\n\n[linha 1]\n[linha 2]\n<arquivo {0} (fechado)>\n\
+             Arquivo '{0}' já foi fechado\nfalse 42\n",
+            caminho
+        )
+    );
+    fs::remove_dir_all(diretorio).unwrap();
+}
+
+#[test]
+fn erros_de_arquivo_podem_ser_capturados() {
+    let mut interpreter = interpretador();
+    let valor = interpreter
+        .eval("variavel m = nulo; tentar { ler_arquivo(\"/nao/existe.txt\"); } capturar (e) { m = e.mensagem; } m")
+        .unwrap();
+    assert_eq!(valor.to_string_repr(), "Erro ao ler arquivo '/nao/existe.txt': arquivo ou diretório não encontrado");
+
+    let erro = interpreter.eval("ler_arquivo(1)").unwrap_err();
+    assert!(erro.mensagem.contains("caminho como texto"), "{}", erro.mensagem);
+    let erro = interpreter.eval("fechar(\"x\")").unwrap_err();
+    assert!(erro.mensagem.contains("abrir()"), "{}", erro.mensagem);
+}
+
+#[test]
+fn erros_vao_para_a_saida_de_erros() {
+    let saida = Command::new(env!("CARGO_BIN_EXE_lucas"))
+        .args(["-e", "imprimir \"antes\";\nler_arquivo(\"/nao/existe.txt\");\nimprimir \"depois\";"])
+        .output()
+        .unwrap();
+    assert_eq!(saida.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&saida.stdout), "antes\n");
+    let erros = String::from_utf8_lossy(&saida.stderr);
+    assert!(erros.contains("Erro ao ler arquivo '/nao/existe.txt'"), "{}", erros);
+}
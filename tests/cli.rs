@@ -0,0 +1,163 @@
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+/// Roda o executável com `args` e `entrada` no stdin.
+fn lucas(args: &[&str], entrada: &str) -> Output {
+    let mut processo = Command::new(env!("CARGO_BIN_EXE_lucas"))
+        .args(args)
+        .env("NO_COLOR", "1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    processo.stdin.take().unwrap().write_all(entrada.as_bytes()).unwrap();
+    processo.wait_with_output().unwrap()
+}
+
+fn texto(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+fn arquivo(nome: &str, conteudo: &str) -> PathBuf {
+    let caminho = std::env::temp_dir().join(format!("lucas_cli_{}_{}.lucas", nome, std::process::id()));
+    fs::write(&caminho, conteudo).unwrap();
+    caminho
+}
+
+#[test]
+fn executar_arquivo_com_argumentos() {
+    let caminho = arquivo("argumentos", "imprimir(comprimento(argumentos), argumentos);\n");
+    let caminho = caminho.to_str().unwrap();
+    for args in [vec![caminho, "a", "b c"], vec!["executar", caminho, "a", "b c"], vec!["--vm", caminho, "a", "b c"]] {
+        let saida = lucas(&args, "");
+        assert!(saida.status.success(), "{:?}", args);
+        assert_eq!(texto(&saida.stdout), "2 [a, b c]\n");
+    }
+    fs::remove_file(caminho).unwrap();
+}
+
+#[test]
+fn codigo_pela_linha_de_comando_e_pela_entrada_padrao() {
+    let saida = lucas(&["-e", "imprimir argumentos[0] + 1;", "x"], "");
+    assert_eq!(texto(&saida.stdout), "x1\n");
+
+    let saida = lucas(&["-", "arg"], "imprimir argumentos;\n");
+    assert_eq!(texto(&saida.stdout), "[arg]\n");
+}
+
+#[test]
+fn verificar_nao_executa() {
+    let caminho = arquivo("verificar", "imprimir \"executou\";\n");
+    let saida = lucas(&["verificar", caminho.to_str().unwrap()], "");
+    assert!(saida.status.success());
+    assert_eq!(texto(&saida.stdout), format!("{}: nenhum erro encontrado\n", caminho.display()));
+
+    fs::write(&caminho, "imprimir \"executou\";\nimprimir nada;\n").unwrap();
+    let saida = lucas(&["verificar", caminho.to_str().unwrap()], "");
+    assert_eq!(saida.status.code(), Some(2));
+    assert_eq!(texto(&saida.stdout), "");
+    assert!(texto(&saida.stderr).contains("Variável 'nada' não definida"));
+
+    fs::write(&caminho, "variavel total = 1;\nimprimir total;\nvariavel total = 2;\n").unwrap();
+    let saida = lucas(&["verificar", caminho.to_str().unwrap()], "");
+    assert_eq!(saida.status.code(), Some(2));
+    assert!(texto(&saida.stderr).contains("Variável 'total' já foi declarada neste escopo"));
+    fs::remove_file(caminho).unwrap();
+}
+
+#[test]
+fn tokens_e_arvore_do_arquivo() {
+    let saida = lucas(&["tokens", "-"], "x;");
+    assert_eq!(texto(&saida.stdout), "    1:1   Ident(\"x\")\n    1:2   PontoVirgula\n    1:3   Fim\n");
+
+    let saida = lucas(&["ast", "-"], "nulo;");
+    assert_eq!(texto(&saida.stdout), "ExprStmt(\n    Nulo,\n    1,\n)\n");
+
+    // Erros aparecem depois da listagem e saem com o código de erro de análise
+    let saida = lucas(&["tokens", "-"], "x = \"abc");
+    assert_eq!(saida.status.code(), Some(2));
+    assert!(texto(&saida.stderr).contains("Texto sem as aspas de fechamento"), "{}", texto(&saida.stderr));
+
+    let saida = lucas(&["ast", "-"], "nulo;\nx = (1");
+    assert_eq!(saida.status.code(), Some(2));
+    assert!(texto(&saida.stdout).starts_with("ExprStmt(\n    Nulo,"));
+    assert!(texto(&saida.stderr).contains("Esperado ')'"), "{}", texto(&saida.stderr));
+
+    // `tokens` só olha o léxico
+    let saida = lucas(&["tokens", "-"], "x = (1");
+    assert!(saida.status.success());
+}
+
+#[test]
+fn testar_chama_as_funcoes_de_teste() {
+    let caminho = arquivo(
+        "testar",
+        "funcao teste_soma() { se (1 + 1 != 2) { lancar \"conta errada\"; } }\n\
+         funcao teste_falha() { lancar \"esperava 3\"; }\n\
+         funcao auxiliar() { lancar \"não é teste\"; }\n",
+    );
+    let nome = caminho.to_str().unwrap();
+    let saida = lucas(&["testar", nome], "");
+    assert_eq!(saida.status.code(), Some(1));
+    assert_eq!(
+        texto(&saida.stdout),
+        format!("FALHOU {0}: teste_falha\nok     {0}: teste_soma\n\n2 testes, 1 falhas\n", nome)
+    );
+    assert!(texto(&saida.stderr).contains("esperava 3"));
+
+    fs::write(&caminho, "funcao teste_ok() { }\n").unwrap();
+    let saida = lucas(&["testar", nome], "");
+    assert!(saida.status.success());
+    fs::remove_file(caminho).unwrap();
+}
+
+#[test]
+fn versao_ajuda_e_opcao_desconhecida() {
+    let saida = lucas(&["--versao"], "");
+    assert_eq!(texto(&saida.stdout), format!("lucas {}\n", env!("CARGO_PKG_VERSION")));
+
+    let saida = lucas(&["--ajuda"], "");
+    assert!(saida.status.success());
+    assert!(texto(&saida.stdout).contains("lucas verificar <arquivo>"));
+
+    let saida = lucas(&["--nao-existe"], "");
+    assert_eq!(saida.status.code(), Some(1));
+    assert!(texto(&saida.stdout).contains("Uso:"));
+
+    let saida = lucas(&["/nao/existe.lucas"], "");
+    assert_eq!(saida.status.code(), Some(1));
+    assert!(texto(&saida.stderr).contains("Erro ao ler arquivo '/nao/existe.lucas'"));
+}
+
+#[test]
+fn codigos_de_saida() {
+    let casos = [
+        ("imprimir 1;", 0),
+        ("imprimir (1;", 2),
+        ("imprimir nada;", 2),
+        ("imprimir 1 / 0;", 1),
+        ("lancar \"x\";", 1),
+        ("sair(7);", 7),
+        ("sair();", 0),
+        ("tentar { sair(3); } capturar (e) { } finalmente { imprimir \"nunca\"; }", 3),
+        ("sair(0); imprimir 1 / 0;", 0),
+        ("sair(256);", 1),
+        ("sair(-1);", 1),
+        ("sair(1.5);", 1),
+        ("lancar {\"tipo\": \"Sair\", \"valor\": 7};", 1),
+    ];
+    for usar_vm in [false, true] {
+        for (codigo, esperado) in casos {
+            let args = if usar_vm { vec!["--vm", "-e", codigo] } else { vec!["-e", codigo] };
+            let saida = lucas(&args, "");
+            assert_eq!(saida.status.code(), Some(esperado), "{} (vm: {})", codigo, usar_vm);
+        }
+    }
+
+    let saida = lucas(&["-e", "imprimir \"antes\"; sair(256);"], "");
+    assert_eq!(texto(&saida.stdout), "antes\n");
+    assert!(texto(&saida.stderr).contains("sair() espera um código inteiro entre 0 e 255"));
+}
@@ -0,0 +1,68 @@
+use lucas::{ErrorType, Interpreter, Saida};
+
+fn interpretador(usar_vm: bool) -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_saida(Saida::Buffer(Vec::new()));
+    interpreter.set_saida_erros(Saida::Buffer(Vec::new()));
+    interpreter.set_usar_vm(usar_vm);
+    interpreter
+}
+
+#[test]
+fn atribuir_a_constante_e_erro_antes_de_executar() {
+    for codigo in [
+        "imprimir 1; constante A = 1; A = 2;",
+        "imprimir 1; funcao f() { constante B = 1; B = 2; }",
+        "imprimir 1; funcao f() { A = 2; } constante A = 1;",
+    ] {
+        let mut interpreter = interpretador(false);
+        let erro = interpreter.eval(codigo).unwrap_err();
+        assert!(matches!(erro.tipo, ErrorType::SemanticoError), "{}", codigo);
+        assert!(erro.mensagem.starts_with("Não é possível alterar a constante"), "{}", erro.mensagem);
+        assert_eq!(interpreter.saida_mut().capturado(), "");
+    }
+}
+
+#[test]
+fn constante_de_execucao_anterior_tambem_e_protegida() {
+    for usar_vm in [false, true] {
+        let mut interpreter = interpretador(usar_vm);
+        interpreter.eval("constante LIMITE = 10;").unwrap();
+        let erro = interpreter.eval("LIMITE = 11;").unwrap_err();
+        assert!(erro.mensagem.contains("constante 'LIMITE'"), "{}", erro.mensagem);
+        let erro = interpreter.eval("variavel LIMITE = 11;").unwrap_err();
+        assert_eq!(erro.mensagem, "Redeclaracao: Constante 'LIMITE' já foi declarada neste escopo");
+        assert_eq!(interpreter.get_global("LIMITE").unwrap().to_string_repr(), "10");
+    }
+}
+
+#[test]
+fn redeclarar_no_mesmo_escopo_e_erro() {
+    let mut interpreter = interpretador(false);
+    let erro = interpreter.eval("{ variavel y = 1; variavel y = 2; }").unwrap_err();
+    assert!(matches!(erro.tipo, ErrorType::SemanticoError));
+    assert_eq!(erro.mensagem, "Variável 'y' já foi declarada neste escopo");
+
+    for usar_vm in [false, true] {
+        // No nível principal também é apontado antes de executar
+        let mut interpreter = interpretador(usar_vm);
+        let erro = interpreter.eval("imprimir 1; variavel x = 1; funcao x() { }").unwrap_err();
+        assert!(matches!(erro.tipo, ErrorType::SemanticoError));
+        assert_eq!(erro.mensagem, "Variável 'x' já foi declarada neste escopo");
+        assert_eq!(interpreter.saida_mut().capturado(), "");
+        // Uma execução anterior só é conhecida ao executar
+        interpreter.eval("variavel x = 1;").unwrap();
+        let erro = interpreter.eval("variavel x = 2;").unwrap_err();
+        assert_eq!(erro.mensagem, "Redeclaracao: Variável 'x' já foi declarada neste escopo");
+        // Em outro escopo, o mesmo nome pode ser declarado
+        interpreter.eval("{ variavel x = 3; imprimir x; }").unwrap();
+        assert_eq!(interpreter.saida_mut().capturado(), "3\n");
+    }
+}
+
+#[test]
+fn conteudo_de_constante_continua_mutavel() {
+    let mut interpreter = interpretador(false);
+    let valor = interpreter.eval("constante LISTA = [1]; LISTA[0] = 5; LISTA").unwrap();
+    assert_eq!(valor.to_string_repr(), "[5]");
+}
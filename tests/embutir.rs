@@ -0,0 +1,133 @@
+use lucas::{Arquivo, ErrorType, Intervalo, Interpreter, Limites, Saida, Value, LIMITE_EXCEDIDO};
+use std::cell::RefCell;
+
+fn interpretador() -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_saida(Saida::Buffer(Vec::new()));
+    interpreter.set_saida_erros(Saida::Buffer(Vec::new()));
+    interpreter
+}
+
+#[test]
+fn eval_devolve_o_valor_da_ultima_expressao() {
+    let mut interpreter = interpretador();
+    let valor = interpreter.eval("variavel x = 20; x + 22").unwrap();
+    assert!(matches!(valor, Value::Number(n) if n == 42.0));
+    assert!(matches!(interpreter.eval("variavel y = 1;").unwrap(), Value::Nil));
+}
+
+#[test]
+fn eval_guarda_o_estado_entre_chamadas() {
+    let mut interpreter = interpretador();
+    interpreter.eval("funcao soma(a, b) { retornar a + b; }").unwrap();
+    let valor = interpreter.eval("soma(2, 3)").unwrap();
+    assert_eq!(valor.to_string_repr(), "5");
+}
+
+#[test]
+fn globais_vao_e_voltam_do_script() {
+    let mut interpreter = interpretador();
+    interpreter.set_global("itens", Value::from(vec![Value::from(1.0), Value::from("dois")]));
+    interpreter.eval("variavel total = comprimento(itens); itens[0] = 10;").unwrap();
+
+    assert_eq!(interpreter.get_global("total").unwrap().to_string_repr(), "2");
+    assert_eq!(interpreter.get_global("itens").unwrap().to_string_repr(), "[10, dois]");
+    assert!(interpreter.get_global("inexistente").is_none());
+}
+
+#[test]
+fn call_function_chama_funcoes_do_script() {
+    let mut interpreter = interpretador();
+    interpreter.eval("funcao saudacao(nome) { retornar \"Olá, \" + nome; }").unwrap();
+
+    let valor = interpreter.call_function("saudacao", vec![Value::from("Lia")]).unwrap();
+    assert_eq!(valor.to_string_repr(), "Olá, Lia");
+
+    let erro = interpreter.call_function("nao_existe", Vec::new()).unwrap_err();
+    assert!(matches!(erro.tipo, ErrorType::RuntimeError));
+    assert!(erro.mensagem.contains("nao_existe"), "{}", erro.mensagem);
+    let erro = interpreter.call_function("saudacao", Vec::new()).unwrap_err();
+    assert!(erro.mensagem.contains("argumentos"), "{}", erro.mensagem);
+}
+
+#[test]
+fn register_function_expoe_closures_ao_script() {
+    let mut interpreter = interpretador();
+    interpreter.register_function("maiusculo", |args| match args {
+        [Value::Text(texto)] => Ok(Value::from(texto.to_uppercase())),
+        _ => Err("maiusculo() espera um texto".into()),
+    });
+
+    let valor = interpreter.eval("maiusculo(\"lucas\")").unwrap();
+    assert_eq!(valor.to_string_repr(), "LUCAS");
+
+    // O `Err` da closure pode ser capturado pelo script
+    let valor = interpreter
+        .eval("variavel m = \"\"; tentar { maiusculo(1); } capturar (erro) { m = erro.mensagem; } m")
+        .unwrap();
+    assert_eq!(valor.to_string_repr(), "maiusculo() espera um texto");
+}
+
+#[test]
+fn saida_capturada_em_buffer_e_closure() {
+    let mut interpreter = interpretador();
+    interpreter.eval("imprimir(\"um\"); imprimir(2);").unwrap();
+    assert_eq!(interpreter.saida_mut().capturado(), "um\n2\n");
+    assert_eq!(interpreter.saida_mut().capturado(), "");
+
+    let trechos = std::rc::Rc::new(std::cell::RefCell::new(String::new()));
+    let destino = trechos.clone();
+    interpreter.set_saida(Saida::Funcao(Box::new(move |texto| destino.borrow_mut().push_str(texto))));
+    interpreter.eval("imprimir(\"três\");").unwrap();
+    assert_eq!(*trechos.borrow(), "três\n");
+}
+
+#[test]
+fn erros_de_sintaxe_e_de_execucao() {
+    let mut interpreter = interpretador();
+    let erro = interpreter.eval("variavel = ;").unwrap_err();
+    assert!(matches!(erro.tipo, ErrorType::SintaticoError));
+
+    let erro = interpreter.eval("1 / 0").unwrap_err();
+    assert!(matches!(erro.tipo, ErrorType::RuntimeError));
+    assert_eq!(erro.localizacao.map(|l| l.linha), Some(1));
+}
+
+#[test]
+fn limites_configurados_pelo_programa() {
+    let mut interpreter = interpretador();
+    interpreter.set_limites(Limites { passos: Some(100), ..Limites::default() });
+    assert_eq!(interpreter.limites().passos, Some(100));
+
+    let erro = interpreter.eval("enquanto (verdadeiro) { }").unwrap_err();
+    assert!(erro.mensagem.starts_with(LIMITE_EXCEDIDO), "{}", erro.mensagem);
+
+    // O orçamento vale para cada execução
+    assert_eq!(interpreter.eval("1 + 1").unwrap().to_string_repr(), "2");
+}
+
+#[test]
+fn run_devolve_o_codigo_de_sair() {
+    let mut interpreter = interpretador();
+    assert_eq!(interpreter.run("imprimir(1);").unwrap(), 0);
+    assert_eq!(interpreter.run("sair(3); imprimir(2);").unwrap(), 3);
+    assert_eq!(interpreter.saida_mut().capturado(), "1\n");
+}
+
+#[test]
+fn intervalos_e_arquivos_do_script_podem_ser_examinados() {
+    let mut interpreter = interpretador();
+    let Value::Intervalo(intervalo) = interpreter.eval("1..=9").unwrap() else { panic!("esperava um intervalo") };
+    assert_eq!(*intervalo, Intervalo::novo(Some(1.0), Some(9.0), true, 1.0).unwrap());
+    assert_eq!(intervalo.tamanho(), Some(9));
+
+    let caminho = std::env::temp_dir().join(format!("lucas_embutir_{}.txt", std::process::id()));
+    std::fs::write(&caminho, "primeira\n").unwrap();
+    interpreter.set_global("caminho", Value::from(caminho.to_str().unwrap()));
+    let Value::Arquivo(arquivo) = interpreter.eval("abrir(caminho)").unwrap() else { panic!("esperava um arquivo") };
+    let arquivo: &RefCell<Arquivo> = &arquivo;
+    assert_eq!(arquivo.borrow_mut().ler_linha().unwrap().as_deref(), Some("primeira"));
+    arquivo.borrow_mut().fechar();
+    assert!(!arquivo.borrow().esta_aberto());
+    std::fs::remove_file(caminho).unwrap();
+}
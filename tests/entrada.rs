@@ -0,0 +1,40 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Roda `lucas -e codigo` com `entrada` na entrada padrão; devolve a saída padrão.
+fn com_entrada(codigo: &str, entrada: &str) -> String {
+    let mut filho = Command::new(env!("CARGO_BIN_EXE_lucas"))
+        .args(["-e", codigo])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    filho.stdin.take().unwrap().write_all(entrada.as_bytes()).unwrap();
+    let saida = filho.wait_with_output().unwrap();
+    String::from_utf8(saida.stdout).unwrap()
+}
+
+#[test]
+fn ler_mostra_o_prompt_e_devolve_a_linha() {
+    let codigo = "variavel nome = ler(\"Nome: \"); imprimir \"Olá, \" + nome + \"!\";";
+    assert_eq!(com_entrada(codigo, "Ana\n"), "Nome: Olá, Ana!\n");
+    // Fim da entrada sem quebra de linha, com \r do Windows, e entrada vazia
+    assert_eq!(com_entrada(codigo, "Bia"), "Nome: Olá, Bia!\n");
+    assert_eq!(com_entrada(codigo, "Caio\r\n"), "Nome: Olá, Caio!\n");
+    assert_eq!(com_entrada("imprimir ler();", ""), "nulo\n");
+}
+
+#[test]
+fn ler_numero_aceita_virgula_decimal() {
+    let codigo = "variavel a = ler_numero(\"a? \"); variavel b = ler_numero(\"b? \"); imprimir a + b;";
+    assert_eq!(com_entrada(codigo, "1,5\n 2 \n"), "a? b? 3.5\n");
+    let codigo = "tentar { ler_numero(); } capturar (e) { imprimir e.mensagem; }";
+    assert_eq!(com_entrada(codigo, "dez\n"), "ler_numero() esperava um número, mas recebeu 'dez'\n");
+}
+
+#[test]
+fn ler_linhas_consome_o_resto_da_entrada() {
+    let codigo = "variavel primeira = ler(); imprimir(primeira, ler_linhas());";
+    assert_eq!(com_entrada(codigo, "um\ndois\ntres\n"), "um [dois, tres]\n");
+}
@@ -0,0 +1,82 @@
+use lucas::{Interpreter, Saida};
+
+/// Saída de `codigo`, que precisa executar sem erros.
+fn saida(codigo: &str) -> String {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_saida(Saida::Buffer(Vec::new()));
+    interpreter.set_saida_erros(Saida::Buffer(Vec::new()));
+    if let Err(erro) = interpreter.eval(codigo) {
+        panic!("{}", erro);
+    }
+    interpreter.saida_mut().capturado()
+}
+
+#[test]
+fn erros_das_nativas_podem_ser_capturados() {
+    let codigo = r#"
+        tentar { imprimir 1 / 0; } capturar (e) { imprimir(e.tipo, e.mensagem, e.linha); }
+        tentar { imprimir [1, 2][5]; } capturar (e) { imprimir e.tipo; }
+        tentar { comprimento(1, 2); } capturar (erro) { imprimir erro.mensagem; }
+    "#;
+    assert_eq!(
+        saida(codigo),
+        "DivisaoPorZero Divisão por zero 2\nIndiceForaDosLimites\ncomprimento() espera 1 argumento\n"
+    );
+}
+
+#[test]
+fn lancar_valores_e_relancar() {
+    let codigo = r#"
+        tentar { lancar "falhou"; } capturar (e) { imprimir(e.tipo, e.mensagem, e.valor); }
+        tentar { lancar {"tipo": "Meu", "mensagem": "algo"}; } capturar (e) { imprimir(e.tipo, e.mensagem); }
+        tentar {
+            tentar { 1 / 0; } capturar (e) { lancar e; }
+        } capturar (externo) {
+            imprimir externo.tipo;
+        }
+    "#;
+    assert_eq!(saida(codigo), "Erro falhou falhou\nMeu algo\nDivisaoPorZero\n");
+}
+
+#[test]
+fn finalmente_roda_em_todos_os_caminhos() {
+    let codigo = r#"
+        funcao f(x) {
+            tentar {
+                se (x == 0) { retornar "zero"; }
+                lancar "x=" + x;
+            } capturar (e) {
+                retornar e.mensagem;
+            } finalmente {
+                imprimir "finalmente " + x;
+            }
+        }
+        imprimir f(0);
+        imprimir f(1);
+    "#;
+    assert_eq!(saida(codigo), "finalmente 0\nzero\nfinalmente 1\nx=1\n");
+}
+
+#[test]
+fn e_e_variavel_fora_da_posicao_de_operador() {
+    let codigo = r#"
+        variavel e = 2;
+        imprimir e;
+        imprimir e e verdadeiro;
+        imprimir falso e e;
+        funcao dobro(e) { retornar e * 2; }
+        imprimir dobro(e);
+        variavel a = verdadeiro;
+        variavel b = falso;
+        imprimir a e b;
+        imprimir e - 1;
+    "#;
+    assert_eq!(saida(codigo), "2\ntrue\nfalse\n4\nfalse\n1\n");
+}
+
+#[test]
+fn formatador_distingue_a_variavel_e_do_operador() {
+    assert_eq!(lucas::formatar("imprimir e-1;").unwrap(), "imprimir e - 1;\n");
+    assert_eq!(lucas::formatar("imprimir a e -1;").unwrap(), "imprimir a e -1;\n");
+    assert_eq!(lucas::formatar("imprimir e e -e;").unwrap(), "imprimir e e -e;\n");
+}
@@ -0,0 +1,113 @@
+use lucas::{formatar, ErrorType};
+use std::fs;
+use std::process::Command;
+
+const DESARRUMADO: &str = "// cabeçalho
+variavel d = {\"a\": 1, \"b\": [1,2]};  // dicionário
+funcao f(x){se(x>0){retornar -x;}senao{retornar x*-1;} // fim
+}
+
+
+imprimir f(-3) - -2;
+{ variavel y = 1; }
+";
+
+const FORMATADO: &str = "// cabeçalho
+variavel d = {\"a\": 1, \"b\": [1, 2]}; // dicionário
+funcao f(x) {
+    se (x > 0) {
+        retornar -x;
+    } senao {
+        retornar x * -1;
+    } // fim
+}
+
+imprimir f(-3) - -2;
+{
+    variavel y = 1;
+}
+";
+
+#[test]
+fn formata_no_estilo_padrao() {
+    assert_eq!(formatar(DESARRUMADO).unwrap(), FORMATADO);
+}
+
+#[test]
+fn formatar_de_novo_nao_muda_nada() {
+    for codigo in [DESARRUMADO, FORMATADO, "variavel x = -(1 + 2) * -3;\nimprimir(x, -x);\n"] {
+        let uma_vez = formatar(codigo).unwrap();
+        assert_eq!(formatar(&uma_vez).unwrap(), uma_vez);
+    }
+}
+
+#[test]
+fn comentarios_ficam_no_lugar() {
+    let codigo = "// antes\nvariavel x = 1; // depois de x\n\n// sozinho\nfuncao f() {\n// dentro\nretornar x;\n}\n";
+    assert_eq!(
+        formatar(codigo).unwrap(),
+        "// antes\nvariavel x = 1; // depois de x\n\n// sozinho\nfuncao f() {\n    // dentro\n    retornar x;\n}\n"
+    );
+}
+
+#[test]
+fn chave_de_dicionario_e_de_bloco() {
+    assert_eq!(formatar("variavel d = {\"a\": {\"b\": 1}};").unwrap(), "variavel d = {\"a\": {\"b\": 1}};\n");
+    assert_eq!(formatar("variavel d = {};").unwrap(), "variavel d = {};\n");
+    assert_eq!(formatar("{ imprimir {\"a\": 1}; }").unwrap(), "{\n    imprimir {\"a\": 1};\n}\n");
+    assert_eq!(
+        formatar("enquanto (falso) { variavel d = {\"k\": 1}; }").unwrap(),
+        "enquanto (falso) {\n    variavel d = {\"k\": 1};\n}\n"
+    );
+}
+
+#[test]
+fn menos_unario_e_binario() {
+    assert_eq!(formatar("x=a-1;").unwrap(), "x = a - 1;\n");
+    assert_eq!(formatar("x=-1;").unwrap(), "x = -1;\n");
+    assert_eq!(formatar("x=f(-1,-a)-[-2][0];").unwrap(), "x = f(-1, -a) - [-2][0];\n");
+    assert_eq!(formatar("x=a*-b;").unwrap(), "x = a * -b;\n");
+    assert_eq!(formatar("x=(a)-b;").unwrap(), "x = (a) - b;\n");
+    assert_eq!(formatar("x=-  -a;").unwrap(), "x = --a;\n");
+}
+
+#[test]
+fn crlf_vira_lf() {
+    let codigo = "variavel a=1;\r\n// c\r\nimprimir   a;\r\n";
+    assert_eq!(formatar(codigo).unwrap(), "variavel a = 1;\n// c\nimprimir a;\n");
+
+    // `fmt --verificar` aponta o arquivo com CRLF mesmo já estando no estilo padrão
+    let caminho = std::env::temp_dir().join(format!("lucas_crlf_{}.lucas", std::process::id()));
+    fs::write(&caminho, "variavel a = 1;\r\nimprimir a;\r\n").unwrap();
+    let saida = Command::new(env!("CARGO_BIN_EXE_lucas"))
+        .args(["fmt", "--verificar", caminho.to_str().unwrap()])
+        .output()
+        .unwrap();
+    assert_eq!(saida.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&saida.stdout).contains("precisa ser formatado"));
+
+    let saida = Command::new(env!("CARGO_BIN_EXE_lucas")).args(["fmt", caminho.to_str().unwrap()]).output().unwrap();
+    assert!(saida.status.success());
+    assert_eq!(fs::read_to_string(&caminho).unwrap(), "variavel a = 1;\nimprimir a;\n");
+    fs::remove_file(caminho).unwrap();
+}
+
+#[test]
+fn codigo_com_erro_e_recusado() {
+    assert!(matches!(formatar("variavel = ;").unwrap_err().tipo, ErrorType::SintaticoError));
+    assert!(matches!(formatar("imprimir \"sem fim").unwrap_err().tipo, ErrorType::LexicoError));
+}
+
+#[test]
+fn comandos_sem_ponto_e_virgula_ficam_em_linhas_separadas() {
+    assert_eq!(formatar("variavel a = 1 variavel b = 2").unwrap(), "variavel a = 1\nvariavel b = 2\n");
+    assert_eq!(
+        formatar("funcao f() { imprimir a imprimir b } variavel c = 1 { imprimir c }").unwrap(),
+        "funcao f() {\n    imprimir a\n    imprimir b\n}\nvariavel c = 1\n{\n    imprimir c\n}\n"
+    );
+    // O corpo sem chaves e o cabeçalho do `para` continuam na mesma linha
+    assert_eq!(
+        formatar("se (a) imprimir 1\npara (variavel i = 0; i < 2; i = i + 1) { imprimir i }").unwrap(),
+        "se (a) imprimir 1\npara (variavel i = 0; i < 2; i = i + 1) {\n    imprimir i\n}\n"
+    );
+}
@@ -0,0 +1,66 @@
+use lucas::{Interpreter, Saida};
+
+/// Resultado de `codigo` percorrendo a árvore e na VM, que precisam coincidir.
+fn executar(codigo: &str) -> Result<String, String> {
+    let resultados: Vec<Result<String, String>> = [false, true]
+        .into_iter()
+        .map(|usar_vm| {
+            let mut interpreter = Interpreter::new();
+            interpreter.set_saida(Saida::Buffer(Vec::new()));
+            interpreter.set_saida_erros(Saida::Buffer(Vec::new()));
+            interpreter.set_usar_vm(usar_vm);
+            match interpreter.eval(codigo) {
+                Ok(_) => Ok(interpreter.saida_mut().capturado()),
+                Err(erro) => Err(erro.mensagem),
+            }
+        })
+        .collect();
+    assert_eq!(resultados[0], resultados[1], "árvore e VM divergem em:\n{}", codigo);
+    resultados[0].clone()
+}
+
+#[test]
+fn indices_negativos_contam_do_fim() {
+    let codigo = r#"
+        variavel a = [1, 2, 3, 4, 5];
+        imprimir(a[-1], a[-5], "lucas"[-1]);
+        a[-1] = 50;
+        imprimir a;
+    "#;
+    assert_eq!(executar(codigo).unwrap(), "5 1 s\n[1, 2, 3, 4, 50]\n");
+}
+
+#[test]
+fn indices_invalidos_sao_erros() {
+    assert_eq!(
+        executar("[1, 2, 3][-4];").unwrap_err(),
+        "IndiceForaDosLimites: Índice -4 fora dos limites (tamanho: 3)"
+    );
+    assert_eq!(executar("[1, 2, 3][1.5];").unwrap_err(), "ErroDeTipo: Índice 1.5 não é inteiro");
+}
+
+#[test]
+fn fatias_de_listas_e_textos() {
+    let codigo = r#"
+        variavel a = [1, 2, 3, 4, 5];
+        imprimir(a[1..3], a[1..=3], a[..2], a[3..], a[..]);
+        imprimir(a[1..-1], a[-2..], a[4..2], a[0..100]);
+        imprimir(a[intervalo(0, 5, 2)]);
+        imprimir("lucas"[..2], "lucas"[-3..], "aéb"[1..2]);
+    "#;
+    assert_eq!(
+        executar(codigo).unwrap(),
+        "[2, 3] [2, 3, 4] [1, 2] [4, 5] [1, 2, 3, 4, 5]\n[2, 3, 4] [4, 5] [] [1, 2, 3, 4, 5]\n[1, 3, 5]\nlu cas é\n"
+    );
+}
+
+#[test]
+fn intervalos_sao_valores() {
+    let codigo = r#"
+        variavel r = 1..10;
+        imprimir(r, comprimento(r), 1..=3, intervalo(0, 10, 3));
+        para cada (x em 3..0) { imprimir "nunca"; }
+        para cada (x em intervalo(6, 0, -3)) { imprimir x; }
+    "#;
+    assert_eq!(executar(codigo).unwrap(), "1..10 9 1..=3 0..10 (passo 3)\n6\n3\n");
+}
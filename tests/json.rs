@@ -0,0 +1,84 @@
+use lucas::{Interpreter, Saida, Value};
+
+fn interpretador() -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_saida(Saida::Buffer(Vec::new()));
+    interpreter.set_saida_erros(Saida::Buffer(Vec::new()));
+    interpreter
+}
+
+/// `json_ler(texto)`: o valor lido ou a mensagem de erro.
+fn ler(texto: &str) -> Result<Value, String> {
+    let mut interpreter = interpretador();
+    interpreter.set_global("texto", Value::from(texto));
+    interpreter.eval("json_ler(texto)").map_err(|e| e.mensagem)
+}
+
+#[test]
+fn numeros_validos_segundo_a_rfc() {
+    for (texto, esperado) in [
+        ("0", 0.0),
+        ("-0", 0.0),
+        ("7", 7.0),
+        ("-12", -12.0),
+        ("10.25", 10.25),
+        ("0.5", 0.5),
+        ("1e3", 1000.0),
+        ("1E+2", 100.0),
+        ("-2.5e-1", -0.25),
+        ("0e0", 0.0),
+    ] {
+        match ler(texto) {
+            Ok(Value::Number(n)) => assert_eq!(n, esperado, "{}", texto),
+            outro => panic!("{}: {:?}", texto, outro),
+        }
+    }
+}
+
+#[test]
+fn numeros_fora_da_gramatica_sao_recusados() {
+    for texto in ["1.", "01", "-", "-01", ".5", "+1", "1.e3", "1e", "1e+", "--1", "1-2", "0x10", "1.2.3", "- 1"] {
+        let erro = ler(texto).expect_err(texto);
+        assert!(erro.contains("JSON inválido"), "{}: {}", texto, erro);
+    }
+    let erro = ler("[1, 02]").unwrap_err();
+    assert!(erro.contains("coluna 5: número inválido '02'"), "{}", erro);
+}
+
+#[test]
+fn ler_estruturas_e_escrever_de_volta() {
+    let mut interpreter = interpretador();
+    let codigo = r#"
+        variavel dados = json_ler("{\"nome\": \"Ana\", \"notas\": [9.5, 10, -1e2], \"ativo\": true, \"extra\": null, \"s\": \"\\u00e9\\n\"}");
+        imprimir(dados.nome, dados.notas[2], dados.ativo, dados.extra, comprimento(dados.s));
+        imprimir json_escrever(dados);
+        imprimir json_escrever([1, {"a": []}], verdadeiro);
+    "#;
+    interpreter.eval(codigo).unwrap();
+    assert_eq!(
+        interpreter.saida_mut().capturado(),
+        "Ana -100 true nulo 2\n\
+         {\"ativo\":true,\"extra\":null,\"nome\":\"Ana\",\"notas\":[9.5,10,-100],\"s\":\"é\\n\"}\n\
+         [\n  1,\n  {\n    \"a\": []\n  }\n]\n"
+    );
+}
+
+#[test]
+fn erros_de_sintaxe_e_valores_sem_json() {
+    assert!(ler("{\"a\": 1,}").is_err());
+    assert!(ler("[1, 2").is_err());
+    assert!(ler("nulo").unwrap_err().contains("valor desconhecido 'nulo'"));
+
+    let mut interpreter = interpretador();
+    let erro = interpreter.eval("variavel a = [1]; a[0] = a; json_escrever(a)").unwrap_err();
+    assert!(erro.mensagem.contains("array cíclico"), "{}", erro.mensagem);
+}
+
+#[test]
+fn aninhamento_fundo_demais_e_recusado() {
+    let no_limite = format!("{}{}", "[".repeat(512), "]".repeat(512));
+    assert!(ler(&no_limite).is_ok());
+    let fundo = format!("{}{}", "[".repeat(513), "]".repeat(513));
+    assert!(ler(&fundo).unwrap_err().contains("mais de 512 arrays e objetos aninhados"));
+    assert!(ler(&"[{\"a\":".repeat(200_000)).unwrap_err().contains("aninhados"));
+}
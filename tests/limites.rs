@@ -0,0 +1,167 @@
+use lucas::{Interpreter, Limites, Saida, LIMITE_EXCEDIDO};
+use std::fs;
+use std::path::PathBuf;
+
+fn interpretador(limites: Limites, usar_vm: bool) -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_saida(Saida::Buffer(Vec::new()));
+    interpreter.set_saida_erros(Saida::Buffer(Vec::new()));
+    interpreter.set_limites(limites);
+    interpreter.set_usar_vm(usar_vm);
+    interpreter
+}
+
+fn sem_acesso() -> Limites {
+    Limites { acesso_sistema: false, ..Limites::default() }
+}
+
+/// Arquivo temporário com um conteúdo que não pode vazar para o script.
+fn arquivo_secreto(nome: &str) -> PathBuf {
+    let caminho = std::env::temp_dir().join(format!("lucas_{}_{}.lucas", nome, std::process::id()));
+    fs::write(&caminho, "SEGREDO-DO-SERVIDOR nao e codigo lucas\n").unwrap();
+    caminho
+}
+
+#[test]
+fn importar_sem_acesso_ao_sistema_nao_le_o_arquivo() {
+    let caminho = arquivo_secreto("importar");
+    for usar_vm in [false, true] {
+        for codigo in [
+            format!("importar \"{}\" como m;", caminho.display()),
+            format!("de \"{}\" importar x;", caminho.display()),
+        ] {
+            let mut interpreter = interpretador(sem_acesso(), usar_vm);
+            let erro = interpreter.eval(&codigo).unwrap_err();
+            assert!(erro.mensagem.starts_with(LIMITE_EXCEDIDO), "{}", erro.mensagem);
+            assert!(!erro.to_string().contains("SEGREDO"), "{}", erro);
+            assert!(!interpreter.saida_erros_mut().capturado().contains("SEGREDO"));
+        }
+    }
+
+    // Com acesso, o mesmo arquivo é lido (e recusado por não ser código)
+    let mut interpreter = interpretador(Limites::default(), false);
+    let erro = interpreter.eval(&format!("importar \"{}\" como m;", caminho.display())).unwrap_err();
+    assert!(!erro.mensagem.starts_with(LIMITE_EXCEDIDO), "{}", erro.mensagem);
+    fs::remove_file(caminho).unwrap();
+}
+
+#[test]
+fn importar_sem_acesso_nao_pode_ser_capturado_quando_fatal() {
+    let caminho = arquivo_secreto("fatal");
+    let limites = Limites { fatais: true, ..sem_acesso() };
+    let codigo = format!(
+        "tentar {{ importar \"{}\" como m; }} capturar (e) {{ imprimir(\"capturado\"); }}",
+        caminho.display()
+    );
+    let mut interpreter = interpretador(limites, false);
+    assert!(interpreter.eval(&codigo).is_err());
+    assert_eq!(interpreter.saida_mut().capturado(), "");
+    fs::remove_file(caminho).unwrap();
+}
+
+#[test]
+fn nativas_do_sistema_desativadas() {
+    let caminho = arquivo_secreto("nativas");
+    for usar_vm in [false, true] {
+        let mut interpreter = interpretador(sem_acesso(), usar_vm);
+        let erro = interpreter.eval(&format!("ler_arquivo(\"{}\")", caminho.display())).unwrap_err();
+        assert!(erro.mensagem.contains("desativada"), "{}", erro.mensagem);
+        assert!(!erro.to_string().contains("SEGREDO"));
+
+        let erro = interpreter.eval("sair(0)").unwrap_err();
+        assert!(erro.mensagem.contains("desativada"), "{}", erro.mensagem);
+    }
+    fs::remove_file(caminho).unwrap();
+}
+
+#[test]
+fn dicionario_lancado_nao_se_passa_por_sair() {
+    for usar_vm in [false, true] {
+        let mut interpreter = interpretador(sem_acesso(), usar_vm);
+        assert!(interpreter.run("lancar {\"tipo\": \"Sair\", \"valor\": 5};").is_err());
+
+        let limites = Limites { fatais: true, ..sem_acesso() };
+        let mut interpreter = interpretador(limites, usar_vm);
+        let codigo = "tentar { lancar {\"tipo\": \"Sair\", \"valor\": 5}; } capturar (e) { imprimir e[\"tipo\"]; }
+            tentar { lancar {\"tipo\": \"LimiteExcedido\"}; } capturar (e) { imprimir e[\"tipo\"]; }
+            tentar { lancar {\"tipo\": \"Meu\"}; } capturar (e) { imprimir e[\"tipo\"]; }";
+        assert_eq!(interpreter.run(codigo).unwrap(), 0);
+        assert_eq!(interpreter.saida_mut().capturado(), "Erro\nErro\nMeu\n");
+    }
+}
+
+#[test]
+fn passos_e_tamanhos() {
+    for usar_vm in [false, true] {
+        let limites = Limites { passos: Some(1000), ..Limites::default() };
+        let mut interpreter = interpretador(limites, usar_vm);
+        let erro = interpreter.eval("variavel i = 0; enquanto (verdadeiro) { i = i + 1; }").unwrap_err();
+        assert!(erro.mensagem.contains("passos"), "{}", erro.mensagem);
+
+        let limites = Limites { tamanho_colecao: Some(10), tamanho_texto: Some(8), ..Limites::default() };
+        let mut interpreter = interpretador(limites, usar_vm);
+        let erro = interpreter.eval("variavel d = {}; para (variavel i = 0; i < 20; i = i + 1) { d[\"k\" + i] = i; }");
+        assert!(erro.unwrap_err().mensagem.starts_with(LIMITE_EXCEDIDO));
+        let erro = interpreter.eval("\"abcde\" + \"fghij\"").unwrap_err();
+        assert!(erro.mensagem.contains("bytes"), "{}", erro.mensagem);
+
+        let limites = Limites { bytes_saida: Some(4), ..Limites::default() };
+        let mut interpreter = interpretador(limites, usar_vm);
+        let erro = interpreter.eval("imprimir(\"ab\"); imprimir(\"cd\");").unwrap_err();
+        assert!(erro.mensagem.contains("saída"), "{}", erro.mensagem);
+        assert_eq!(interpreter.saida_mut().capturado(), "ab\n");
+    }
+}
+
+#[test]
+fn tempo_limite_interrompe_laco_infinito() {
+    let limites = Limites { tempo: Some(std::time::Duration::from_millis(50)), ..Limites::default() };
+    let mut interpreter = interpretador(limites, false);
+    let erro = interpreter.eval("enquanto (verdadeiro) { }").unwrap_err();
+    assert!(erro.mensagem.contains("Tempo limite"), "{}", erro.mensagem);
+}
+
+/// Array com 2^40 folhas que ocupa só 40 arrays na memória.
+const ARRAY_REPETIDO: &str = "variavel a = [1, 2, 3]; para (variavel i = 0; i < 40; i = i + 1) { a = [a, a]; }";
+
+#[test]
+fn texto_enorme_e_recusado_enquanto_e_montado() {
+    for usar_vm in [false, true] {
+        let limites = Limites {
+            tempo: Some(std::time::Duration::from_secs(2)),
+            tamanho_texto: Some(10000),
+            ..Limites::default()
+        };
+        for uso in ["json_escrever(a);", "imprimir a;", "imprimir(a, 1);", "variavel t = \"\" + a;", "lancar a;"] {
+            let mut interpreter = interpretador(limites.clone(), usar_vm);
+            let inicio = std::time::Instant::now();
+            let erro = interpreter.eval(&format!("{} {}", ARRAY_REPETIDO, uso)).unwrap_err();
+            assert!(erro.mensagem.contains("Texto com mais de 10000 bytes"), "{}: {}", uso, erro.mensagem);
+            assert!(inicio.elapsed().as_secs_f64() < 1.0, "{}", uso);
+            assert_eq!(interpreter.saida_mut().capturado(), "");
+        }
+
+        // Só com o tempo limitado, o relógio é consultado durante a escrita
+        let limites = Limites { tempo: Some(std::time::Duration::from_millis(100)), ..Limites::default() };
+        for uso in ["json_escrever(a);", "imprimir a;"] {
+            let mut interpreter = interpretador(limites.clone(), usar_vm);
+            let erro = interpreter.eval(&format!("{} {}", ARRAY_REPETIDO, uso)).unwrap_err();
+            assert!(erro.mensagem.contains("Tempo limite"), "{}: {}", uso, erro.mensagem);
+        }
+    }
+}
+
+#[test]
+fn valores_ciclicos_sao_escritos_sem_estourar_a_pilha() {
+    for usar_vm in [false, true] {
+        let mut interpreter = interpretador(Limites::default(), usar_vm);
+        let codigo = "variavel a = [1]; a[0] = a; imprimir a;
+            variavel d = {\"n\": 1}; d[\"eu\"] = d; d[\"lista\"] = [d];
+            tentar { lancar d; } capturar (e) { imprimir e[\"mensagem\"]; }";
+        interpreter.eval(codigo).unwrap();
+        assert_eq!(
+            interpreter.saida_mut().capturado(),
+            "[[...]]\n{eu: {...}, lista: [{...}], n: 1}\n"
+        );
+    }
+}
@@ -0,0 +1,92 @@
+use lucas::{Interpreter, Saida};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn diretorio_temporario(nome: &str) -> PathBuf {
+    let diretorio = std::env::temp_dir().join(format!("lucas_modulos_{}_{}", nome, std::process::id()));
+    fs::create_dir_all(diretorio.join("sub")).unwrap();
+    diretorio
+}
+
+/// Saída de `codigo` executado como se estivesse no arquivo `caminho`.
+fn executar_em(caminho: &Path, codigo: &str) -> Result<String, String> {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_saida(Saida::Buffer(Vec::new()));
+    interpreter.set_saida_erros(Saida::Buffer(Vec::new()));
+    interpreter.set_caminho(Some(caminho.to_path_buf()));
+    match interpreter.eval(codigo) {
+        Ok(_) => Ok(interpreter.saida_mut().capturado()),
+        Err(erro) => Err(erro.mensagem),
+    }
+}
+
+#[test]
+fn caminhos_relativos_ao_arquivo_que_importa() {
+    let diretorio = diretorio_temporario("relativos");
+    fs::write(diretorio.join("sub/util.lucas"), "importar \"auxiliar\" como a;\nexportar funcao dobro(x) { retornar a.vezes(x, 2); }\n").unwrap();
+    fs::write(diretorio.join("sub/auxiliar.lucas"), "exportar funcao vezes(x, y) { retornar x * y; }\n").unwrap();
+
+    let codigo = "importar \"sub/util.lucas\" como u;\nimprimir u.dobro(21);";
+    assert_eq!(executar_em(&diretorio.join("principal.lucas"), codigo).unwrap(), "42\n");
+
+    // `auxiliar` não existe ao lado do programa principal
+    let erro = executar_em(&diretorio.join("principal.lucas"), "importar \"auxiliar\" como a;").unwrap_err();
+    assert!(erro.starts_with("ModuloNaoEncontrado"), "{}", erro);
+    fs::remove_dir_all(diretorio).unwrap();
+}
+
+#[test]
+fn modulo_executa_uma_vez_e_so_expoe_o_exportado() {
+    let diretorio = diretorio_temporario("cache");
+    fs::write(
+        diretorio.join("contador.lucas"),
+        "imprimir \"carregando\";\nvariavel interno = 1;\nexportar variavel total = interno + 1;\n",
+    )
+    .unwrap();
+
+    let codigo = r#"
+        importar "contador" como a;
+        importar "contador.lucas" como b;
+        de "contador" importar total;
+        imprimir(a.total, b.total, total, chaves(a));
+        a.total = 10;
+        importar "contador" como c;
+        imprimir(a.total, b.total, c.total);
+    "#;
+    let principal = diretorio.join("principal.lucas");
+    // Cada importação tem o seu dicionário: mudar `a` não muda os outros
+    assert_eq!(executar_em(&principal, codigo).unwrap(), "carregando\n2 2 2 [total]\n10 2 2\n");
+
+    let erro = executar_em(&principal, "de \"contador\" importar interno;").unwrap_err();
+    assert!(erro.contains("não exporta 'interno'"), "{}", erro);
+    fs::remove_dir_all(diretorio).unwrap();
+}
+
+#[test]
+fn lucas_path_e_buscado_depois_do_diretorio_do_arquivo() {
+    let diretorio = diretorio_temporario("lucas_path");
+    let biblioteca = diretorio.join("sub");
+    fs::write(biblioteca.join("saudacao.lucas"), "exportar variavel ola = \"da biblioteca\";\n").unwrap();
+    let principal = diretorio.join("principal.lucas");
+    fs::write(&principal, "de \"saudacao\" importar ola;\nimprimir ola;\n").unwrap();
+
+    let saida = Command::new(env!("CARGO_BIN_EXE_lucas")).arg(&principal).env("LUCAS_PATH", &biblioteca).output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&saida.stdout), "da biblioteca\n");
+
+    let saida = Command::new(env!("CARGO_BIN_EXE_lucas")).arg(&principal).env_remove("LUCAS_PATH").output().unwrap();
+    assert_eq!(saida.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&saida.stderr).contains("saudacao"));
+    fs::remove_dir_all(diretorio).unwrap();
+}
+
+#[test]
+fn importacao_circular_comeca_no_arquivo_principal() {
+    let diretorio = diretorio_temporario("circular");
+    fs::write(diretorio.join("a.lucas"), "importar \"principal\";\n").unwrap();
+    let principal = diretorio.join("principal.lucas");
+    fs::write(&principal, "importar \"a\";\n").unwrap();
+    let erro = executar_em(&principal.canonicalize().unwrap(), "importar \"a\";").unwrap_err();
+    assert!(erro.ends_with("Importação circular: principal.lucas → a.lucas → principal.lucas"), "{}", erro);
+    fs::remove_dir_all(diretorio).unwrap();
+}
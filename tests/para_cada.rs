@@ -0,0 +1,81 @@
+use lucas::{Interpreter, Saida};
+
+/// Resultado de `codigo` percorrendo a árvore e na VM, que precisam coincidir.
+fn executar(codigo: &str) -> Result<String, String> {
+    let resultados: Vec<Result<String, String>> = [false, true]
+        .into_iter()
+        .map(|usar_vm| {
+            let mut interpreter = Interpreter::new();
+            interpreter.set_saida(Saida::Buffer(Vec::new()));
+            interpreter.set_saida_erros(Saida::Buffer(Vec::new()));
+            interpreter.set_usar_vm(usar_vm);
+            match interpreter.eval(codigo) {
+                Ok(_) => Ok(interpreter.saida_mut().capturado()),
+                Err(erro) => Err(erro.mensagem),
+            }
+        })
+        .collect();
+    assert_eq!(resultados[0], resultados[1], "árvore e VM divergem em:\n{}", codigo);
+    resultados[0].clone()
+}
+
+#[test]
+fn percorre_listas_textos_intervalos_e_dicionarios() {
+    let codigo = r#"
+        para cada (x em [1, "dois", nulo]) { imprimir x; }
+        para cada (c em "aé") { imprimir c; }
+        para cada (i em 3..5) { imprimir i; }
+        para cada (i em 3..=5) { imprimir i; }
+        para cada (chave em {"b": 2, "a": 1}) { imprimir chave; }
+        para cada (x em []) { imprimir "nunca"; }
+    "#;
+    assert_eq!(executar(codigo).unwrap(), "1\ndois\nnulo\na\né\n3\n4\n3\n4\n5\na\nb\n");
+}
+
+#[test]
+fn forma_com_indice() {
+    let codigo = r#"
+        para cada (i, x em [10, 20]) { imprimir(i, x); }
+        para cada (i, c em "oi") { imprimir(i, c); }
+        para cada (chave, valor em {"a": 1, "b": 2}) { imprimir(chave, valor); }
+        para (i, x em [7]) { imprimir(i, x); }
+    "#;
+    assert_eq!(executar(codigo).unwrap(), "0 10\n1 20\n0 o\n1 i\na 1\nb 2\n0 7\n");
+}
+
+#[test]
+fn parar_e_continuar() {
+    let codigo = r#"
+        para cada (x em 1..=10) {
+            se (x == 2) { continuar; }
+            se (x > 4) { parar; }
+            imprimir x;
+        }
+        para cada (linha em [[1, 2], [3, 4]]) {
+            para cada (x em linha) {
+                se (x == 2) { parar; }
+                imprimir x;
+            }
+        }
+    "#;
+    assert_eq!(executar(codigo).unwrap(), "1\n3\n4\n1\n3\n4\n");
+}
+
+#[test]
+fn variavel_do_laco_e_local_a_cada_volta() {
+    let codigo = r#"
+        variavel funcoes = {};
+        para cada (x em ["a", "b"]) {
+            funcao f() { retornar x; }
+            funcoes[x] = f;
+        }
+        imprimir(funcoes["a"](), funcoes["b"]());
+    "#;
+    assert_eq!(executar(codigo).unwrap(), "a b\n");
+}
+
+#[test]
+fn valor_que_nao_e_iteravel() {
+    let erro = executar("para cada (x em 5) { }").unwrap_err();
+    assert_eq!(erro, "ErroDeTipo: Não é possível iterar sobre '5'");
+}
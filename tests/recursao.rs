@@ -0,0 +1,98 @@
+use lucas::{Interpreter, Saida};
+use std::thread;
+
+/// Pilha bem menor que a de uma thread comum, onde mil quadros de chamada
+/// Lucas não caberiam.
+const PILHA_PEQUENA: usize = 256 * 1024;
+
+/// Roda `codigo` numa thread com `PILHA_PEQUENA` e devolve a mensagem de erro.
+fn erro_em_pilha_pequena(codigo: &'static str, usar_vm: bool, profundidade: Option<usize>) -> Option<String> {
+    thread::Builder::new()
+        .stack_size(PILHA_PEQUENA)
+        .spawn(move || {
+            let mut interpreter = Interpreter::new();
+            interpreter.set_saida(Saida::Buffer(Vec::new()));
+            interpreter.set_saida_erros(Saida::Buffer(Vec::new()));
+            interpreter.set_usar_vm(usar_vm);
+            if let Some(profundidade) = profundidade {
+                interpreter.set_profundidade_maxima(profundidade);
+            }
+            interpreter.eval(codigo).err().map(|e| e.mensagem)
+        })
+        .unwrap()
+        .join()
+        .expect("o interpretador estourou a pilha da thread")
+}
+
+#[test]
+fn recursao_infinita_em_pilha_pequena() {
+    let codigo = "funcao f(n) { retornar f(n + 1) + 1; } f(0)";
+    for usar_vm in [false, true] {
+        let erro = erro_em_pilha_pequena(codigo, usar_vm, None).expect("recursão infinita deveria falhar");
+        assert!(erro.contains("recursão máxima excedida (limite de 1000 chamadas)"), "{}", erro);
+    }
+}
+
+#[test]
+fn recursao_profunda_alem_do_padrao() {
+    let codigo = "funcao soma(n) { se (n == 0) { retornar 0; } retornar n + soma(n - 1); } soma(20000)";
+    assert_eq!(erro_em_pilha_pequena(codigo, false, Some(30_000)), None);
+    let erro = erro_em_pilha_pequena(codigo, false, Some(10_000)).unwrap();
+    assert!(erro.contains("limite de 10000 chamadas"), "{}", erro);
+}
+
+
+#[test]
+fn rastro_lista_as_chamadas_em_andamento() {
+    let codigo = "funcao soma(a, b) {\n  retornar a + b.x;\n}\nfuncao media(l) {\n  retornar soma(l, 1) / 2;\n}\nmedia(1);";
+    for usar_vm in [false, true] {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_saida_erros(Saida::Buffer(Vec::new()));
+        interpreter.set_usar_vm(usar_vm);
+        let erro = interpreter.eval(codigo).unwrap_err();
+        assert_eq!(erro.localizacao.as_ref().map(|l| l.linha), Some(2));
+        assert_eq!(erro.rastro, ["em <principal> (linha 7)", "em media (linha 5)", "em soma (linha 2)"]);
+        let mut exibido = Vec::new();
+        erro.exibir_em(&mut exibido).unwrap();
+        let exibido = String::from_utf8_lossy(&exibido);
+        assert!(exibido.contains("Rastreamento (chamada mais recente por último):"), "{}", exibido);
+    }
+}
+
+#[test]
+fn rastro_de_funcao_chamada_pelo_programa_hospedeiro() {
+    for usar_vm in [false, true] {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_usar_vm(usar_vm);
+        interpreter.eval("funcao f() {\n  retornar g() + 1;\n}\nfuncao g() {\n  retornar nulo.x;\n}").unwrap();
+        let erro = interpreter.call_function("f", Vec::new()).unwrap_err();
+        assert_eq!(erro.rastro, ["em <principal>", "em f (linha 2)", "em g (linha 5)"], "vm: {}", usar_vm);
+    }
+}
+
+#[test]
+fn milhao_de_chamadas_finais_em_pilha_pequena() {
+    let codigo = "
+        funcao contar(n) { se (n > 0) retornar contar(n - 1); senao retornar \"fim\"; }
+        funcao par(n) { se (n == 0) { retornar verdadeiro; } retornar impar(n - 1); }
+        funcao impar(n) { se (n == 0) { retornar falso; } retornar par(n - 1); }
+        se (contar(1000000) != \"fim\" ou nao par(100000)) { lancar \"errado\"; }
+    ";
+    for usar_vm in [false, true] {
+        assert_eq!(erro_em_pilha_pequena(codigo, usar_vm, None), None);
+    }
+}
+
+#[test]
+fn chamada_final_dentro_de_tentar_continua_capturada() {
+    let codigo = "
+        funcao falha(n) { se (n == 0) { lancar \"no fundo\"; } retornar falha(n - 1); }
+        funcao protegida() {
+            tentar { retornar falha(5000); } capturar (e) { retornar e.mensagem; }
+        }
+        se (protegida() != \"no fundo\") { lancar \"não capturado\"; }
+    ";
+    for usar_vm in [false, true] {
+        assert_eq!(erro_em_pilha_pequena(codigo, usar_vm, None), None);
+    }
+}
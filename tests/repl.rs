@@ -0,0 +1,153 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+fn diretorio_temporario(nome: &str) -> PathBuf {
+    let diretorio = std::env::temp_dir().join(format!("lucas_repl_{}_{}", nome, std::process::id()));
+    fs::create_dir_all(&diretorio).unwrap();
+    diretorio
+}
+
+/// Roda o REPL em `diretorio` com `entrada` no stdin; devolve (stdout, stderr).
+fn repl(diretorio: &Path, args: &[&str], entrada: &str) -> (String, String) {
+    let mut processo = Command::new(env!("CARGO_BIN_EXE_lucas"))
+        .args(args)
+        .current_dir(diretorio)
+        .env("HOME", diretorio)
+        .env("NO_COLOR", "1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    processo.stdin.take().unwrap().write_all(entrada.as_bytes()).unwrap();
+    let saida = processo.wait_with_output().unwrap();
+    (String::from_utf8_lossy(&saida.stdout).into_owned(), String::from_utf8_lossy(&saida.stderr).into_owned())
+}
+
+/// O que o REPL mostrou entre a mensagem de boas-vindas e a de despedida.
+#[cfg(feature = "repl")]
+fn ecos(saida: &str) -> &str {
+    let inicio = saida.find("para encerrar\n\n").expect("boas-vindas") + "para encerrar\n\n".len();
+    let fim = saida.rfind("\nVolte logo").expect("despedida");
+    &saida[inicio..fim]
+}
+
+#[test]
+#[cfg(feature = "repl")]
+fn salvar_inclui_arquivos_carregados() {
+    let diretorio = diretorio_temporario("salvar");
+    fs::write(diretorio.join("base.lucas"), "funcao dobro(x) { retornar x * 2; }\n").unwrap();
+    fs::write(diretorio.join("extra.lucas"), "variavel extra = 5;\n").unwrap();
+
+    let entrada = ":carregar extra.lucas\nvariavel y = dobro(extra);\nnao_existe\n\
+                   variavel y = y + 1;\nfuncao dobro(x) { retornar x * 20; }\ndobro(y)\n_ + 1\nimprimir(_, y)\n\
+                   :salvar sessao.lucas\n";
+    let (saida, _) = repl(&diretorio, &["-i", "base.lucas"], entrada);
+    assert!(saida.contains("Sessão salva em 'sessao.lucas' (8 comandos)"), "{}", saida);
+
+    // Redeclarações viram atribuições e o valor mostrado vai para `_`
+    let sessao = fs::read_to_string(diretorio.join("sessao.lucas")).unwrap();
+    assert_eq!(
+        sessao,
+        "// :carregar base.lucas\nfuncao dobro(x) { retornar x * 2; }\n\
+         // :carregar extra.lucas\nvariavel extra = 5;\n\
+         variavel y = dobro(extra);\ny = y + 1;\n\
+         funcao dobro_2(x) { retornar x * 20; }\ndobro = dobro_2;\n\
+         variavel _ = dobro(y)\n_ = _ + 1\nimprimir(_, y)\n"
+    );
+
+    // A sessão salva roda sozinha, como um script qualquer
+    let saida = Command::new(env!("CARGO_BIN_EXE_lucas")).args(["executar", "sessao.lucas"]).current_dir(&diretorio).output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&saida.stderr), "");
+    assert_eq!(String::from_utf8_lossy(&saida.stdout), "221 11\n");
+    fs::remove_dir_all(diretorio).unwrap();
+}
+
+#[test]
+#[cfg(not(feature = "repl"))]
+fn sem_a_feature_repl_o_executavel_recusa_o_repl() {
+    let diretorio = diretorio_temporario("sem_repl");
+    let (saida, erros) = repl(&diretorio, &[], "imprimir(1)\n");
+    assert_eq!(saida, "");
+    assert!(erros.contains("sem o REPL"), "{}", erros);
+    fs::remove_dir_all(diretorio).unwrap();
+}
+
+#[test]
+#[cfg(feature = "repl")]
+fn comandos_em_varias_linhas() {
+    let diretorio = diretorio_temporario("varias_linhas");
+    let entrada = "funcao soma(a, b) {\n  retornar a +\n    b;\n}\nvariavel d = {\n  \"x\": [1,\n        2]\n};\nsoma(2, 3)\nd\nimprimir(\"incompleto\",\n";
+    let (saida, erros) = repl(&diretorio, &[], entrada);
+    assert!(saida.contains("\n5\n{x: [1, 2]}\n"), "{}", saida);
+    // O comando que ficou pela metade no fim da entrada é descartado
+    assert!(!saida.contains("incompleto"), "{}", saida);
+    assert_eq!(erros, "");
+    fs::remove_dir_all(diretorio).unwrap();
+}
+
+#[test]
+#[cfg(feature = "repl")]
+fn mostra_o_valor_so_de_expressoes() {
+    let diretorio = diretorio_temporario("eco");
+    let entrada = "variavel senha = 1;\nsenha = 2\nsenha + 1\n_ * 10\n\"texto\"\n\
+                   se (verdadeiro) { 7; }\nfuncao f() { retornar 3; }\nf()\nimprimir(\"dito\")\nsegundo = 1\n";
+    let (saida, erros) = repl(&diretorio, &[], entrada);
+    assert_eq!(ecos(&saida), "3\n30\n\"texto\"\n3\ndito\n");
+    // `segundo` não é confundido com `se`: é só uma variável indefinida
+    assert!(erros.contains("Variável 'segundo' não definida"), "{}", erros);
+    fs::remove_dir_all(diretorio).unwrap();
+}
+
+#[test]
+#[cfg(feature = "repl")]
+fn comandos_de_inspecao() {
+    let diretorio = diretorio_temporario("inspecao");
+    let entrada = ":tokens x + 1\n:tipo [1]\n:tipo 1..2\n:ajuda comprimento\n\
+                   // Soma dois números\nfuncao soma(a, b) { retornar a + b; }\n:ajuda soma\n:ajuda nada\n\
+                   :tempo soma(1, 2)\n:desconhecido\n:tipo\n";
+    let (saida, _) = repl(&diretorio, &[], entrada);
+    let ecos = ecos(&saida);
+    let (antes, depois) = ecos.split_once("Tempo: ").expect("tempo");
+    assert_eq!(
+        antes,
+        "    1:1   Ident(\"x\")\n    1:3   Mais\n    1:5   Numero(1.0)\n    1:6   Fim\narray\nintervalo\n\
+         comprimento(x) (nativa)\n  Número de caracteres de um texto ou de elementos de um array, dicionário ou intervalo\n\
+         soma(a, b)\n  Soma dois números\n'nada' não é uma função conhecida\n3\n"
+    );
+    assert!(depois.ends_with(" ms\nComando desconhecido ':desconhecido'. Digite :ajuda para ver os comandos.\nUso: :tipo <código>\n"), "{}", depois);
+    fs::remove_dir_all(diretorio).unwrap();
+}
+
+#[test]
+fn listagens_de_tokens_e_da_arvore() {
+    let mut tokens = Vec::new();
+    lucas::print_tokens("imprimir \"oi\";", &mut tokens).unwrap();
+    assert_eq!(String::from_utf8(tokens).unwrap(), "    1:1   Imprimir\n    1:10  Texto(\"oi\")\n    1:14  PontoVirgula\n    1:15  Fim\n");
+
+    let mut arvore = Vec::new();
+    lucas::print_ast("imprimir 1;", &mut arvore).unwrap();
+    assert_eq!(String::from_utf8(arvore).unwrap(), "Imprimir(\n    Numero(\n        1.0,\n    ),\n    1,\n)\n");
+}
+
+#[test]
+#[cfg(feature = "repl")]
+fn lista_de_variaveis() {
+    let diretorio = diretorio_temporario("variaveis");
+    let entrada = "variavel numeros = [1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19,20];\n\
+                   funcao soma(a, b) { retornar a + b; }\nconstante NOME = \"lucas\";\n\
+                   :variaveis\n:variaveis so\n:variaveis --todas compr\n:variaveis xyz\n";
+    let (saida, _) = repl(&diretorio, &[], entrada);
+    assert_eq!(
+        ecos(&saida),
+        "\nVariáveis Globais:\n  Nome     Tipo    Valor\n  NOME     texto   \"lucas\"\n\
+         \x20 numeros  array   [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 1…\n\
+         \x20 soma     função  soma(a, b)\n\n\
+         \nVariáveis Globais:\n  Nome  Tipo    Valor\n  soma  função  soma(a, b)\n\n\
+         \nVariáveis Globais:\n  Nome         Tipo    Valor\n  comprimento  função  comprimento(x)\n\n\
+         \nVariáveis Globais:\n  (nenhuma variável definida)\n\n"
+    );
+    fs::remove_dir_all(diretorio).unwrap();
+}
@@ -0,0 +1,110 @@
+use lucas::{ErrorType, Interpreter, Saida};
+
+fn interpretador(usar_vm: bool) -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_saida(Saida::Buffer(Vec::new()));
+    interpreter.set_saida_erros(Saida::Buffer(Vec::new()));
+    interpreter.set_usar_vm(usar_vm);
+    interpreter
+}
+
+#[test]
+fn nome_de_escopo_irmao_nao_e_visivel() {
+    for codigo in [
+        "funcao f() { { variavel a = 1; imprimir a; } retornar a; }",
+        "{ variavel a = 1; imprimir a; } imprimir a;",
+        "funcao f() { variavel a = 1; retornar a; } funcao g() { retornar a; }",
+    ] {
+        let mut interpreter = interpretador(false);
+        let erro = interpreter.eval(codigo).unwrap_err();
+        assert!(matches!(erro.tipo, ErrorType::SemanticoError), "{}", codigo);
+        assert_eq!(erro.mensagem, "Variável 'a' não definida");
+        assert!(erro.sugestao.unwrap().contains("não é visível"));
+        assert_eq!(interpreter.saida_mut().capturado(), "", "{}", codigo);
+    }
+}
+
+#[test]
+fn nome_declarado_depois_num_escopo_externo() {
+    let bloco = "{ funcao g() { retornar y + z; } variavel y = 2; r = g(); }";
+    for usar_vm in [false, true] {
+        let mut interpreter = interpretador(usar_vm);
+        let valor = interpreter.eval(&format!("variavel z = 40; variavel r = nulo; {} r", bloco)).unwrap();
+        assert_eq!(valor.to_string_repr(), "42");
+
+        // `z` é aceita pelo resolver, mas ainda não existe quando `g` é chamada
+        let mut interpreter = interpretador(usar_vm);
+        let erro = interpreter.eval(&format!("variavel r = nulo; {} variavel z = 40;", bloco)).unwrap_err();
+        assert!(matches!(erro.tipo, ErrorType::RuntimeError));
+        assert!(erro.mensagem.contains("'z'"), "{}", erro.mensagem);
+    }
+}
+
+#[test]
+fn variaveis_locais_por_posicao() {
+    let codigo = "
+        funcao contador() {
+            variavel n = 0;
+            funcao inc() { n = n + 1; retornar n; }
+            retornar inc;
+        }
+        funcao soma(lista) {
+            variavel total = 0;
+            para cada (i, x em lista) { variavel dobro = x * 2; total = total + dobro + i; }
+            tentar { lancar \"x\"; } capturar (e) { total = total + 1; } finalmente { total = total + 1; }
+            retornar total;
+        }
+        variavel c = contador(); c();
+        [c(), soma([1, 2, 3])]
+    ";
+    for usar_vm in [false, true] {
+        let mut interpreter = interpretador(usar_vm);
+        assert_eq!(interpreter.eval(codigo).unwrap().to_string_repr(), "[2, 17]");
+    }
+}
+
+#[test]
+fn avisos_de_variaveis_nao_usadas_e_escondidas() {
+    let mut interpreter = interpretador(false);
+    let codigo = "
+        variavel x = 1;
+        funcao f(p) {
+            variavel x = 2;
+            variavel _ignorada = 3;
+            variavel sobra = 4;
+            funcao auxiliar() { }
+            retornar x;
+        }
+        f(0);
+    ";
+    interpreter.eval(codigo).unwrap();
+    let avisos = interpreter.saida_erros_mut().capturado();
+    let linhas: Vec<&str> = avisos.lines().collect();
+    assert_eq!(linhas.len(), 3, "{}", avisos);
+    assert!(linhas[0].contains("linha 4") && linhas[0].contains("esconde"), "{}", avisos);
+    assert!(linhas[1].contains("Variável 'sobra' declarada mas nunca usada"), "{}", avisos);
+    assert!(linhas[2].contains("Função 'auxiliar' declarada mas nunca usada"), "{}", avisos);
+}
+
+#[test]
+fn funcao_ve_declaracao_feita_depois_num_escopo_externo() {
+    let codigo = "
+        {
+            variavel x = 1;
+            {
+                funcao f() { retornar x; }
+                imprimir f();
+                variavel x = 2;
+                imprimir f();
+            }
+        }
+    ";
+    for usar_vm in [false, true] {
+        let mut interpreter = interpretador(usar_vm);
+        interpreter.eval(codigo).unwrap();
+        assert_eq!(interpreter.saida_mut().capturado(), "1\n2\n");
+        let avisos = interpreter.saida_erros_mut().capturado();
+        assert!(avisos.contains("esconde"), "{}", avisos);
+        assert!(!avisos.contains("nunca usada"), "{}", avisos);
+    }
+}
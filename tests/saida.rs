@@ -0,0 +1,63 @@
+use lucas::{Interpreter, Saida};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+fn interpretador(usar_vm: bool) -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_saida(Saida::Buffer(Vec::new()));
+    interpreter.set_saida_erros(Saida::Buffer(Vec::new()));
+    interpreter.set_usar_vm(usar_vm);
+    interpreter
+}
+
+#[test]
+fn erros_de_execucao_vao_so_para_a_saida_de_erros() {
+    for usar_vm in [false, true] {
+        let mut interpreter = interpretador(usar_vm);
+        assert!(interpreter.run("imprimir \"antes\";\nimprimir 1 / 0;\nimprimir \"depois\";").is_err());
+        assert_eq!(interpreter.saida_mut().capturado(), "antes\n");
+        let erros = interpreter.saida_erros_mut().capturado();
+        assert!(erros.contains("Erro de Execução"), "{}", erros);
+        assert!(erros.contains("Divisão por zero"), "{}", erros);
+    }
+}
+
+#[test]
+fn erros_de_analise_e_avisos_vao_para_a_saida_de_erros() {
+    let mut interpreter = interpretador(false);
+    assert!(interpreter.run("imprimir (1;").is_err());
+    assert_eq!(interpreter.saida_mut().capturado(), "");
+    assert!(interpreter.saida_erros_mut().capturado().contains("Erro Sintático"));
+
+    assert_eq!(interpreter.run("funcao f() { variavel sobra = 1; }\nimprimir \"ok\";").unwrap(), 0);
+    assert_eq!(interpreter.saida_mut().capturado(), "ok\n");
+    assert!(interpreter.saida_erros_mut().capturado().contains("'sobra' declarada mas nunca usada"));
+}
+
+#[test]
+fn cada_mensagem_de_erro_chega_inteira() {
+    let trechos = Rc::new(RefCell::new(Vec::new()));
+    let destino = trechos.clone();
+    let mut interpreter = interpretador(false);
+    interpreter.set_saida_erros(Saida::Funcao(Box::new(move |texto| destino.borrow_mut().push(texto.to_string()))));
+    assert!(interpreter.run("variavel x = nulo;\nx.campo;").is_err());
+
+    let trechos = trechos.borrow();
+    assert_eq!(trechos.len(), 1, "{:?}", trechos);
+    assert!(trechos[0].contains("x.campo") && trechos[0].contains("Rastreamento"), "{}", trechos[0]);
+}
+
+#[test]
+fn imprimir_vai_para_a_saida_configurada_nos_dois_jeitos() {
+    for usar_vm in [false, true] {
+        let mut interpreter = interpretador(usar_vm);
+        interpreter.run("imprimir(1, \"dois\", [3]);\nimprimir {\"a\": nulo};").unwrap();
+        assert_eq!(interpreter.saida_mut().capturado(), "1 dois [3]\n{a: nulo}\n");
+        assert_eq!(interpreter.saida_erros_mut().capturado(), "");
+    }
+
+    // Fora de um `Saida::Buffer` não há o que capturar
+    let mut interpreter = Interpreter::new();
+    interpreter.set_saida(Saida::ErroPadrao);
+    assert_eq!(interpreter.saida_mut().capturado(), "");
+}
@@ -0,0 +1,42 @@
+use lucas::{Interpreter, Saida};
+
+/// Saída de `codigo`, que precisa executar sem erros.
+fn saida(codigo: &str) -> String {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_saida(Saida::Buffer(Vec::new()));
+    interpreter.set_saida_erros(Saida::Buffer(Vec::new()));
+    if let Err(erro) = interpreter.eval(codigo) {
+        panic!("{}", erro);
+    }
+    interpreter.saida_mut().capturado()
+}
+
+#[test]
+fn imprimir_com_varios_argumentos_ou_expressao_entre_parenteses() {
+    assert_eq!(saida("imprimir(1, \"dois\", [3]);"), "1 dois [3]\n");
+    assert_eq!(saida("imprimir(1)\nimprimir(2)"), "1\n2\n");
+    assert_eq!(saida("imprimir (5) - 1;"), "4\n");
+    assert_eq!(saida("imprimir (2 + 3) * 2;"), "10\n");
+    assert_eq!(saida("imprimir ({\"a\": 3}).a;"), "3\n");
+    assert_eq!(saida("imprimir ([1, 2])[1];"), "2\n");
+    assert_eq!(saida("imprimir (1) e falso;"), "false\n");
+}
+
+/// Mensagem do erro de análise de `codigo`.
+fn erro(codigo: &str) -> String {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_saida_erros(Saida::Buffer(Vec::new()));
+    match interpreter.eval(codigo) {
+        Ok(_) => panic!("'{}' deveria falhar", codigo),
+        Err(erro) => erro.to_string(),
+    }
+}
+
+#[test]
+fn atribuir_a_algo_que_nao_e_variavel_nem_indice() {
+    for codigo in ["1 = 2;", "f() = 3;", "a + b = 1;"] {
+        let mensagem = erro(codigo);
+        assert!(mensagem.contains("Alvo de atribuição inválido perto de '='"), "{}: {}", codigo, mensagem);
+    }
+    assert_eq!(saida("variavel d = {\"a\": 1}; d.a = 2; d[\"b\"] = 3; imprimir d;"), "{a: 2, b: 3}\n");
+}
@@ -0,0 +1,323 @@
+//! Os mesmos programas percorrendo a árvore e na máquina virtual (`--vm`)
+//! precisam ter a mesma saída, os mesmos erros e o mesmo resultado.
+
+use lucas::{Interpreter, Limites, Saida};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Resultado de `Interpreter::run`, saída e saída de erros.
+#[derive(Debug, PartialEq)]
+struct Execucao {
+    resultado: Result<i32, String>,
+    saida: String,
+    erros: String,
+}
+
+fn executar(codigo: &str, caminho: Option<&Path>, limites: &Limites, usar_vm: bool) -> Execucao {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_saida(Saida::Buffer(Vec::new()));
+    interpreter.set_saida_erros(Saida::Buffer(Vec::new()));
+    interpreter.set_limites(limites.clone());
+    interpreter.set_usar_vm(usar_vm);
+    interpreter.set_caminho(caminho.map(Path::to_path_buf));
+    let resultado = interpreter.run(codigo).map_err(|e| e.mensagem);
+    Execucao {
+        resultado,
+        saida: interpreter.saida_mut().capturado(),
+        erros: interpreter.saida_erros_mut().capturado(),
+    }
+}
+
+/// Executa `codigo` dos dois jeitos, confere que tudo coincide e devolve a execução.
+fn comparar_com(codigo: &str, caminho: Option<&Path>, limites: &Limites) -> Execucao {
+    let arvore = executar(codigo, caminho, limites, false);
+    let vm = executar(codigo, caminho, limites, true);
+    assert_eq!(arvore, vm, "árvore e VM divergem em:\n{}", codigo);
+    arvore
+}
+
+/// Saída de `codigo`, que precisa rodar sem erros e igual nos dois jeitos.
+fn saida(codigo: &str) -> String {
+    let execucao = comparar_com(codigo, None, &Limites::default());
+    assert_eq!(execucao.resultado, Ok(0), "{}", execucao.erros);
+    execucao.saida
+}
+
+/// Mensagem do erro de `codigo`, igual nos dois jeitos.
+fn erro(codigo: &str) -> Execucao {
+    let execucao = comparar_com(codigo, None, &Limites::default());
+    assert!(execucao.resultado.is_err(), "{} deveria falhar", codigo);
+    execucao
+}
+
+#[test]
+fn aritmetica_textos_e_colecoes() {
+    let codigo = r#"
+        imprimir 1 + 2 * 3 - 4 / 2;
+        imprimir "a" + 1 + verdadeiro;
+        imprimir -2 < 3 e nao (1 == 2) ou falso;
+        variavel d = {"um": 1, "dois": [2, 2]};
+        d["tres"] = 3;
+        d.dois[1] = "x";
+        imprimir d;
+        imprimir(chaves(d), comprimento("olá"));
+    "#;
+    assert_eq!(saida(codigo), "5\na1true\ntrue\n{dois: [2, x], tres: 3, um: 1}\n[dois, tres, um] 3\n");
+}
+
+#[test]
+fn closures_compartilham_variaveis_capturadas() {
+    let codigo = r#"
+        funcao contador() {
+            variavel n = 0;
+            funcao incrementar() { n = n + 1; retornar n; }
+            retornar incrementar;
+        }
+        variavel a = contador();
+        variavel b = contador();
+        a(); a();
+        imprimir(a(), b());
+
+        variavel funcoes = {};
+        para (variavel i = 0; i < 3; i = i + 1) {
+            variavel j = i * 10;
+            funcao f() { retornar j; }
+            funcoes["f" + i] = f;
+        }
+        para cada (nome em funcoes) { imprimir funcoes[nome](); }
+    "#;
+    assert_eq!(saida(codigo), "3 1\n0\n10\n20\n");
+}
+
+#[test]
+fn chamadas_finais_nao_acumulam_pilha() {
+    let codigo = r#"
+        funcao conta(n, total) {
+            se (n == 0) { retornar total; }
+            retornar conta(n - 1, total + n);
+        }
+        imprimir conta(100000, 0);
+
+        funcao par(n) { se (n == 0) { retornar verdadeiro; } retornar impar(n - 1); }
+        funcao impar(n) { se (n == 0) { retornar falso; } retornar par(n - 1); }
+        imprimir par(5001);
+    "#;
+    assert_eq!(saida(codigo), "5000050000\nfalse\n");
+}
+
+#[test]
+fn recursao_sem_chamada_final_respeita_o_limite() {
+    let execucao = erro("funcao f(n) { retornar 1 + f(n + 1); }\nf(0);");
+    assert!(execucao.erros.contains("recursão máxima excedida"), "{}", execucao.erros);
+    assert!(execucao.erros.contains("repetida mais"), "{}", execucao.erros);
+}
+
+#[test]
+fn tentar_capturar_finalmente() {
+    let codigo = r#"
+        funcao dividir(a, b) {
+            se (b == 0) { lancar {"tipo": "DivisaoPorZero", "mensagem": "b é zero"}; }
+            retornar a / b;
+        }
+        funcao seguro(a, b) {
+            tentar {
+                retornar dividir(a, b);
+            } capturar (erro) {
+                imprimir("capturado:", erro.tipo, erro.mensagem);
+                retornar nulo;
+            } finalmente {
+                imprimir("finalmente", a, b);
+            }
+        }
+        imprimir seguro(6, 3);
+        imprimir seguro(1, 0);
+
+        para (variavel i = 0; i < 3; i = i + 1) {
+            tentar {
+                se (i == 1) { continuar; }
+                se (i == 2) { parar; }
+                imprimir("corpo", i);
+            } finalmente {
+                imprimir("fim", i);
+            }
+        }
+
+        tentar {
+            tentar { lancar "interno"; } finalmente { imprimir "limpa"; }
+        } capturar (e) {
+            imprimir e.mensagem;
+        }
+        tentar { [1][5]; } capturar (e) { imprimir e.tipo; }
+    "#;
+    let esperado = "finalmente 6 3\n2\ncapturado: DivisaoPorZero b é zero\nfinalmente 1 0\nnulo\n\
+                    corpo 0\nfim 0\nfim 1\nfim 2\nlimpa\ninterno\nIndiceForaDosLimites\n";
+    assert_eq!(saida(codigo), esperado);
+}
+
+#[test]
+fn fatias_intervalos_e_para_cada() {
+    let codigo = r#"
+        variavel a = [10, 20, 30, 40, 50];
+        imprimir(a[1..3], a[..2], a[3..], a[-2..], a[1..=3]);
+        imprimir("lucas"[1..4], "lucas"[-1]);
+        imprimir(intervalo(0, 10, 3), 1..=3);
+        para cada (i em 0..3) { imprimir i; }
+        para cada (letra em "oi") { imprimir letra; }
+        para cada (chave em {"b": 2, "a": 1}) { imprimir chave; }
+        variavel soma = 0;
+        para cada (x em intervalo(10, 0, -2)) { soma = soma + x; }
+        imprimir soma;
+    "#;
+    assert_eq!(
+        saida(codigo),
+        "[20, 30] [10, 20] [40, 50] [40, 50] [20, 30, 40]\nuca s\n0..10 (passo 3) 1..=3\n0\n1\n2\no\ni\na\nb\n30\n"
+    );
+}
+
+#[test]
+fn constantes_e_escopos() {
+    let codigo = r#"
+        constante LIMITE = 3;
+        variavel x = "global";
+        {
+            variavel x = "bloco";
+            imprimir x;
+        }
+        imprimir x;
+        funcao sombra() { variavel LIMITE = 1; retornar LIMITE; }
+        imprimir(sombra(), LIMITE);
+    "#;
+    assert_eq!(saida(codigo), "bloco\nglobal\n1 3\n");
+
+    // Atribuir a uma constante é recusado antes de executar
+    let execucao = erro("imprimir 1;\nconstante LIMITE = 3;\nLIMITE = 4;");
+    assert_eq!(execucao.saida, "");
+    assert!(execucao.erros.contains("constante 'LIMITE'"), "{}", execucao.erros);
+}
+
+#[test]
+fn funcao_e_declaracao_posterior_com_mesmo_nome() {
+    // `f` vê o `x` que existir quando é chamada, nos dois jeitos
+    let codigo = r#"
+        {
+            variavel x = 1;
+            {
+                funcao f() { retornar x; }
+                funcao g() { x = x + 10; }
+                imprimir f();
+                variavel x = 2;
+                imprimir f();
+                g();
+                imprimir x;
+            }
+            imprimir x;
+        }
+        funcao h() {
+            variavel y = "externo";
+            { funcao ler() { retornar y; } variavel y = "interno"; retornar ler(); }
+        }
+        imprimir h();
+    "#;
+    assert_eq!(saida(codigo), "1\n2\n12\n1\ninterno\n");
+}
+
+#[test]
+fn erros_de_execucao_com_rastro() {
+    let codigo = "funcao a(x) {\n  retornar b(x);\n}\nfuncao b(x) {\n  retornar x.campo;\n}\nimprimir \"antes\";\na(1);";
+    let execucao = erro(codigo);
+    assert_eq!(execucao.saida, "antes\n");
+    assert!(execucao.erros.contains("linha 5"), "{}", execucao.erros);
+
+    let execucao = erro("variavel d = {};\nimprimir d.nada;");
+    assert!(execucao.erros.contains("nada"), "{}", execucao.erros);
+    erro("parar;");
+    erro("imprimir 1 + verdadeiro * [];");
+}
+
+#[test]
+fn sair_encerra_com_o_codigo() {
+    let execucao = comparar_com(
+        "funcao f() { tentar { sair(4); } capturar (e) { imprimir \"nunca\"; } }\nimprimir 1;\nf();\nimprimir 2;",
+        None,
+        &Limites::default(),
+    );
+    assert_eq!(execucao.resultado, Ok(4));
+    assert_eq!(execucao.saida, "1\n");
+}
+
+#[test]
+fn modulos_importados_dos_dois_jeitos() {
+    let diretorio = std::env::temp_dir().join(format!("lucas_vm_modulos_{}", std::process::id()));
+    fs::create_dir_all(&diretorio).unwrap();
+    fs::write(
+        diretorio.join("util.lucas"),
+        "imprimir \"carregando util\";\nexportar funcao soma(a, b) { retornar a + b; }\nfuncao privada() { retornar 1; }\nexportar constante PI = 3.14;\n",
+    )
+    .unwrap();
+    fs::write(diretorio.join("ciclo_a.lucas"), "importar \"ciclo_b\";\n").unwrap();
+    fs::write(diretorio.join("ciclo_b.lucas"), "importar \"ciclo_a\";\n").unwrap();
+    let principal: PathBuf = diretorio.join("principal.lucas");
+
+    let codigo = r#"
+        importar "util.lucas" como u;
+        de "util" importar soma, PI;
+        imprimir(u.soma(2, 3), soma(1, PI * 100));
+        imprimir chaves(u);
+        tentar { de "util" importar privada; } capturar (e) { imprimir e.mensagem; }
+        tentar { importar "nada"; } capturar (e) { imprimir e.tipo; }
+        tentar { importar "ciclo_a"; } capturar (e) { imprimir e.mensagem; }
+    "#;
+    let execucao = comparar_com(codigo, Some(&principal), &Limites::default());
+    assert_eq!(execucao.resultado, Ok(0), "{}", execucao.erros);
+    assert_eq!(
+        execucao.saida,
+        "carregando util\n5 315\n[PI, soma]\nO módulo 'util' não exporta 'privada'\nModuloNaoEncontrado\n\
+         Importação circular: principal.lucas → ciclo_a.lucas → ciclo_b.lucas → ciclo_a.lucas\n"
+    );
+    fs::remove_dir_all(diretorio).unwrap();
+}
+
+#[test]
+fn limites_iguais_nos_dois_jeitos() {
+    let limites = Limites { passos: Some(500), ..Limites::default() };
+    let execucao = comparar_com("variavel i = 0;\nenquanto (verdadeiro) { i = i + 1; }", None, &limites);
+    assert!(execucao.erros.contains("500 passos"), "{}", execucao.erros);
+
+    let limites = Limites { tamanho_colecao: Some(3), fatais: true, ..Limites::default() };
+    let codigo = "variavel d = {};\ntentar { para cada (i em 0..5) { d[\"k\" + i] = i; } } capturar (e) { imprimir \"capturado\"; }\nimprimir \"depois\";";
+    let execucao = comparar_com(codigo, None, &limites);
+    assert!(execucao.resultado.is_err());
+    assert_eq!(execucao.saida, "");
+
+    let limites = Limites { bytes_saida: Some(10), ..Limites::default() };
+    let execucao = comparar_com("para cada (i em 0..100) { imprimir i; }", None, &limites);
+    assert_eq!(execucao.saida, "0\n1\n2\n3\n4\n");
+    assert!(execucao.erros.contains("10 bytes"), "{}", execucao.erros);
+}
+
+/// Compara o tempo de `fib(27)` nos dois jeitos; rode com
+/// `cargo test --release --test vm -- --ignored --nocapture`. A VM fica entre
+/// 3,5x e 4x mais rápida que a árvore (longe dos 10x pedidos de início): o que
+/// sobra é, sobretudo, o custo de cada chamada de função.
+#[test]
+#[ignore]
+fn desempenho_fib() {
+    let codigo = "funcao fib(n) { se (n < 2) { retornar n; } retornar fib(n - 1) + fib(n - 2); }\nimprimir fib(27);";
+    // O menor de alguns tempos, para não depender de uma medição ruidosa
+    let medir = |usar_vm| {
+        (0..3)
+            .map(|_| {
+                let inicio = std::time::Instant::now();
+                let execucao = executar(codigo, None, &Limites::default(), usar_vm);
+                assert_eq!(execucao.saida, "196418\n");
+                inicio.elapsed()
+            })
+            .min()
+            .unwrap()
+    };
+    let arvore = medir(false);
+    let vm = medir(true);
+    let razao = arvore.as_secs_f64() / vm.as_secs_f64();
+    println!("árvore: {:?}, vm: {:?} ({:.1}x)", arvore, vm, razao);
+    assert!(razao >= 3.0, "a VM deveria ser ao menos 3x mais rápida, foi {:.1}x", razao);
+}